@@ -313,7 +313,9 @@ fn bench_high_eviction_stress(c: &mut Criterion) {
                 &(small_cache, large_dataset, pf_type),
                 |b, &(small_cache, large_dataset, pf_type)| {
                     b.iter(|| {
-                        let mut cache = create_arc_cache_with_prefetch(small_cache, pf_type);
+                        // A loader makes predictions land in the prefetch buffer
+                        let mut cache = create_arc_cache_with_prefetch(small_cache, pf_type)
+                            .with_loader(Box::new(|key: &i32| Some(format!("stress_{key}"))));
                         for i in 0..large_dataset {
                             let key = match i % 4 {
                                 0 => i as i32,                    // New keys
@@ -326,7 +328,7 @@ fn bench_high_eviction_stress(c: &mut Criterion) {
                             }
                         }
                         let stats = cache.prefetch_stats();
-                        (cache.len(), stats.predictions_made, stats.prefetch_hits)
+                        (cache.len(), stats.predictions_made, stats.cache_hits_from_prefetch)
                     })
                 },
             );
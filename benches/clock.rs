@@ -1,3 +1,5 @@
+mod common;
+
 use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 use fulgurance::prelude::*;
 use fulgurance::policies::{ClockCache};
@@ -48,54 +50,48 @@ fn bench_insert_then_get(c: &mut Criterion) {
 /// Bench: Sequential access pattern
 fn bench_sequential(c: &mut Criterion) {
     let mut group = c.benchmark_group("Clock Sequential Pattern");
-    let configs = vec![(100, 500), (200, 1000), (500, 2000)];
-    for &(cache_size, data_size) in &configs {
-        for &pf_type in &all_prefetch_types() {
-            group.bench_with_input(
-                BenchmarkId::new(pf_type.name(), format!("cache{}_data{}", cache_size, data_size)),
-                &(cache_size, data_size, pf_type),
-                |b, &(cache_size, data_size, pf_type)| {
-                    b.iter(|| {
-                        let mut cache = create_clock_cache_with_prefetch(cache_size, pf_type);
-                        for i in 0..data_size {
-                            let key = i as i32;
-                            if cache.get(&key).is_none() {
-                                cache.insert(key, format!("seq_{key}"));
-                            }
-                        }
-                        cache.len()
-                    })
-                },
-            );
-        }
-    }
+    common::bench_policy(
+        &mut group,
+        &[(100, 500), (200, 1000), (500, 2000)],
+        &all_prefetch_types(),
+        create_clock_cache_with_prefetch,
+        |_, data_size| (0..data_size as i32).collect(),
+        |key| format!("seq_{key}"),
+    );
     group.finish();
 }
 
+/// Random trace over twice as many keys as accesses
+fn random_trace(data_size: usize) -> Vec<usize> {
+    (0..data_size).map(|i| (i * 17) % (data_size * 2)).collect()
+}
+
 /// Bench: Random access pattern
 fn bench_random(c: &mut Criterion) {
     let mut group = c.benchmark_group("Clock Random Pattern");
-    let configs = vec![(100, 500), (200, 1000), (500, 2000)];
-    for &(cache_size, data_size) in &configs {
-        for &pf_type in &all_prefetch_types() {
-            group.bench_with_input(
-                BenchmarkId::new(pf_type.name(), format!("cache{}_data{}", cache_size, data_size)),
-                &(cache_size, data_size, pf_type),
-                |b, &(cache_size, data_size, pf_type)| {
-                    b.iter(|| {
-                        let mut cache = create_clock_cache_with_prefetch(cache_size, pf_type);
-                        for i in 0..data_size {
-                            let key = ((i * 17) % (data_size * 2)) as i32;
-                            if cache.get(&key).is_none() {
-                                cache.insert(key, format!("rand_{key}"));
-                            }
-                        }
-                        cache.len()
-                    })
-                },
-            );
-        }
-    }
+    common::bench_policy(
+        &mut group,
+        &[(100, 500), (200, 1000), (500, 2000)],
+        &all_prefetch_types(),
+        create_clock_cache_with_prefetch,
+        |_, data_size| random_trace(data_size).into_iter().map(|key| key as i32).collect(),
+        |key| format!("rand_{key}"),
+    );
+    group.finish();
+}
+
+/// Bench: Random access pattern on u64 keys, at the same capacities as the
+/// i32 run; prefetching only exists for i32, i64 and usize keys
+fn bench_random_u64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Clock Random Pattern (u64 keys)");
+    common::bench_policy(
+        &mut group,
+        &[(100, 500), (200, 1000), (500, 2000)],
+        &[PrefetchType::None],
+        |capacity, _| ClockCache::<u64, String>::new(capacity),
+        |_, data_size| random_trace(data_size).into_iter().map(|key| key as u64).collect(),
+        |key| format!("rand_{key}"),
+    );
     group.finish();
 }
 
@@ -238,32 +234,19 @@ fn bench_cyclic(c: &mut Criterion) {
 /// Bench: Working set pattern (80/20 rule)
 fn bench_working_set(c: &mut Criterion) {
     let mut group = c.benchmark_group("Clock Working Set Pattern (80/20)");
-    let configs = vec![(100, 500), (200, 1000), (300, 1500)];
-    for &(cache_size, total_accesses) in &configs {
-        for &pf_type in &all_prefetch_types() {
-            group.bench_with_input(
-                BenchmarkId::new(pf_type.name(), format!("cache{}_acc{}", cache_size, total_accesses)),
-                &(cache_size, total_accesses, pf_type),
-                |b, &(cache_size, total_accesses, pf_type)| {
-                    b.iter(|| {
-                        let mut cache = create_clock_cache_with_prefetch(cache_size, pf_type);
-                        let hot_keys = cache_size / 5; // 20% "hot" keys
-                        for i in 0..total_accesses {
-                            let key = if i % 5 < 4 {
-                                (i % hot_keys) as i32  // 80% hot keys
-                            } else {
-                                i as i32               // 20% cold keys
-                            };
-                            if cache.get(&key).is_none() {
-                                cache.insert(key, format!("ws_{key}"));
-                            }
-                        }
-                        cache.len()
-                    })
-                },
-            );
-        }
-    }
+    common::bench_policy(
+        &mut group,
+        &[(100, 500), (200, 1000), (300, 1500)],
+        &all_prefetch_types(),
+        create_clock_cache_with_prefetch,
+        |cache_size, total_accesses| {
+            let hot_keys = cache_size / 5; // 20% "hot" keys
+            (0..total_accesses)
+                .map(|i| (if i % 5 < 4 { i % hot_keys } else { i }) as i32) // 80% hot keys
+                .collect()
+        },
+        |key| format!("ws_{key}"),
+    );
     group.finish();
 }
 
@@ -350,6 +333,7 @@ criterion_group!(
     bench_insert_then_get,
     bench_sequential,
     bench_random,
+    bench_random_u64,
     bench_clock_hand_efficiency,
     bench_reference_bit_effectiveness,
     bench_stride,
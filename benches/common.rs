@@ -0,0 +1,61 @@
+//! Shared bench scaffolding, included with `mod common;`
+//!
+//! Most policy benches replay an access trace read-through style for a few
+//! cache sizes and every prefetch strategy. [`bench_policy`] does that for
+//! any policy and key type, so a bench only supplies a constructor and a
+//! trace generator.
+
+use criterion::measurement::WallTime;
+use criterion::{BenchmarkGroup, BenchmarkId};
+use fulgurance::CachePolicy;
+use fulgurance::prefetch::PrefetchType;
+
+/// Replays `trace` against `cache`: a miss inserts `value_of(key)`
+///
+/// Returns the final length so the work can't be optimized away.
+pub fn replay<K, V, C>(cache: &mut C, trace: &[K], value_of: &impl Fn(&K) -> V) -> usize
+where
+    K: Clone,
+    C: CachePolicy<K, V>,
+{
+    for key in trace {
+        if cache.get(key).is_none() {
+            cache.insert(key.clone(), value_of(key));
+        }
+    }
+    cache.len()
+}
+
+/// Benchmarks a fresh `policy_ctor(capacity, prefetch)` replaying
+/// `trace_gen(capacity, data_size)` for every `(capacity, data_size)` in
+/// `configs` and every prefetch type
+///
+/// Traces are generated once per config, outside the measured loop. Runs
+/// are named after the prefetch type and `cache{capacity}_data{data_size}`.
+pub fn bench_policy<K, V, C>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    configs: &[(usize, usize)],
+    prefetch_types: &[PrefetchType],
+    policy_ctor: impl Fn(usize, PrefetchType) -> C,
+    trace_gen: impl Fn(usize, usize) -> Vec<K>,
+    value_of: impl Fn(&K) -> V,
+) where
+    K: Clone,
+    C: CachePolicy<K, V>,
+{
+    for &(capacity, data_size) in configs {
+        let trace = trace_gen(capacity, data_size);
+        for &pf_type in prefetch_types {
+            group.bench_with_input(
+                BenchmarkId::new(pf_type.name(), format!("cache{}_data{}", capacity, data_size)),
+                &trace,
+                |b, trace| {
+                    b.iter(|| {
+                        let mut cache = policy_ctor(capacity, pf_type);
+                        replay(&mut cache, trace, &value_of)
+                    })
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fulgurance::CachePolicy;
+use fulgurance::policies::{DenseLruCache, LruCache};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Uniform trace over the dense key range `0..data_size`
+fn dense_trace(len: usize, data_size: usize) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..len).map(|_| rng.gen_range(0..data_size)).collect()
+}
+
+/// Read-through replay: a miss inserts the key as its own value
+fn replay(cache: &mut impl CachePolicy<usize, usize>, trace: &[usize]) -> usize {
+    for &key in trace {
+        if cache.get(&key).is_none() {
+            cache.insert(key, key);
+        }
+    }
+    cache.len()
+}
+
+/// Bench: the same read-through replay through hashed and direct-indexed LRU
+fn bench_hashed_vs_dense(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Hashed vs Dense LRU");
+    for &(capacity, data_size) in &[(100, 1_000), (1_000, 10_000), (10_000, 100_000)] {
+        let trace = dense_trace(50_000, data_size);
+        let id = format!("cache{}_data{}", capacity, data_size);
+        group.bench_with_input(BenchmarkId::new("hashed", &id), &trace, |b, trace| {
+            b.iter(|| replay(&mut LruCache::new(capacity), trace))
+        });
+        group.bench_with_input(BenchmarkId::new("dense", &id), &trace, |b, trace| {
+            b.iter(|| replay(&mut DenseLruCache::with_key_bound(capacity, data_size), trace))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hashed_vs_dense);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fulgurance::prelude::*;
+use fulgurance::policies::{create_cache_policy, AnyCache};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Skewed trace: most accesses fall on a small hot set
+fn hot_set_trace(len: usize, keys: i32) -> Vec<i32> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..len)
+        .map(|_| {
+            if rng.gen_bool(0.8) {
+                rng.gen_range(0..keys / 10)
+            } else {
+                rng.gen_range(0..keys)
+            }
+        })
+        .collect()
+}
+
+/// Bench: the same read-through replay through a boxed trait object and
+/// through enum dispatch
+fn bench_boxed_vs_enum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Boxed vs Enum Dispatch");
+    let trace = hot_set_trace(20_000, 2_000);
+    for &policy in &[PolicyType::Lru, PolicyType::Lfu, PolicyType::Arc, PolicyType::Sieve] {
+        group.bench_with_input(BenchmarkId::new("boxed", policy.name()), &policy, |b, &policy| {
+            b.iter(|| {
+                let mut cache = create_cache_policy::<i32, i32>(policy, 500);
+                for &key in &trace {
+                    if cache.get(&key).is_none() {
+                        cache.insert(key, key);
+                    }
+                }
+                cache.len()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("enum", policy.name()), &policy, |b, &policy| {
+            b.iter(|| {
+                let mut cache = AnyCache::<i32, i32>::new(policy, 500);
+                for &key in &trace {
+                    if cache.get(&key).is_none() {
+                        cache.insert(key, key);
+                    }
+                }
+                cache.len()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_boxed_vs_enum);
+criterion_main!(benches);
@@ -0,0 +1,80 @@
+//! The get, load on miss, insert idiom as a single call
+
+use crate::CachePolicy;
+
+/// Hits and misses seen by [`cache_aside`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsideTally {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl AsideTally {
+    /// Fraction of lookups served from the cache, 0 before any lookup
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Returns the cached value of `key`, or loads, inserts and returns it
+///
+/// `loader` runs only on a miss. The lookup is counted in `tally`.
+pub fn cache_aside<K, V, C>(cache: &mut C, key: K, tally: &mut AsideTally, loader: impl FnOnce(&K) -> V) -> V
+where
+    V: Clone,
+    C: CachePolicy<K, V>,
+{
+    let (value, hit) = cache_aside_hit(cache, key, loader);
+    if hit {
+        tally.hits += 1;
+    } else {
+        tally.misses += 1;
+    }
+    value
+}
+
+/// Like [`cache_aside`] without a tally, also telling whether it was a hit
+pub fn cache_aside_hit<K, V, C>(cache: &mut C, key: K, loader: impl FnOnce(&K) -> V) -> (V, bool)
+where
+    V: Clone,
+    C: CachePolicy<K, V>,
+{
+    if let Some(value) = cache.get(&key) {
+        return (value.clone(), true);
+    }
+    let value = loader(&key);
+    cache.insert(key, value.clone());
+    (value, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_cache_aside_loads_each_miss_once() {
+        let mut cache = LruCache::new(8);
+        let mut tally = AsideTally::default();
+        let mut loads = Vec::new();
+        for key in [1, 2, 1, 3, 2, 1, 4] {
+            let value = cache_aside(&mut cache, key, &mut tally, |k| {
+                loads.push(*k);
+                k * 100
+            });
+            assert_eq!(value, key * 100);
+        }
+        assert_eq!(loads, vec![1, 2, 3, 4]);
+        assert_eq!(tally, AsideTally { hits: 3, misses: 4 });
+        assert!((tally.hit_ratio() - 3.0 / 7.0).abs() < 1e-12);
+
+        assert_eq!(cache_aside_hit(&mut cache, 4, |_| unreachable!()), (400, true));
+        assert_eq!(cache_aside_hit(&mut cache, 5, |k| k * 100), (500, false));
+        assert_eq!(cache.get(&5), Some(&500));
+    }
+}
@@ -0,0 +1,203 @@
+use std::marker::PhantomData;
+
+use crate::CachePolicy;
+
+/// Cache wrapper that resizes its policy to reach a target hit ratio
+///
+/// Every `window` lookups the measured hit ratio is compared with the
+/// target: below it the capacity grows by `step` (up to `max_capacity`),
+/// and once it is above `target + shrink_margin` the capacity shrinks by
+/// `step` (down to `min_capacity`) to give memory back. The margin keeps
+/// the capacity from oscillating around the target.
+pub struct AutoTuningCache<K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    inner: C,
+    target_hit_ratio: f64,
+    min_capacity: usize,
+    max_capacity: usize,
+    step: usize,
+    window: usize,
+    shrink_margin: f64,
+    window_hits: usize,
+    window_lookups: usize,
+    last_hit_ratio: Option<f64>,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> AutoTuningCache<K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    /// Wrap a policy, growing it up to `max_capacity` to reach `target_hit_ratio`
+    ///
+    /// Defaults: a window of 1000 lookups, a step of a tenth of the starting
+    /// capacity (at least 1), a shrink margin of 0.1 and the starting
+    /// capacity as the minimum.
+    ///
+    /// # Panics
+    /// Panics if `target_hit_ratio` is not in `(0, 1]` or `max_capacity` is
+    /// below the policy's current capacity
+    pub fn new(inner: C, target_hit_ratio: f64, max_capacity: usize) -> Self {
+        assert!(
+            target_hit_ratio > 0.0 && target_hit_ratio <= 1.0,
+            "target hit ratio must be in (0, 1]"
+        );
+        let capacity = inner.capacity();
+        assert!(max_capacity >= capacity, "max capacity must be at least the current capacity");
+
+        Self {
+            inner,
+            target_hit_ratio,
+            min_capacity: capacity,
+            max_capacity,
+            step: (capacity / 10).max(1),
+            window: 1000,
+            shrink_margin: 0.1,
+            window_hits: 0,
+            window_lookups: 0,
+            last_hit_ratio: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Number of lookups between two tuning decisions
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Capacity change applied per tuning decision
+    pub fn with_step(mut self, step: usize) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    /// Lowest capacity the tuner may shrink to
+    pub fn with_min_capacity(mut self, min_capacity: usize) -> Self {
+        self.min_capacity = min_capacity.clamp(1, self.max_capacity);
+        self
+    }
+
+    /// How far above the target the hit ratio must be before shrinking
+    pub fn with_shrink_margin(mut self, margin: f64) -> Self {
+        self.shrink_margin = margin.max(0.0);
+        self
+    }
+
+    /// Look up a key, counting the hit or miss towards the current window
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.window_lookups >= self.window {
+            self.tune();
+        }
+        let result = self.inner.get(key);
+        self.window_lookups += 1;
+        if result.is_some() {
+            self.window_hits += 1;
+        }
+        result
+    }
+
+    /// Insert or update a key-value pair
+    pub fn insert(&mut self, key: K, value: V) {
+        self.inner.insert(key, value);
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Current capacity chosen by the tuner
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Hit ratio of the last completed window, if any
+    pub fn measured_hit_ratio(&self) -> Option<f64> {
+        self.last_hit_ratio
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Close the current window and resize if the hit ratio is off target
+    fn tune(&mut self) {
+        let ratio = self.window_hits as f64 / self.window_lookups as f64;
+        self.last_hit_ratio = Some(ratio);
+        self.window_hits = 0;
+        self.window_lookups = 0;
+
+        let capacity = self.inner.capacity();
+        if ratio < self.target_hit_ratio && capacity < self.max_capacity {
+            self.inner.resize((capacity + self.step).min(self.max_capacity));
+        } else if ratio > self.target_hit_ratio + self.shrink_margin && capacity > self.min_capacity {
+            self.inner.resize(capacity.saturating_sub(self.step).max(self.min_capacity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_autotune_climbs_to_target_and_stabilizes() {
+        // Uniform accesses over 200 keys: LRU hits about capacity / 200
+        let mut cache = AutoTuningCache::new(LruCache::new(10), 0.5, 400)
+            .with_window(500)
+            .with_step(10)
+            .with_shrink_margin(0.2);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut capacities = Vec::new();
+
+        for round in 0..60_000 {
+            let key: u32 = rng.gen_range(0..200);
+            if cache.get(&key).is_none() {
+                cache.insert(key, key);
+            }
+            if round % 500 == 0 {
+                capacities.push(cache.capacity());
+            }
+        }
+
+        // Climbed from 10 to roughly the size needed for a 50% hit ratio
+        let settled = &capacities[capacities.len() - 40..];
+        assert!(settled.iter().all(|&c| (90..=150).contains(&c)), "{:?}", settled);
+        assert!(cache.measured_hit_ratio().unwrap() >= 0.4);
+    }
+
+    #[test]
+    fn test_autotune_shrinks_when_well_above_target() {
+        let mut cache = AutoTuningCache::new(LruCache::new(100), 0.3, 100)
+            .with_window(100)
+            .with_step(10)
+            .with_min_capacity(20);
+
+        // A tiny hot set hits every time, so capacity is reclaimed
+        for i in 0..2_000 {
+            let key = i % 5;
+            if cache.get(&key).is_none() {
+                cache.insert(key, key);
+            }
+        }
+        assert_eq!(cache.capacity(), 20);
+        assert!(cache.len() <= 20);
+    }
+}
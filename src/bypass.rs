@@ -0,0 +1,249 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::CachePolicy;
+
+/// Cache wrapper that stops admitting new keys while the policy thrashes
+///
+/// Every `window` lookups the wrapper looks back at that window: when the
+/// hit ratio is at most `thrash_ratio` and at least half the lookups were
+/// matched by an insert that evicted something, the workload is churning
+/// through the cache without reuse, as a large scan does. Filling a cache
+/// that still has room never counts. Inserts of new keys are then skipped,
+/// so the resident hot set survives; updates of resident keys still go
+/// through.
+///
+/// While bypassing, skipped keys are remembered in a ghost list as long as
+/// the capacity. A lookup of a remembered key counts as a would-be hit, and
+/// admission resumes once hits plus would-be hits exceed `resume_ratio`
+/// over a window, i.e. once the workload shows reuse again.
+pub struct BypassCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    inner: C,
+    window: usize,
+    thrash_ratio: f64,
+    resume_ratio: f64,
+    window_lookups: usize,
+    window_hits: usize,
+    window_ghost_hits: usize,
+    window_evictions: usize,
+    bypassing: bool,
+    /// Recently skipped keys, oldest first, mirrored in `ghost_set`
+    ghosts: VecDeque<K>,
+    ghost_set: HashSet<K>,
+    _phantom: PhantomData<V>,
+}
+
+impl<K, V, C> BypassCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    /// Wrap a policy with scan detection
+    ///
+    /// Defaults: a window of 32 lookups, a thrash ratio of 0.05 and a
+    /// resume ratio of 0.2.
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            window: 32,
+            thrash_ratio: 0.05,
+            resume_ratio: 0.2,
+            window_lookups: 0,
+            window_hits: 0,
+            window_ghost_hits: 0,
+            window_evictions: 0,
+            bypassing: false,
+            ghosts: VecDeque::new(),
+            ghost_set: HashSet::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Number of lookups between two thrashing decisions
+    ///
+    /// Keep it well below the capacity: a scan evicts up to a window's
+    /// worth of entries before it is detected.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Hit ratio at or below which a churning window counts as thrashing,
+    /// and the ratio of hits plus would-be hits above which admission resumes
+    ///
+    /// # Panics
+    /// Panics if `thrash_ratio` is above `resume_ratio`
+    pub fn with_thresholds(mut self, thrash_ratio: f64, resume_ratio: f64) -> Self {
+        assert!(thrash_ratio <= resume_ratio, "thrash ratio must not exceed the resume ratio");
+        self.thrash_ratio = thrash_ratio;
+        self.resume_ratio = resume_ratio;
+        self
+    }
+
+    /// Look up a key, counting the hit or miss towards the current window
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.window_lookups >= self.window {
+            self.evaluate();
+        }
+        let result = self.inner.get(key);
+        self.window_lookups += 1;
+        if result.is_some() {
+            self.window_hits += 1;
+        } else if self.ghost_set.contains(key) {
+            self.window_ghost_hits += 1;
+        }
+        result
+    }
+
+    /// Insert or update a key-value pair
+    ///
+    /// While bypassing, a key that isn't resident is not inserted.
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.inner.contains_key(&key) {
+            self.inner.insert(key, value);
+            return;
+        }
+        if self.bypassing {
+            self.remember_ghost(key);
+            return;
+        }
+        if self.inner.len() >= self.inner.capacity() {
+            self.window_evictions += 1;
+        }
+        self.inner.insert(key, value);
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Whether inserts of new keys are currently being skipped
+    pub fn is_bypassing(&self) -> bool {
+        self.bypassing
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Close the current window and switch bypass on or off
+    fn evaluate(&mut self) {
+        let lookups = self.window_lookups as f64;
+        if self.bypassing {
+            let reuse = (self.window_hits + self.window_ghost_hits) as f64 / lookups;
+            if reuse > self.resume_ratio {
+                self.bypassing = false;
+                self.ghosts.clear();
+                self.ghost_set.clear();
+            }
+        } else {
+            let hit_ratio = self.window_hits as f64 / lookups;
+            if hit_ratio <= self.thrash_ratio && self.window_evictions * 2 >= self.window_lookups {
+                self.bypassing = true;
+            }
+        }
+        self.window_lookups = 0;
+        self.window_hits = 0;
+        self.window_ghost_hits = 0;
+        self.window_evictions = 0;
+    }
+
+    /// Records a skipped key, forgetting the oldest beyond the capacity
+    fn remember_ghost(&mut self, key: K) {
+        if !self.ghost_set.insert(key.clone()) {
+            return;
+        }
+        self.ghosts.push_back(key);
+        while self.ghosts.len() > self.inner.capacity() {
+            if let Some(old) = self.ghosts.pop_front() {
+                self.ghost_set.remove(&old);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    fn read_through<C: CachePolicy<u32, u32>>(cache: &mut BypassCache<u32, u32, C>, key: u32) {
+        if cache.get(&key).is_none() {
+            cache.insert(key, key);
+        }
+    }
+
+    #[test]
+    fn test_bypass_scan_preserves_hot_set() {
+        let mut cache = BypassCache::new(LruCache::new(100)).with_window(20);
+        // Filling a cold cache misses without evicting, so it isn't thrashing
+        for key in 100..190 {
+            read_through(&mut cache, key);
+        }
+        for _ in 0..50 {
+            for key in 0..10 {
+                read_through(&mut cache, key);
+            }
+        }
+        assert!(!cache.is_bypassing());
+
+        // A one-shot scan far larger than the cache
+        for key in 1_000..11_000 {
+            read_through(&mut cache, key);
+        }
+        // Detected within two windows, while the scan was still evicting
+        // the cold filler
+        assert!(cache.is_bypassing());
+        for key in 0..10 {
+            assert!(cache.inner().contains_key(&key), "hot key {} was churned out", key);
+        }
+
+        // Once the hot set is read again the workload calms and admission resumes
+        for _ in 0..10 {
+            for key in 0..10 {
+                read_through(&mut cache, key);
+            }
+        }
+        assert!(!cache.is_bypassing());
+    }
+
+    #[test]
+    fn test_bypass_resumes_on_new_working_set() {
+        let mut cache = BypassCache::new(LruCache::new(50)).with_window(10);
+        for key in 0..1_000 {
+            read_through(&mut cache, key);
+        }
+        assert!(cache.is_bypassing());
+
+        // A new working set, never cached, is noticed through the ghost list
+        for _ in 0..20 {
+            for key in 5_000..5_020 {
+                read_through(&mut cache, key);
+            }
+        }
+        assert!(!cache.is_bypassing());
+        assert!(cache.inner().contains_key(&5_000));
+    }
+}
@@ -0,0 +1,226 @@
+//! Recommended entry point: one owned cache type for every policy
+
+use std::hash::Hash;
+
+use crate::policies::{AnyCache, BenchmarkablePolicy, PolicyType};
+use crate::prefetch::NoPrefetch;
+use crate::{Access, CachePolicy, CacheStats, PrefetchStrategy};
+
+/// Cache with a runtime-chosen policy behind a small, stable API
+///
+/// The recommended way to use the crate. The policy structs under
+/// [`policies`](crate::policies) expose their internals for tuning and
+/// research; `Cache` offers only safe owned operations and counts hits,
+/// misses and evictions, so switching policy is a builder setting rather
+/// than a type change.
+///
+/// ```
+/// use fulgurance::prelude::*;
+///
+/// let mut cache = Cache::builder().policy(PolicyType::Arc).capacity(2).build();
+/// cache.insert(1, "one");
+/// assert_eq!(cache.get(&1), Some(&"one"));
+/// assert_eq!(cache.stats().hits, 1);
+/// ```
+pub struct Cache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    inner: AnyCache<K, V>,
+    stats: CacheStats,
+}
+
+/// Configures a [`Cache`]; defaults to LRU with capacity 100 and no prefetch
+pub struct CacheBuilder<K, V> {
+    policy: PolicyType,
+    capacity: usize,
+    prefetch: Option<Box<dyn PrefetchStrategy<K>>>,
+    _phantom: std::marker::PhantomData<V>,
+}
+
+impl<K, V> CacheBuilder<K, V>
+where
+    K: Hash + Eq + Clone + 'static,
+    V: Clone,
+{
+    /// Eviction policy to use
+    pub fn policy(mut self, policy: PolicyType) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Prefetch strategy the policy runs on every hit
+    pub fn prefetch(mut self, strategy: Box<dyn PrefetchStrategy<K>>) -> Self {
+        self.prefetch = Some(strategy);
+        self
+    }
+
+    /// Creates the cache
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn build(self) -> Cache<K, V> {
+        let prefetch = self.prefetch.unwrap_or_else(|| Box::new(NoPrefetch));
+        Cache {
+            inner: AnyCache::with_custom_prefetch(self.policy, self.capacity, prefetch),
+            stats: CacheStats::default(),
+        }
+    }
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Hash + Eq + Clone + 'static,
+    V: Clone,
+{
+    /// Starts configuring a cache
+    pub fn builder() -> CacheBuilder<K, V> {
+        CacheBuilder {
+            policy: PolicyType::Lru,
+            capacity: 100,
+            prefetch: None,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Look up a key, updating the policy's usage state and the stats
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.stats.total_accesses += 1;
+        let (access, value) = self.inner.access(key);
+        match access {
+            Access::MainHit => self.stats.hits += 1,
+            Access::PrefetchHit => {
+                self.stats.hits += 1;
+                self.stats.prefetch_hits += 1;
+            }
+            Access::Miss => self.stats.misses += 1,
+        }
+        value
+    }
+
+    /// Insert or update a value, evicting per the policy if full
+    pub fn insert(&mut self, key: K, value: V) {
+        let fresh = !self.inner.contains_key(&key);
+        let before = self.inner.len();
+        self.inner.insert(key, value);
+        if fresh && self.inner.len() <= before {
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    /// Check residency without affecting eviction order
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Change the capacity, evicting per the policy if shrinking
+    ///
+    /// Entries dropped by a shrink count as evictions.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn resize(&mut self, capacity: usize) {
+        let before = self.inner.len();
+        self.inner.resize(capacity);
+        self.stats.evictions += before.saturating_sub(self.inner.len()) as u64;
+    }
+
+    /// Remove all entries; the stats are kept
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// The policy this cache was built with
+    pub fn policy(&self) -> PolicyType {
+        self.inner.policy_type()
+    }
+
+    /// Hits, misses and evictions so far
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    /// Reset the stats to zero
+    pub fn reset_stats(&mut self) {
+        self.stats.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prefetch::SequentialPrefetch;
+
+    #[test]
+    fn test_cache_builder_drives_each_policy() {
+        for policy in [PolicyType::Lru, PolicyType::Lfu, PolicyType::Arc, PolicyType::Sieve, PolicyType::TwoQ] {
+            let mut cache = Cache::builder().policy(policy).capacity(64).build();
+            assert_eq!(cache.policy(), policy);
+            assert_eq!(cache.capacity(), 64);
+
+            for key in 0..64 {
+                cache.insert(key, key * 2);
+            }
+            assert_eq!(cache.get(&63), Some(&126), "{:?}", policy);
+            assert_eq!(cache.get(&1000), None);
+            assert_eq!(cache.remove(&63), Some(126));
+            assert!(!cache.contains_key(&63));
+
+            for key in 100..200 {
+                cache.insert(key, key);
+            }
+            assert!(cache.len() <= 64, "{:?} holds {}", policy, cache.len());
+            let stats = cache.stats();
+            assert_eq!((stats.hits, stats.misses, stats.total_accesses), (1, 1, 2));
+            assert!(stats.evictions > 0);
+
+            let (held, evicted) = (cache.len() as u64, stats.evictions);
+            cache.resize(8);
+            assert_eq!(cache.stats().evictions, evicted + held - cache.len() as u64, "{:?}", policy);
+
+            cache.reset_stats();
+            assert_eq!(cache.stats().total_accesses, 0);
+        }
+    }
+
+    #[test]
+    fn test_cache_builder_defaults_and_prefetch() {
+        let cache: Cache<i32, i32> = Cache::builder().build();
+        assert_eq!((cache.policy(), cache.capacity()), (PolicyType::Lru, 100));
+
+        let mut cache = Cache::builder()
+            .capacity(16)
+            .prefetch(Box::new(SequentialPrefetch::<i32>::new()))
+            .build();
+        cache.insert(1, "one");
+        assert_eq!(cache.get(&1), Some(&"one"));
+        assert_eq!(cache.stats().hits, 1);
+    }
+}
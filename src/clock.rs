@@ -0,0 +1,65 @@
+//! Time sources for time-dependent caches
+//!
+//! Types that expire or age entries read the time through a [`Clock`] so
+//! tests can substitute a [`MockClock`] and step time deterministically.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Monotonic time source
+pub trait Clock: Send + Sync {
+    /// Current instant; never goes backwards
+    fn now(&self) -> Instant;
+}
+
+/// Real time, through [`Instant::now`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Manually advanced clock for tests
+///
+/// Time stands still until [`advance`](Self::advance) is called. Share it
+/// through an `Arc` to keep a handle after passing it to a cache.
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    elapsed_nanos: AtomicU64,
+}
+
+impl MockClock {
+    /// Creates a clock frozen at the current instant
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Moves time forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_nanos.fetch_add(by.as_nanos() as u64, Ordering::AcqRel);
+    }
+
+    /// Time advanced since creation
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Acquire))
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
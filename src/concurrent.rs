@@ -0,0 +1,315 @@
+//! Thread-safe handles sharing caches between threads
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::CachePolicy;
+
+/// Cloneable, thread-safe handle to a cache behind a single `Mutex`
+///
+/// Every call, reads included, takes the lock: a hit still updates the
+/// policy's recency or frequency state, so readers can't share it. Under
+/// heavy contention the threads serialize on that one lock; when reads
+/// dominate and staleness is acceptable, hand out a
+/// [`FrozenCache`](crate::frozen::FrozenCache) instead.
+///
+/// Values are cloned out, since a reference can't outlive the lock.
+/// Clones of the handle share the same cache.
+pub struct SharedCache<C> {
+    inner: Arc<Mutex<C>>,
+}
+
+impl<C> SharedCache<C> {
+    /// Wrap a cache for sharing across threads
+    pub fn new(cache: C) -> Self {
+        Self { inner: Arc::new(Mutex::new(cache)) }
+    }
+
+    /// Locks the cache, panicking if another thread panicked while holding it
+    fn lock(&self) -> MutexGuard<'_, C> {
+        self.inner.lock().expect("shared cache lock poisoned")
+    }
+
+    /// Retrieve a clone of the value, updating the policy's usage state
+    pub fn get<K, V>(&self, key: &K) -> Option<V>
+    where
+        C: CachePolicy<K, V>,
+        V: Clone,
+    {
+        self.lock().get(key).cloned()
+    }
+
+    /// Insert or update a key-value pair; may evict items if at capacity
+    pub fn insert<K, V>(&self, key: K, value: V)
+    where
+        C: CachePolicy<K, V>,
+    {
+        self.lock().insert(key, value);
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove<K, V>(&self, key: &K) -> Option<V>
+    where
+        C: CachePolicy<K, V>,
+    {
+        self.lock().remove(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len<K, V>(&self) -> usize
+    where
+        C: CachePolicy<K, V>,
+    {
+        self.lock().len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty<K, V>(&self) -> bool
+    where
+        C: CachePolicy<K, V>,
+    {
+        self.lock().is_empty()
+    }
+
+    /// Remove all entries
+    pub fn clear<K, V>(&self)
+    where
+        C: CachePolicy<K, V>,
+    {
+        self.lock().clear();
+    }
+}
+
+impl<C> Clone for SharedCache<C> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+/// Cloneable, thread-safe cache split into independently locked shards
+///
+/// Each key hashes to one shard, so threads working on keys in different
+/// shards don't wait on each other. The price is that eviction is per
+/// shard: each shard evicts within its own capacity, and a skewed key
+/// distribution can evict from a full shard while others have room.
+///
+/// Clones of the handle share the same shards.
+pub struct ShardedCache<C> {
+    inner: Arc<Shards<C>>,
+}
+
+struct Shards<C> {
+    shards: Vec<Mutex<C>>,
+    hasher: RandomState,
+}
+
+impl<C> ShardedCache<C> {
+    /// Builds `shard_count` shards, calling `make_cache` with each shard index
+    ///
+    /// For a total capacity `n`, each shard would typically be a cache of
+    /// capacity `n / shard_count`.
+    ///
+    /// # Panics
+    /// Panics if `shard_count` is 0
+    pub fn with_shards(shard_count: usize, make_cache: impl Fn(usize) -> C) -> Self {
+        assert!(shard_count > 0, "sharded cache needs at least one shard");
+        let shards = (0..shard_count).map(|index| Mutex::new(make_cache(index))).collect();
+        Self { inner: Arc::new(Shards { shards, hasher: RandomState::new() }) }
+    }
+
+    /// Number of shards
+    pub fn shard_count(&self) -> usize {
+        self.inner.shards.len()
+    }
+
+    /// Index of the shard holding `key`, fixed for the life of the cache
+    pub fn shard_of<K: Hash>(&self, key: &K) -> usize {
+        (self.inner.hasher.hash_one(key) % self.inner.shards.len() as u64) as usize
+    }
+
+    /// Locks one shard, panicking if another thread panicked while holding it
+    fn lock(&self, index: usize) -> MutexGuard<'_, C> {
+        self.inner.shards[index].lock().expect("cache shard lock poisoned")
+    }
+
+    /// Retrieve a clone of the value, updating its shard's usage state
+    pub fn get<K, V>(&self, key: &K) -> Option<V>
+    where
+        C: CachePolicy<K, V>,
+        K: Hash,
+        V: Clone,
+    {
+        self.lock(self.shard_of(key)).get(key).cloned()
+    }
+
+    /// Insert or update a key-value pair; may evict from the key's shard
+    pub fn insert<K, V>(&self, key: K, value: V)
+    where
+        C: CachePolicy<K, V>,
+        K: Hash,
+    {
+        self.lock(self.shard_of(&key)).insert(key, value);
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove<K, V>(&self, key: &K) -> Option<V>
+    where
+        C: CachePolicy<K, V>,
+        K: Hash,
+    {
+        self.lock(self.shard_of(key)).remove(key)
+    }
+
+    /// Number of entries across all shards
+    ///
+    /// Shards are counted one at a time, so under concurrent writes the
+    /// sum need not match any single moment.
+    pub fn len<K, V>(&self) -> usize
+    where
+        C: CachePolicy<K, V>,
+    {
+        (0..self.shard_count()).map(|index| self.lock(index).len()).sum()
+    }
+
+    /// Check if every shard is empty
+    pub fn is_empty<K, V>(&self) -> bool
+    where
+        C: CachePolicy<K, V>,
+    {
+        (0..self.shard_count()).all(|index| self.lock(index).is_empty())
+    }
+
+    /// Remove all entries from every shard
+    pub fn clear<K, V>(&self)
+    where
+        C: CachePolicy<K, V>,
+    {
+        for index in 0..self.shard_count() {
+            self.lock(index).clear();
+        }
+    }
+}
+
+impl<C> Clone for ShardedCache<C> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+    use std::thread;
+
+    #[test]
+    fn test_shared_cache_survives_concurrent_hammering() {
+        let shared = SharedCache::new(LruCache::new(64));
+        let workers: Vec<_> = (0..8)
+            .map(|t| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for i in 0..2_000 {
+                        let key = (t * 37 + i) % 200;
+                        match shared.get(&key) {
+                            Some(value) => assert_eq!(value, key * 10),
+                            None => shared.insert(key, key * 10),
+                        }
+                        if i % 50 == 0 {
+                            shared.remove(&((key + 1) % 200));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let len = shared.len();
+        assert!(len > 0 && len <= 64, "len {}", len);
+        let resident = (0..200).filter(|key| shared.get(key).is_some()).count();
+        assert_eq!(resident, len);
+
+        shared.clear();
+        assert!(shared.is_empty());
+    }
+
+    #[test]
+    fn test_sharded_cache_routes_each_key_to_one_shard() {
+        let sharded = ShardedCache::with_shards(4, |_| LruCache::new(25));
+        assert_eq!(sharded.shard_count(), 4);
+        for key in 0..100 {
+            sharded.insert(key, key.to_string());
+        }
+        for key in 0..100 {
+            let shard = sharded.shard_of(&key);
+            assert_eq!(sharded.shard_of(&key), shard);
+            let present = sharded.get(&key).is_some();
+            for index in 0..4 {
+                let resident = sharded.lock(index).contains_key(&key);
+                assert_eq!(resident, present && index == shard, "key {}", key);
+            }
+        }
+        let total: usize = (0..4).map(|index| sharded.lock(index).len()).sum();
+        assert_eq!(sharded.len(), total);
+        assert!(total <= 100);
+
+        sharded.insert(7, "seven".to_string());
+        assert_eq!(sharded.remove(&7), Some("seven".to_string()));
+        assert_eq!(sharded.get(&7), None);
+        sharded.clear();
+        assert!(sharded.is_empty());
+    }
+
+    #[test]
+    fn test_sharded_cache_inserts_past_a_held_shard() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let sharded = ShardedCache::with_shards(8, |_| LruCache::new(64));
+        let held = sharded.shard_of(&0);
+        let guard = sharded.lock(held);
+
+        // A writer on the other shards finishes while one shard stays locked
+        let writer = {
+            let sharded = sharded.clone();
+            let (done_tx, done_rx) = mpsc::channel();
+            let handle = thread::spawn(move || {
+                let mut written = 0;
+                for key in 1..1_000 {
+                    if sharded.shard_of(&key) != held {
+                        sharded.insert(key, key);
+                        written += 1;
+                    }
+                }
+                done_tx.send(written).unwrap();
+            });
+            (handle, done_rx)
+        };
+        let written = writer.1.recv_timeout(Duration::from_secs(10));
+        drop(guard);
+        writer.0.join().unwrap();
+        assert!(written.is_ok(), "writer blocked behind an unrelated shard");
+        assert!(written.unwrap() > 0);
+
+        // Many writers across all shards leave a consistent total
+        let workers: Vec<_> = (0..8)
+            .map(|t| {
+                let sharded = sharded.clone();
+                thread::spawn(move || {
+                    for key in t * 1_000..t * 1_000 + 500 {
+                        sharded.insert(key, key);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        let len = sharded.len();
+        assert!(len <= 8 * 64);
+        assert_eq!((0..8_000).filter(|key| sharded.get(key).is_some()).count(), len);
+    }
+}
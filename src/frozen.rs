@@ -0,0 +1,90 @@
+//! Immutable cache snapshots for lock-free concurrent reads
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// Read-only snapshot of a cache's contents, from [`CachePolicy::freeze`](crate::CachePolicy::freeze)
+///
+/// Cloning is cheap and shares the same map, so a snapshot can be handed
+/// to any number of threads. Lookups take `&self`: there is no lock and
+/// no eviction state to update.
+#[derive(Debug)]
+pub struct FrozenCache<K, V> {
+    map: Arc<HashMap<K, V>>,
+}
+
+impl<K, V> FrozenCache<K, V>
+where
+    K: Hash + Eq,
+{
+    pub(crate) fn new(map: HashMap<K, V>) -> Self {
+        Self { map: Arc::new(map) }
+    }
+
+    /// Look up a key as of the moment the snapshot was taken
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Check whether a key was resident when the snapshot was taken
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Number of entries in the snapshot
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check if the snapshot is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates the entries in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+}
+
+impl<K, V> Clone for FrozenCache<K, V> {
+    fn clone(&self) -> Self {
+        Self { map: Arc::clone(&self.map) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CachePolicy;
+    use crate::policies::LruCache;
+    use std::thread;
+
+    #[test]
+    fn test_frozen_snapshot_serves_concurrent_readers() {
+        let mut cache = LruCache::new(100);
+        for key in 0..100 {
+            cache.insert(key, key * 2);
+        }
+        let frozen = cache.freeze();
+
+        // The live cache moves on without affecting the snapshot
+        cache.insert(500, 0);
+        cache.remove(&7);
+        assert_eq!(cache.len(), 99);
+        assert!(!cache.contains_key(&0));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let frozen = frozen.clone();
+                thread::spawn(move || (0..100).all(|key| frozen.get(&key) == Some(&(key * 2))))
+            })
+            .collect();
+        for reader in readers {
+            assert!(reader.join().unwrap());
+        }
+        assert_eq!(frozen.len(), 100);
+        assert!(frozen.contains_key(&7));
+        assert!(!frozen.contains_key(&500));
+    }
+}
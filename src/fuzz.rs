@@ -0,0 +1,283 @@
+//! Operation sequences and an oracle for property-based testing of policies
+//!
+//! [`CacheOp`] describes one call against a [`CachePolicy`], and
+//! [`ModelCache`] is a `HashMap` oracle that only knows which keys a policy
+//! *may* hold: eviction choices are the policy's own, but it must never
+//! hold a key the model lacks, a stale value, or more than its capacity.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::CachePolicy;
+
+/// A single call against a cache policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheOp<K, V> {
+    Get(K),
+    Insert(K, V),
+    Remove(K),
+    Clear,
+}
+
+impl<K, V> CacheOp<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    /// Performs the operation on `cache`, discarding any result
+    pub fn apply<C: CachePolicy<K, V>>(&self, cache: &mut C) {
+        match self {
+            CacheOp::Get(key) => {
+                cache.get(key);
+            }
+            CacheOp::Insert(key, value) => cache.insert(key.clone(), value.clone()),
+            CacheOp::Remove(key) => {
+                cache.remove(key);
+            }
+            CacheOp::Clear => cache.clear(),
+        }
+    }
+}
+
+/// Performs every operation on `cache` in order
+pub fn apply_ops<K, V, C>(cache: &mut C, ops: &[CacheOp<K, V>])
+where
+    K: Clone,
+    V: Clone,
+    C: CachePolicy<K, V>,
+{
+    for op in ops {
+        op.apply(cache);
+    }
+}
+
+/// Reference model tracking the latest value of every key a policy may hold
+#[derive(Debug, Clone)]
+pub struct ModelCache<K, V> {
+    entries: HashMap<K, V>,
+}
+
+impl<K, V> ModelCache<K, V>
+where
+    K: Hash + Eq + Clone + Debug,
+    V: Clone + PartialEq + Debug,
+{
+    /// Creates an empty model
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Performs `op` on both the model and `cache`, then checks that they agree
+    ///
+    /// Per operation: a `get` hits exactly when `contains_key` said so and
+    /// returns the latest value, a freshly inserted key is resident, a
+    /// removed key is gone and `clear` empties the cache. After every
+    /// operation, [`check`](Self::check) runs as well.
+    pub fn step<C: CachePolicy<K, V>>(&mut self, cache: &mut C, op: &CacheOp<K, V>) -> Result<(), String> {
+        match op {
+            CacheOp::Get(key) => {
+                let resident = cache.contains_key(key);
+                let got = cache.get(key).cloned();
+                if resident != got.is_some() {
+                    return Err(format!("contains_key({:?}) is {} but get returned {:?}", key, resident, got));
+                }
+                if got.is_some() && got.as_ref() != self.entries.get(key) {
+                    return Err(format!("get({:?}) returned stale value {:?}", key, got));
+                }
+            }
+            CacheOp::Insert(key, value) => {
+                cache.insert(key.clone(), value.clone());
+                self.entries.insert(key.clone(), value.clone());
+                if !cache.contains_key(key) {
+                    return Err(format!("{:?} is not resident right after its insert", key));
+                }
+            }
+            CacheOp::Remove(key) => {
+                let removed = cache.remove(key);
+                let expected = self.entries.remove(key);
+                if removed.is_some() && removed != expected {
+                    return Err(format!("remove({:?}) returned {:?}, expected {:?}", key, removed, expected));
+                }
+                if cache.contains_key(key) {
+                    return Err(format!("{:?} is still resident after remove", key));
+                }
+            }
+            CacheOp::Clear => {
+                cache.clear();
+                self.entries.clear();
+                if !cache.is_empty() {
+                    return Err(format!("{} entries left after clear", cache.len()));
+                }
+            }
+        }
+        self.check(cache)
+    }
+
+    /// Checks that `cache` respects its capacity and holds only keys the
+    /// model knows, each with its latest value
+    ///
+    /// Values are read through `for_each_value_mut`, so eviction order is
+    /// not disturbed.
+    pub fn check<C: CachePolicy<K, V>>(&self, cache: &mut C) -> Result<(), String> {
+        if cache.len() > cache.capacity() {
+            return Err(format!("len {} exceeds capacity {}", cache.len(), cache.capacity()));
+        }
+        let mut visited = 0;
+        let mut mismatch = None;
+        cache.for_each_value_mut(&mut |key, value| {
+            visited += 1;
+            if mismatch.is_none() && self.entries.get(key) != Some(value) {
+                mismatch = Some(format!(
+                    "resident {:?} = {:?} but the model holds {:?}",
+                    key,
+                    value,
+                    self.entries.get(key)
+                ));
+            }
+        });
+        if let Some(msg) = mismatch {
+            return Err(msg);
+        }
+        if visited != cache.len() {
+            return Err(format!("len is {} but {} entries were visited", cache.len(), visited));
+        }
+        Ok(())
+    }
+}
+
+impl<K, V> Default for ModelCache<K, V>
+where
+    K: Hash + Eq + Clone + Debug,
+    V: Clone + PartialEq + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn op_strategy() -> impl Strategy<Value = CacheOp<u8, u16>> {
+        prop_oneof![
+            8 => (0u8..24).prop_map(CacheOp::Get),
+            8 => (0u8..24, any::<u16>()).prop_map(|(key, value)| CacheOp::Insert(key, value)),
+            3 => (0u8..24).prop_map(CacheOp::Remove),
+            1 => Just(CacheOp::Clear),
+        ]
+    }
+
+    /// Steps `cache` and the model through `ops`, checking the policy's own
+    /// invariants after each one
+    fn run_against_model<C>(
+        mut cache: C,
+        check_invariants: impl Fn(&C) -> Result<(), String>,
+        ops: &[CacheOp<u8, u16>],
+    ) -> Result<(), TestCaseError>
+    where
+        C: CachePolicy<u8, u16>,
+    {
+        let mut model = ModelCache::new();
+        for (step, op) in ops.iter().enumerate() {
+            let checked = model.step(&mut cache, op).and_then(|_| check_invariants(&cache));
+            if let Err(msg) = checked {
+                return Err(TestCaseError::fail(format!("step {} ({:?}): {}", step, op, msg)));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzz_apply_ops_replays_sequence() {
+        let ops = vec![
+            CacheOp::Insert(1, 10),
+            CacheOp::Insert(2, 20),
+            CacheOp::Get(1),
+            CacheOp::Insert(3, 30),
+            CacheOp::Remove(3),
+        ];
+        let mut cache = LruCache::new(2);
+        apply_ops(&mut cache, &ops);
+        // Reading 1 made 2 the eviction victim for 3
+        assert!(cache.contains_key(&1));
+        assert!(!cache.contains_key(&2));
+        assert_eq!(cache.len(), 1);
+
+        apply_ops(&mut cache, &[CacheOp::Clear]);
+        assert!(cache.is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn test_fuzz_lru_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(LruCache::new(8), LruCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_mru_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(MruCache::new(8), MruCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_fifo_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(FifoCache::new(8), FifoCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_lfu_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(LfuCache::new(8), LfuCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_random_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(RandomCache::new(8), RandomCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_clock_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(ClockCache::new(8), ClockCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_two_q_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(TwoQCache::new(8), TwoQCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_arc_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(ArcCache::new(8), ArcCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_slru_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(SlruCache::new(8), SlruCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_sieve_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(SieveCache::new(8), SieveCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_lirs_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(LirsCache::new(8), LirsCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_s3_fifo_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(S3FifoCache::new(8), S3FifoCache::check_invariants, &ops)?;
+        }
+
+        #[test]
+        fn test_fuzz_car_matches_model(ops in vec(op_strategy(), 0..200)) {
+            run_against_model(CarCache::new(8), CarCache::check_invariants, &ops)?;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
+
+use crate::CachePolicy;
+
+/// Cache wrapper sharing one allocation among equal values
+///
+/// Values are stored as `Arc<V>` and interned: inserting a value equal to
+/// one already cached under another key reuses that `Arc` instead of
+/// keeping a second copy. The interning table only holds `Weak` handles, so
+/// once the last key referencing a value is evicted or removed (and no
+/// caller still holds it) the value is dropped.
+pub struct InterningCache<K, V, C>
+where
+    C: CachePolicy<K, Arc<V>>,
+{
+    inner: C,
+    /// Interned values bucketed by hash
+    table: HashMap<u64, Vec<Weak<V>>>,
+    hasher: RandomState,
+    /// Handles in `table`, live or dead
+    interned: usize,
+    /// Handle count past which dead handles are swept
+    sweep_at: usize,
+    _phantom: PhantomData<K>,
+}
+
+impl<K, V, C> InterningCache<K, V, C>
+where
+    V: Hash + Eq,
+    C: CachePolicy<K, Arc<V>>,
+{
+    /// Wrap a policy storing `Arc<V>` values
+    pub fn new(inner: C) -> Self {
+        let sweep_at = inner.capacity().saturating_mul(2);
+        Self {
+            inner,
+            table: HashMap::new(),
+            hasher: RandomState::new(),
+            interned: 0,
+            sweep_at,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Retrieve a shared handle to the value, updating the policy's usage state
+    pub fn get(&mut self, key: &K) -> Option<Arc<V>> {
+        self.inner.get(key).cloned()
+    }
+
+    /// Insert a value, sharing the allocation of an equal value if one is live
+    pub fn insert(&mut self, key: K, value: V) {
+        let value = self.intern(value);
+        self.inner.insert(key, value);
+    }
+
+    /// Remove a key, returning its shared value if present
+    pub fn remove(&mut self, key: &K) -> Option<Arc<V>> {
+        self.inner.remove(key)
+    }
+
+    /// Number of distinct values still alive in the interning table
+    pub fn distinct_values(&self) -> usize {
+        self.table
+            .values()
+            .flatten()
+            .filter(|weak| weak.strong_count() > 0)
+            .count()
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.table.clear();
+        self.interned = 0;
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the live `Arc` equal to `value`, or interns a new one
+    fn intern(&mut self, value: V) -> Arc<V> {
+        let hash = self.hasher.hash_one(&value);
+        let bucket = self.table.entry(hash).or_default();
+        let before = bucket.len();
+        bucket.retain(|weak| weak.strong_count() > 0);
+        self.interned -= before - bucket.len();
+        if let Some(shared) = bucket
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|shared| **shared == value)
+        {
+            return shared;
+        }
+
+        let shared = Arc::new(value);
+        bucket.push(Arc::downgrade(&shared));
+        self.interned += 1;
+        if self.interned > self.sweep_at {
+            self.sweep();
+        }
+        shared
+    }
+
+    /// Drops handles to values that are no longer alive
+    fn sweep(&mut self) {
+        self.table.retain(|_, bucket| {
+            bucket.retain(|weak| weak.strong_count() > 0);
+            !bucket.is_empty()
+        });
+        self.interned = self.table.values().map(Vec::len).sum();
+        self.sweep_at = (2 * self.interned).max(self.inner.capacity().saturating_mul(2));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_interning_shares_equal_values() {
+        let mut cache = InterningCache::new(LruCache::new(64));
+        for key in 0..50 {
+            cache.insert(key, vec![9u8; 1 << 20]);
+        }
+        assert_eq!(cache.len(), 50);
+        assert_eq!(cache.distinct_values(), 1);
+
+        let first = cache.get(&0).unwrap();
+        let last = cache.get(&49).unwrap();
+        assert!(Arc::ptr_eq(&first, &last));
+        // One reference per key plus the two handles held here
+        assert_eq!(Arc::strong_count(&first), 52);
+
+        // Evicting every key that referenced the value frees it
+        let weak = Arc::downgrade(&first);
+        drop((first, last));
+        for key in 100..164 {
+            cache.insert(key, vec![key as u8]);
+        }
+        assert!(weak.upgrade().is_none());
+        assert_eq!(cache.distinct_values(), 64);
+    }
+
+    #[test]
+    fn test_interning_over_unbounded_inner_cache() {
+        use crate::policies::UnboundedCache;
+
+        let mut cache = InterningCache::new(UnboundedCache::new());
+        cache.insert(1, "shared".to_string());
+        cache.insert(2, "shared".to_string());
+        let first = cache.get(&1).unwrap();
+        let second = cache.get(&2).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.distinct_values(), 1);
+    }
+}
@@ -1,8 +1,33 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::hash::Hash;
 
+use crate::frozen::FrozenCache;
+
 // Exported modules of the crate
 pub mod policies;
 pub mod prefetch;
+pub mod autotune;
+pub mod bypass;
+pub mod shadow;
+pub mod shared_value;
+pub mod weak_value;
+pub mod interning;
+pub mod trace;
+pub mod report;
+pub mod fuzz;
+pub mod weighted;
+pub mod soft_limit;
+pub mod clock;
+pub mod ttl;
+pub mod multi;
+pub mod warm;
+pub mod aside;
+pub mod frozen;
+pub mod cache;
+pub mod concurrent;
+#[cfg(feature = "spill")]
+pub mod spill;
 
 /// Core trait defining cache policy behavior
 ///
@@ -12,12 +37,43 @@ pub trait CachePolicy<K, V> {
     /// Retrieve a value by key, possibly updating internal state (e.g. usage order)
     fn get(&mut self, key: &K) -> Option<&V>;
 
+    /// Like [`get`](Self::get), also reporting where the value came from
+    ///
+    /// Defaults to [`Access::MainHit`] or [`Access::Miss`]; policies with a
+    /// prefetch buffer report [`Access::PrefetchHit`] for a key served from it.
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let value = self.get(key);
+        (Access::of(value.is_some(), false), value)
+    }
+
+    /// Like [`get`](Self::get), with a missing key as an error for `?`
+    fn try_get(&mut self, key: &K) -> Result<&V, CacheMiss<K>>
+    where
+        K: Clone,
+    {
+        self.get(key).ok_or_else(|| CacheMiss { key: key.clone() })
+    }
+
     /// Insert or update a key-value pair; may evict items if at capacity
     fn insert(&mut self, key: K, value: V);
 
     /// Remove a key-value pair from cache, returning the value if present
+    ///
+    /// Prefetched entries not yet read count as present here: removing such
+    /// a key discards the staged value and returns it, even though
+    /// `contains_key` reports it absent. Use
+    /// [`remove_from_cache_only`](Self::remove_from_cache_only) for the
+    /// same notion of presence as `contains_key`.
     fn remove(&mut self, key: &K) -> Option<V>;
 
+    /// Like [`remove`](Self::remove), with a missing key as an error for `?`
+    fn try_remove(&mut self, key: &K) -> Result<V, CacheMiss<K>>
+    where
+        K: Clone,
+    {
+        self.remove(key).ok_or_else(|| CacheMiss { key: key.clone() })
+    }
+
     /// Return current number of entries in the cache
     fn len(&self) -> usize;
 
@@ -31,13 +87,265 @@ pub trait CachePolicy<K, V> {
 
     /// Return the maximum allowed capacity of the cache
     fn capacity(&self) -> usize;
+
+    /// Change the maximum capacity of the cache at runtime
+    ///
+    /// Growing only raises the limit. Shrinking below the current length
+    /// evicts entries according to the policy's own eviction rule until
+    /// `len() <= new_capacity`.
+    ///
+    /// The default is shrink-only: it evicts through
+    /// [`evict_one`](Self::evict_one) and leaves `capacity()` unchanged, so
+    /// it can't grow the cache. Policies override it to move their limit
+    /// as well.
+    ///
+    /// # Panics
+    /// Panics if `new_capacity` is 0
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "Cache capacity must be greater than 0");
+        while self.len() > new_capacity && self.evict_one().is_some() {}
+    }
+
+    /// Evict the entry the policy would drop next to make room, returning it
+    ///
+    /// The victim is chosen, and ghost lists, adaptive targets and segments
+    /// updated, as for a capacity eviction during `insert`; it counts in
+    /// `metrics().evictions`. Wrappers bounding the cache by something other
+    /// than entry count, such as [`WeightedCache`](weighted::WeightedCache),
+    /// evict through this. Returns `None` when nothing can be evicted, which
+    /// is all the default does.
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        None
+    }
+
+    /// Count of new keys stored and of entries evicted to respect the capacity
+    ///
+    /// Explicit removals and `clear` are not evictions. Policies that don't
+    /// keep these counters report zeros.
+    fn metrics(&self) -> CacheMetrics {
+        CacheMetrics::default()
+    }
+
+    /// Zero the counters reported by [`metrics`](CachePolicy::metrics)
+    fn reset_metrics(&mut self) {}
+
+    /// Bound the cache by the total weight of its entries
+    ///
+    /// `weigher` reports an entry's size in bytes, or any other unit, and
+    /// must give the same answer for the same entry every time. Entries are
+    /// then evicted in the policy's usual order until the weighed total fits
+    /// in `bytes`, while `len()` keeps counting entries. The entry capacity
+    /// still applies too, so raise it if only bytes should bind. An entry
+    /// weighing exactly `bytes` is admitted and evicts everything else. An
+    /// insert heavier than the whole budget is dropped, along with any value
+    /// already stored under its key, and evicts nothing else.
+    ///
+    /// Policies without byte support ignore this and stay in entry-count mode.
+    fn set_byte_capacity(&mut self, _bytes: usize, _weigher: Weigher<K, V>) {}
+
+    /// Turn prefetching on or off without rebuilding the cache
+    ///
+    /// While off, accesses neither feed the prefetch strategy nor ask it
+    /// for predictions, so prefetch statistics stay unchanged. Policies
+    /// without prefetching ignore this.
+    fn set_prefetch_enabled(&mut self, _enabled: bool) {}
+
+    /// Whether accesses currently run the prefetch strategy
+    ///
+    /// Always `false` for policies without prefetching.
+    fn prefetch_enabled(&self) -> bool {
+        false
+    }
+
+    /// Turn prefetching off until the returned guard is dropped
+    ///
+    /// The guard derefs to the cache, so the region uses it as usual. On
+    /// drop the previous setting is restored, which makes nested guards
+    /// safe.
+    fn suppress_prefetch(&mut self) -> PrefetchGuard<'_, K, V, Self>
+    where
+        Self: Sized,
+    {
+        let restore = self.prefetch_enabled();
+        self.set_prefetch_enabled(false);
+        PrefetchGuard {
+            cache: self,
+            restore,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Check whether a key is resident, without touching eviction order,
+    /// reference bits or prefetch state
+    ///
+    /// Prefetched entries not yet read are not resident, nor are ghost
+    /// entries that hold no value.
+    fn contains_key(&self, key: &K) -> bool;
+
+    /// Read a resident value without touching eviction order, reference
+    /// bits or prefetch state
+    ///
+    /// Sees the same entries as `contains_key`. The default returns `None`;
+    /// policies that can hand out a reference without mutating override it.
+    fn peek(&self, _key: &K) -> Option<&V> {
+        None
+    }
+
+    /// Read a value for in-place mutation, updating usage state as `get` does
+    ///
+    /// Recency, promotion, reference bits, access counts and prefetching
+    /// all behave as for a read, which saves cloning a large value only to
+    /// `insert` it back. The reference borrows the cache mutably, so the
+    /// cache can't be used again until it is dropped.
+    ///
+    /// In byte-capacity mode an entry is weighed when it is inserted and
+    /// removed. A change that alters its weight must go through `insert` or
+    /// `for_each_value_mut` instead.
+    ///
+    /// The default returns `None`; policies that can hand out a mutable
+    /// reference override it.
+    fn get_mut(&mut self, _key: &K) -> Option<&mut V> {
+        None
+    }
+
+    /// Remove a key only if it is resident, as reported by `contains_key`
+    ///
+    /// A value staged in the prefetch buffer is left where it is and `None`
+    /// is returned for it.
+    fn remove_from_cache_only(&mut self, key: &K) -> Option<V> {
+        if self.contains_key(key) {
+            self.remove(key)
+        } else {
+            None
+        }
+    }
+
+    /// Checks residency of a batch of keys, with the same read-only
+    /// guarantees as `contains_key`
+    ///
+    /// Entry `i` of the result tells whether `keys[i]` is resident.
+    fn contains_many(&self, keys: &[K]) -> Vec<bool> {
+        keys.iter().map(|key| self.contains_key(key)).collect()
+    }
+
+    /// Reclaim internal space left behind by removals
+    ///
+    /// Contents and eviction order are unchanged. Most policies keep no
+    /// such slack, so the default does nothing.
+    fn compact(&mut self) {}
+
+    /// Visit every resident entry, in no particular order
+    ///
+    /// Eviction order is left untouched, unlike reading entries through
+    /// [`get`](Self::get).
+    ///
+    /// The default visits nothing; policies that can enumerate their
+    /// entries override it.
+    fn for_each_entry(&self, _f: &mut dyn FnMut(&K, &V)) {}
+
+    /// Visit every resident value mutably, in no particular order
+    ///
+    /// Eviction order is left untouched, which makes this the hook for bulk
+    /// in-place rewrites such as [`ShadowKeysCache::clear_values`](shadow::ShadowKeysCache::clear_values).
+    ///
+    /// The default visits nothing; policies that can enumerate their
+    /// entries override it.
+    fn for_each_value_mut(&mut self, _f: &mut dyn FnMut(&K, &mut V)) {}
+
+    /// Copy the resident entries into an immutable, shareable snapshot
+    ///
+    /// The snapshot serves any number of threads without locking and
+    /// without recency updates. The cache itself carries on independently;
+    /// freeze again to publish its later contents. The snapshot holds the
+    /// entries [`for_each_entry`](Self::for_each_entry) visits.
+    fn freeze(&self) -> FrozenCache<K, V>
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+    {
+        let mut map = HashMap::with_capacity(self.len());
+        self.for_each_entry(&mut |key, value| {
+            map.insert(key.clone(), value.clone());
+        });
+        FrozenCache::new(map)
+    }
+
+    /// Insert the value computed by `f` only if `key` is absent
+    ///
+    /// Returns `true` with the freshly inserted value, or `false` with the
+    /// existing one. `f` only runs on absence. A hit counts as an access
+    /// like [`get`](Self::get); a fresh insert is read back through
+    /// [`peek`](Self::peek), so it doesn't look like a second access to the
+    /// policy. The value is `None` when the policy didn't keep the insert,
+    /// e.g. an insert transform rejected it or it alone exceeds the byte
+    /// budget.
+    fn insert_if_absent_with<F>(&mut self, key: K, f: F) -> (bool, Option<&V>)
+    where
+        Self: Sized,
+        K: Clone,
+        F: FnOnce() -> V,
+    {
+        if self.contains_key(&key) {
+            return (false, self.get(&key));
+        }
+        self.insert(key.clone(), f());
+        (true, self.peek(&key))
+    }
+
+    /// Like [`insert_if_absent_with`](Self::insert_if_absent_with), but
+    /// borrows the key and only clones it when it has to be inserted
+    ///
+    /// Hits, the common case, never clone the key, which saves an
+    /// allocation per lookup for `String` or other heap-backed keys.
+    fn get_or_insert_with_ref<F>(&mut self, key: &K, f: F) -> Option<&V>
+    where
+        Self: Sized,
+        K: Clone,
+        F: FnOnce() -> V,
+    {
+        if self.contains_key(key) {
+            return self.get(key);
+        }
+        self.insert_if_absent_with(key.clone(), f).1
+    }
+
+    /// Temporarily raise the capacity while `f` runs, then shrink back
+    ///
+    /// Useful for bulk reloads: everything loaded inside `f` fits, and the
+    /// original capacity is restored afterwards through [`resize`](Self::resize),
+    /// evicting the now-excess entries per the policy. The original capacity
+    /// is restored even if `f` panics.
+    ///
+    /// # Panics
+    /// Panics if `resize` doesn't move `capacity()` to `temp_cap`, as with
+    /// the shrink-only default
+    fn with_temporary_capacity<F>(&mut self, temp_cap: usize, f: F)
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self),
+    {
+        let original_capacity = self.capacity();
+        self.resize(temp_cap);
+        assert_eq!(
+            self.capacity(),
+            temp_cap,
+            "with_temporary_capacity needs a resize that changes capacity()"
+        );
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self)));
+        self.resize(original_capacity);
+        if let Err(payload) = result {
+            std::panic::resume_unwind(payload);
+        }
+    }
 }
 
 /// Trait for prefetch strategies predicting future cache accesses.
 ///
 /// Implementations use historical or pattern data to predict keys
 /// that will likely be requested soon, improving cache hit rates.
-pub trait PrefetchStrategy<K> {
+/// Strategies must be `Send` so the caches holding them can move between
+/// threads.
+pub trait PrefetchStrategy<K>: Send {
     /// Predict next keys likely to be accessed following the current key
     fn predict_next(&mut self, accessed_key: &K) -> Vec<K>;
 
@@ -48,13 +356,124 @@ pub trait PrefetchStrategy<K> {
     fn reset(&mut self);
 }
 
+/// Callback tapping prefetch activity: receives the accessed key and the
+/// keys predicted for it
+pub type PredictionObserver<K> = Box<dyn FnMut(&K, &[K]) + Send>;
+
+/// Why an entry left the cache, as reported to an [`EvictionListener`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// Dropped to make room under the entry or byte capacity
+    Capacity,
+    /// Removed by an explicit invalidation
+    Invalidated,
+}
+
+/// Callback receiving each entry dropped by the cache and the reason
+pub type EvictionListener<K, V> = Box<dyn FnMut(&K, &V, EvictionReason) + Send>;
+
+/// Reports the size of an entry for [`CachePolicy::set_byte_capacity`]
+///
+/// Read-only checks such as `check_invariants` call it through a shared
+/// reference, so it must be `Sync` as well as `Send`.
+pub type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize + Send + Sync>;
+
+/// Outcome of a lookup through [`CachePolicy::access`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Served from the main cache
+    MainHit,
+    /// Served from an entry prefetched but not read until now
+    PrefetchHit,
+    /// Not cached
+    Miss,
+}
+
+impl Access {
+    /// Whether the key was found
+    pub fn is_hit(&self) -> bool {
+        !matches!(self, Access::Miss)
+    }
+
+    pub(crate) fn of(hit: bool, prefetched: bool) -> Self {
+        match (hit, prefetched) {
+            (false, _) => Access::Miss,
+            (true, true) => Access::PrefetchHit,
+            (true, false) => Access::MainHit,
+        }
+    }
+}
+
+/// Error for a key that isn't cached, from [`CachePolicy::try_get`] and
+/// [`CachePolicy::try_remove`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheMiss<K> {
+    /// The key that was looked up
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for CacheMiss<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {:?} not found in cache", self.key)
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for CacheMiss<K> {}
+
+/// Keeps prefetching off for a scope, from [`CachePolicy::suppress_prefetch`]
+pub struct PrefetchGuard<'a, K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    cache: &'a mut C,
+    /// Setting in force before the guard, put back on drop
+    restore: bool,
+    _phantom: std::marker::PhantomData<(K, V)>,
+}
+
+impl<K, V, C> std::ops::Deref for PrefetchGuard<'_, K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.cache
+    }
+}
+
+impl<K, V, C> std::ops::DerefMut for PrefetchGuard<'_, K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    fn deref_mut(&mut self) -> &mut C {
+        self.cache
+    }
+}
+
+impl<K, V, C> Drop for PrefetchGuard<'_, K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    fn drop(&mut self) {
+        self.cache.set_prefetch_enabled(self.restore);
+    }
+}
+
 /// Struct holding statistics about cache usage and performance
 #[derive(Debug, Clone, Default)]
 pub struct CacheStats {
     pub hits: u64,
     pub misses: u64,
+    /// Prefetched keys read for the first time while still resident
     pub prefetch_hits: u64,
     pub total_accesses: u64,
+    /// Keys placed by prefetching, predicted or through `prefetch_keys`
+    pub total_prefetches: u64,
+    /// Keys loaded on explicit request through `prefetch_keys`
+    pub explicit_prefetch_loads: u64,
+    /// New keys whose insert pushed another entry out
+    pub evictions: u64,
 }
 
 impl CacheStats {
@@ -67,22 +486,43 @@ impl CacheStats {
         }
     }
 
-    /// Calculate prefetch efficiency (ratio of useful prefetch hits)
+    /// Calculate prefetch efficiency (share of prefetched keys later read)
     pub fn prefetch_efficiency(&self) -> f64 {
-        if self.prefetch_hits == 0 {
+        if self.total_prefetches == 0 {
             0.0
         } else {
-            // This approximation assumes total prefetches = prefetch_hits + misses
-            self.prefetch_hits as f64 / (self.prefetch_hits + self.misses) as f64
+            self.prefetch_hits as f64 / self.total_prefetches as f64
         }
     }
 
+    /// Prefetched keys not read so far, evicted or still waiting
+    pub fn prefetch_waste(&self) -> u64 {
+        self.total_prefetches.saturating_sub(self.prefetch_hits)
+    }
+
     /// Reset all tracked statistics to zero
     pub fn reset(&mut self) {
         *self = Self::default();
     }
 }
 
+/// Entry churn counted by a policy since it was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    /// Keys stored that were not resident before
+    pub insertions: u64,
+    /// Entries dropped to stay within the entry or byte capacity
+    pub evictions: u64,
+}
+
+/// Change in the main counters over one sampling interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStatsDelta {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
 /// Composite cache struct combining an eviction policy and prefetch strategy.
 ///
 /// Supports predictive loading and cache eviction coordination, tracking stats.
@@ -96,7 +536,23 @@ where
     cache: C,
     prefetch_strategy: P,
     prefetch_fn: Option<Box<dyn Fn(&K) -> Option<V>>>, // Custom data loader function
+    /// Keys placed by prefetching and not read since
+    prefetched: HashSet<K>,
     stats: CacheStats,
+    /// Counters as of the last `stats_delta` call
+    sampled: CacheStats,
+    /// Lookups are counted once every `sample_rate` accesses
+    sample_rate: u32,
+    /// Accesses since the last counted one
+    sample_tick: u32,
+    /// Periodic stats snapshots, oldest first
+    history: VecDeque<CacheStats>,
+    /// Accesses between snapshots, 0 when history is off
+    history_every: usize,
+    /// Snapshots kept before the oldest is dropped
+    history_max: usize,
+    /// Accesses since the last snapshot
+    history_tick: usize,
     _phantom: std::marker::PhantomData<(K, V)>,
 }
 
@@ -113,11 +569,41 @@ where
             cache,
             prefetch_strategy,
             prefetch_fn: None,
+            prefetched: HashSet::new(),
             stats: CacheStats::default(),
+            sampled: CacheStats::default(),
+            sample_rate: 1,
+            sample_tick: 0,
+            history: VecDeque::new(),
+            history_every: 0,
+            history_max: 0,
+            history_tick: 0,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Count hits and misses on only one access in `rate`
+    ///
+    /// With `rate` above 1, `hits`, `misses` and `total_accesses` count
+    /// sampled lookups only, and [`hit_ratio`](Self::hit_ratio) becomes an
+    /// estimate from that sample. Over `n` sampled lookups its standard
+    /// error is about `sqrt(r * (1 - r) / n)` for a true ratio `r`, so
+    /// 10 000 samples pin it within roughly ±0.01. Sampling takes every
+    /// `rate`-th access, so a workload cycling with a period dividing
+    /// `rate` is sampled at the same phase each time and can be badly
+    /// biased. Eviction and prefetch counters stay exact. The default rate
+    /// of 1 counts every access; 0 is treated as 1.
+    pub fn set_stats_sampling(&mut self, rate: u32) {
+        self.sample_rate = rate.max(1);
+        self.sample_tick = 0;
+    }
+
+    /// Fraction of lookups that hit, estimated from the sample when
+    /// sampling is on
+    pub fn hit_ratio(&self) -> f64 {
+        self.stats.hit_ratio()
+    }
+
     /// Configure a custom prefetch function to load values on demand
     ///
     /// Function should return `Some(value)` if key can be loaded, `None` otherwise.
@@ -129,49 +615,256 @@ where
         self
     }
 
+    /// Record a snapshot of the stats every `every_n_accesses` accesses
+    ///
+    /// Up to `max_samples` snapshots are kept, dropping the oldest first.
+    /// Every key read by `get`, `access`, `get_snapshot` or `get_many`
+    /// counts as an access, whatever the stats sampling rate. Enabling again discards the
+    /// recorded history; an interval or size of 0 turns recording off.
+    pub fn enable_stat_history(&mut self, every_n_accesses: usize, max_samples: usize) {
+        self.history.clear();
+        self.history_tick = 0;
+        self.history_max = max_samples;
+        self.history_every = if max_samples == 0 { 0 } else { every_n_accesses };
+    }
+
+    /// Recorded stats snapshots, oldest first
+    pub fn stat_history(&self) -> &[CacheStats] {
+        // `tick_history` keeps the buffer contiguous
+        self.history.as_slices().0
+    }
+
     /// Retrieve a value from the cache, triggering prefetching as needed
     pub fn get(&mut self, key: &K) -> Option<V> {
-        self.stats.total_accesses += 1;
+        let value = self.lookup(key);
+        self.tick_history();
+        value
+    }
+
+    /// Body of `get`, leaving the stats history alone
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        let value = self.fetch(key);
+        if value.is_some() {
+            // Trigger predictive prefetching of related keys
+            self.prefetch_predicted_keys(key);
+        }
+        value
+    }
+
+    /// Reads `key` as one counted access, loading it on a miss, without
+    /// prefetching predicted keys
+    fn fetch(&mut self, key: &K) -> Option<V> {
+        let counted = self.sample_access();
+        self.consume_prefetched(key);
         // Update access pattern for prediction
         self.prefetch_strategy.update_access_pattern(key);
         // Attempt to get from cache first
         if let Some(value) = self.cache.get(key) {
-            self.stats.hits += 1;
-            let result = value.clone();
-            // Trigger predictive prefetching of related keys
-            self.prefetch_predicted_keys(key);
-            return Some(result);
+            if counted {
+                self.stats.hits += 1;
+            }
+            return Some(value.clone());
+        }
+        if counted {
+            self.stats.misses += 1;
         }
-        self.stats.misses += 1;
         // Attempt loading via prefetch function if configured
         if let Some(ref prefetch_fn) = self.prefetch_fn {
             if let Some(value) = prefetch_fn(key) {
-                self.cache.insert(key.clone(), value.clone());
-                self.prefetch_predicted_keys(key);
+                Self::insert_counted(&mut self.cache, &mut self.stats, key.clone(), value.clone());
                 return Some(value);
             }
         }
         None
     }
 
+    /// Return the cached value for `key`, computing and inserting it with
+    /// `f` on a miss
+    ///
+    /// A hit counts as a hit; a miss runs `f`, inserts its value and counts
+    /// as a miss. For the requested key `f` takes precedence over the
+    /// prefetch loader, which only fetches predicted keys. Prefetching
+    /// runs as in [`get`](Self::get), but before the value is read so no
+    /// prefetched insert can evict it; in a cache small enough for
+    /// prefetching to evict a hit, `f` runs to put it back. Evictions the
+    /// insert causes are taken from the policy's own
+    /// [`metrics`](CachePolicy::metrics). Returns `None` when the policy
+    /// doesn't keep the computed value, e.g. an insert transform rejects it
+    /// or it alone exceeds the byte budget.
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> Option<&V>
+    where
+        F: FnOnce() -> V,
+    {
+        let counted = self.sample_access();
+        self.consume_prefetched(&key);
+        self.prefetch_strategy.update_access_pattern(&key);
+        if counted {
+            if self.cache.contains_key(&key) {
+                self.stats.hits += 1;
+            } else {
+                self.stats.misses += 1;
+            }
+        }
+        self.tick_history();
+        self.prefetch_predicted_keys_except(&key, Some(&key));
+
+        if self.cache.contains_key(&key) {
+            return self.cache.insert_if_absent_with(key, f).1;
+        }
+        let evictions = self.cache.metrics().evictions;
+        self.cache.insert_if_absent_with(key.clone(), f);
+        self.stats.evictions += self.cache.metrics().evictions.saturating_sub(evictions);
+
+        // Read the new value back without another access, where the policy can
+        if self.cache.peek(&key).is_none() {
+            return self.cache.get(&key);
+        }
+        self.cache.peek(&key)
+    }
+
+    /// Like [`get`](Self::get), also reporting where the value came from
+    ///
+    /// A key placed by prefetching, predicted or through
+    /// [`prefetch_keys`](Self::prefetch_keys), reports
+    /// [`Access::PrefetchHit`] on its first read only. A miss served by the
+    /// loader still reports [`Access::Miss`].
+    pub fn access(&mut self, key: &K) -> (Access, Option<V>) {
+        let resident = self.cache.contains_key(key);
+        let prefetched = resident && self.prefetched.contains(key);
+        let value = self.get(key);
+        (Access::of(resident, prefetched), value)
+    }
+
+    /// Reads several keys as one operation, returning the cached subset
+    ///
+    /// The result is a point-in-time view of a single-threaded cache: every
+    /// key is looked up before anything else happens, and keys not resident
+    /// are left out rather than loaded. Prefetching runs at most once, after
+    /// the batch, predicted from the last requested key that was found.
+    pub fn get_snapshot(&mut self, keys: &[K]) -> HashMap<K, V> {
+        let mut snapshot = HashMap::with_capacity(keys.len());
+        let mut last_hit = None;
+        for key in keys {
+            let counted = self.sample_access();
+            self.consume_prefetched(key);
+            self.prefetch_strategy.update_access_pattern(key);
+            if let Some(value) = self.cache.get(key) {
+                if counted {
+                    self.stats.hits += 1;
+                }
+                snapshot.insert(key.clone(), value.clone());
+                last_hit = Some(key);
+            } else if counted {
+                self.stats.misses += 1;
+            }
+            self.tick_history();
+        }
+        if let Some(key) = last_hit {
+            self.prefetch_predicted_keys(key);
+        }
+        snapshot
+    }
+
+    /// Retrieve several values in order, as a batch of [`get`](Self::get)s
+    ///
+    /// Each key counts as an access and a missing one is loaded, exactly as
+    /// `get` would. The prefetch strategy sees the whole batch before it is
+    /// asked for predictions, which happens once, from the last key found,
+    /// so prefetching can't evict keys the batch is still reading.
+    pub fn get_many(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        let mut values = Vec::with_capacity(keys.len());
+        let mut last_found = None;
+        for key in keys {
+            let value = self.fetch(key);
+            if value.is_some() {
+                last_found = Some(key);
+            }
+            values.push(value);
+            self.tick_history();
+        }
+        if let Some(key) = last_found {
+            self.prefetch_predicted_keys(key);
+        }
+        values
+    }
+
     /// Insert or update a key-value pair directly in the cache
     pub fn insert(&mut self, key: K, value: V) {
-        self.cache.insert(key, value);
+        self.prefetched.remove(&key);
+        Self::insert_counted(&mut self.cache, &mut self.stats, key, value);
+    }
+
+    /// Insert or update several pairs in order, as a batch of
+    /// [`insert`](Self::insert)s
+    ///
+    /// A later pair overwrites an earlier one with the same key, and a
+    /// batch larger than the capacity evicts its own first entries.
+    pub fn insert_many(&mut self, items: Vec<(K, V)>) {
+        for (key, value) in items {
+            self.insert(key, value);
+        }
     }
 
     /// Remove a key-value pair from the cache, returning its value if present
     pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.prefetched.remove(key);
         self.cache.remove(key)
     }
 
+    /// Warm the cache with an explicit set of keys
+    ///
+    /// Each key not already resident is loaded through `loader` and inserted.
+    /// At most `capacity()` keys are loaded so the batch can't evict its own
+    /// earlier entries. Returns the number of keys inserted.
+    pub fn prefetch_keys<F>(&mut self, keys: &[K], loader: F) -> usize
+    where
+        F: Fn(&K) -> Option<V>,
+    {
+        let mut loaded = 0;
+        for key in keys.iter().take(self.cache.capacity()) {
+            if self.cache.contains_key(key) {
+                continue;
+            }
+            if let Some(value) = loader(key) {
+                Self::insert_counted(&mut self.cache, &mut self.stats, key.clone(), value);
+                self.prefetched.insert(key.clone());
+                self.stats.explicit_prefetch_loads += 1;
+                self.stats.total_prefetches += 1;
+                loaded += 1;
+            }
+        }
+        self.trim_prefetched();
+        loaded
+    }
+
     /// Access current cache statistics
     pub fn stats(&self) -> &CacheStats {
         &self.stats
     }
 
+    /// Hits, misses and evictions since the previous call
+    ///
+    /// The first call covers everything since creation or the last reset.
+    /// Sampling this at a fixed period yields per-interval rates without
+    /// the caller keeping previous totals.
+    pub fn stats_delta(&mut self) -> CacheStatsDelta {
+        let delta = CacheStatsDelta {
+            hits: self.stats.hits - self.sampled.hits,
+            misses: self.stats.misses - self.sampled.misses,
+            evictions: self.stats.evictions - self.sampled.evictions,
+        };
+        self.sampled = self.stats.clone();
+        delta
+    }
+
     /// Reset cache and prefetch strategy statistics and state
+    ///
+    /// Recorded stats history is discarded too.
     pub fn reset_stats(&mut self) {
         self.stats.reset();
+        self.sampled.reset();
+        self.history.clear();
+        self.history_tick = 0;
         self.prefetch_strategy.reset();
     }
 
@@ -190,32 +883,721 @@ where
         self.cache.capacity()
     }
 
+    /// Change the capacity without rebuilding the cache
+    ///
+    /// Shrinking evicts per the policy's own rule until the entries fit;
+    /// those count as evictions in the stats.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn resize(&mut self, capacity: usize) {
+        let before = self.cache.len();
+        self.cache.resize(capacity);
+        self.stats.evictions += before.saturating_sub(self.cache.len()) as u64;
+        let cache = &self.cache;
+        self.prefetched.retain(|k| cache.contains_key(k));
+    }
+
     /// Clear all data in the cache and reset statistics
     pub fn clear(&mut self) {
         self.cache.clear();
+        self.prefetched.clear();
         self.reset_stats();
     }
 
     /// Internal helper to prefetch keys predicted by prefetch strategy
+    ///
+    /// Keys placed by this operation that a later prefetch pushes out again
+    /// are not prefetched back, so a small cache can't ping-pong a slot
+    /// between predictions within a single access.
     fn prefetch_predicted_keys(&mut self, accessed_key: &K) {
+        self.prefetch_predicted_keys_except(accessed_key, None);
+    }
+
+    /// [`prefetch_predicted_keys`](Self::prefetch_predicted_keys), never
+    /// loading `skip` even if it is predicted
+    fn prefetch_predicted_keys_except(&mut self, accessed_key: &K, skip: Option<&K>) {
         if let Some(ref prefetch_fn) = self.prefetch_fn {
             let predicted_keys = self.prefetch_strategy.predict_next(accessed_key);
+            let mut placed = vec![accessed_key.clone()];
+            let mut evicted = HashSet::new();
             for key in predicted_keys {
+                if evicted.contains(&key) || skip == Some(&key) {
+                    continue;
+                }
                 // Only fetch if key not already cached
                 if self.cache.get(&key).is_none() {
                     if let Some(value) = prefetch_fn(&key) {
-                        self.cache.insert(key, value);
-                        self.stats.prefetch_hits += 1;
+                        Self::insert_counted(&mut self.cache, &mut self.stats, key.clone(), value);
+                        self.stats.total_prefetches += 1;
+                        placed.retain(|k| {
+                            let resident = self.cache.contains_key(k);
+                            if !resident {
+                                evicted.insert(k.clone());
+                            }
+                            resident
+                        });
+                        placed.push(key.clone());
+                        self.prefetched.insert(key);
                     }
                 }
             }
         }
+        self.trim_prefetched();
+    }
+
+    /// Counts an access towards the next history snapshot, taking it if due
+    fn tick_history(&mut self) {
+        if self.history_every == 0 {
+            return;
+        }
+        self.history_tick += 1;
+        if self.history_tick == self.history_every {
+            self.history_tick = 0;
+            if self.history.len() == self.history_max {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.stats.clone());
+            self.history.make_contiguous();
+        }
+    }
+
+    /// Advances the sampling counter, counting the access if it is sampled
+    fn sample_access(&mut self) -> bool {
+        self.sample_tick += 1;
+        if self.sample_tick < self.sample_rate {
+            return false;
+        }
+        self.sample_tick = 0;
+        self.stats.total_accesses += 1;
+        true
+    }
+
+    /// Inserts into `cache`, counting an eviction if a new key didn't grow it
+    fn insert_counted(cache: &mut C, stats: &mut CacheStats, key: K, value: V) {
+        let fresh = !cache.contains_key(&key);
+        let before = cache.len();
+        cache.insert(key, value);
+        if fresh && cache.len() <= before {
+            stats.evictions += 1;
+        }
+    }
+
+    /// Credits a prefetch hit if `key` was prefetched and is read for the
+    /// first time while still resident
+    ///
+    /// Counted on every access, sampled or not, to match `total_prefetches`.
+    fn consume_prefetched(&mut self, key: &K) {
+        if self.prefetched.remove(key) && self.cache.contains_key(key) {
+            self.stats.prefetch_hits += 1;
+        }
+    }
+
+    /// Forgets evicted prefetched keys once the set outgrows the cache
+    fn trim_prefetched(&mut self) {
+        if self.prefetched.len() > self.cache.capacity() {
+            let cache = &self.cache;
+            self.prefetched.retain(|k| cache.contains_key(k));
+        }
     }
 }
 
 // Convenient re-exports for common types and modules
 pub mod prelude {
-    pub use super::{CachePolicy, PrefetchStrategy, FulgranceCache, CacheStats};
+    pub use super::{Access, CacheMiss, CachePolicy, EvictionReason, PrefetchGuard, PrefetchStrategy, FulgranceCache, CacheMetrics, CacheStats, CacheStatsDelta};
+    pub use super::cache::{Cache, CacheBuilder};
+    pub use super::frozen::FrozenCache;
     pub use super::policies::{LruCache, MruCache, PolicyType};
     pub use super::prefetch::{SequentialPrefetch, PrefetchType};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{LruCache, PolicyType, create_cache_policy};
+    use crate::prefetch::SequentialPrefetch;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_temporary_capacity_bulk_load() {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+
+        cache.with_temporary_capacity(10, |c| {
+            for i in 4..=10 {
+                c.insert(i, i);
+            }
+            assert_eq!(c.len(), 10);
+        });
+
+        assert_eq!(cache.capacity(), 3);
+        assert_eq!(cache.len(), 3);
+        for key in 8..=10 {
+            assert!(cache.get(&key).is_some());
+        }
+        assert!(cache.get(&1).is_none());
+    }
+
+    #[test]
+    fn test_temporary_capacity_restored_on_panic() {
+        let mut cache = LruCache::new(2);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cache.with_temporary_capacity(8, |c| {
+                for i in 0..8 {
+                    c.insert(i, i);
+                }
+                panic!("bulk load failed");
+            });
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(cache.capacity(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a resize that changes capacity()")]
+    fn test_temporary_capacity_rejects_shrink_only_resize() {
+        /// Fixed-size cache relying on the default `resize`
+        struct Fixed(HashMap<i32, i32>);
+
+        impl CachePolicy<i32, i32> for Fixed {
+            fn get(&mut self, key: &i32) -> Option<&i32> {
+                self.0.get(key)
+            }
+            fn insert(&mut self, key: i32, value: i32) {
+                self.0.insert(key, value);
+            }
+            fn remove(&mut self, key: &i32) -> Option<i32> {
+                self.0.remove(key)
+            }
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+            fn clear(&mut self) {
+                self.0.clear();
+            }
+            fn capacity(&self) -> usize {
+                4
+            }
+            fn contains_key(&self, key: &i32) -> bool {
+                self.0.contains_key(key)
+            }
+        }
+
+        Fixed(HashMap::new()).with_temporary_capacity(8, |_| {});
+    }
+
+    /// Predicts the next key and then the accessed key again
+    struct PingPongPrefetch;
+
+    impl PrefetchStrategy<i32> for PingPongPrefetch {
+        fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
+            vec![accessed_key + 1, *accessed_key]
+        }
+        fn update_access_pattern(&mut self, _key: &i32) {}
+        fn reset(&mut self) {}
+    }
+
+    fn counting_loader(loads: &Rc<RefCell<Vec<i32>>>) -> impl Fn(&i32) -> Option<i32> + 'static {
+        let loads = Rc::clone(loads);
+        move |key| {
+            loads.borrow_mut().push(*key);
+            Some(*key)
+        }
+    }
+
+    #[test]
+    fn test_prefetch_skips_keys_evicted_in_same_access() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(1), PingPongPrefetch)
+            .with_prefetch_fn(counting_loader(&loads));
+
+        assert_eq!(cache.get(&5), Some(5));
+        // 5 is loaded, pushed out by the prefetch of 6, and not loaded back
+        assert_eq!(*loads.borrow(), vec![5, 6]);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_get_snapshot_returns_cached_subset() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(8), PingPongPrefetch)
+            .with_prefetch_fn(counting_loader(&loads));
+        for key in [1, 3, 5] {
+            cache.insert(key, key * 10);
+        }
+
+        let snapshot = cache.get_snapshot(&[1, 2, 3, 4, 5]);
+        assert_eq!(snapshot, HashMap::from([(1, 10), (3, 30), (5, 50)]));
+        // Misses are not loaded; the batch prefetches once, after 5
+        assert_eq!(*loads.borrow(), vec![6]);
+        assert_eq!(cache.stats().hits, 3);
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[test]
+    fn test_stats_delta_covers_one_interval() {
+        let mut cache = FulgranceCache::new(LruCache::new(2), SequentialPrefetch::new());
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.get(&1);
+        cache.get(&3);
+        assert_eq!(
+            cache.stats_delta(),
+            CacheStatsDelta { hits: 1, misses: 1, evictions: 0 }
+        );
+
+        cache.insert(3, 3);
+        cache.insert(4, 4);
+        cache.get(&4);
+        cache.get(&4);
+        cache.get(&1);
+        assert_eq!(
+            cache.stats_delta(),
+            CacheStatsDelta { hits: 2, misses: 1, evictions: 2 }
+        );
+        assert_eq!(cache.stats_delta(), CacheStatsDelta::default());
+        assert_eq!(cache.stats().hits, 3);
+    }
+
+    #[test]
+    fn test_suppress_prefetch_silences_region() {
+        let mut cache = LruCache::with_custom_prefetch(8, Box::new(PingPongPrefetch));
+        for key in 0..4 {
+            cache.insert(key, key);
+        }
+        cache.get(&0);
+        let before = cache.prefetch_stats().predictions_made;
+        assert!(before > 0);
+
+        {
+            let mut scan = cache.suppress_prefetch();
+            assert!(!scan.prefetch_enabled());
+            for key in 0..4 {
+                scan.get(&key);
+            }
+            // Nested guards restore the setting of the outer one
+            scan.suppress_prefetch().get(&1);
+            assert!(!scan.prefetch_enabled());
+            assert_eq!(scan.prefetch_stats().predictions_made, before);
+        }
+
+        assert!(cache.prefetch_enabled());
+        cache.get(&2);
+        assert_eq!(cache.prefetch_stats().predictions_made, before + 2);
+
+        cache.set_prefetch_enabled(false);
+        cache.get(&3);
+        assert_eq!(cache.prefetch_stats().predictions_made, before + 2);
+    }
+
+    #[test]
+    fn test_try_get_propagates_miss_with_key() {
+        fn sum(cache: &mut LruCache<i32, i32>, a: i32, b: i32) -> Result<i32, CacheMiss<i32>> {
+            let a = *cache.try_get(&a)?;
+            let b = *cache.try_get(&b)?;
+            Ok(a + b)
+        }
+
+        let mut cache = LruCache::new(4);
+        cache.insert(1, 10);
+        cache.insert(2, 20);
+        assert_eq!(sum(&mut cache, 1, 2), Ok(30));
+        let err = sum(&mut cache, 1, 7).unwrap_err();
+        assert_eq!(err.key, 7);
+        assert_eq!(err.to_string(), "key 7 not found in cache");
+
+        assert_eq!(cache.try_remove(&2), Ok(20));
+        assert_eq!(cache.try_remove(&2), Err(CacheMiss { key: 2 }));
+    }
+
+    #[test]
+    fn test_prefetch_efficiency_counts_used_prefetches() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(16), PingPongPrefetch)
+            .with_prefetch_fn(counting_loader(&loads));
+
+        // Each access prefetches the next key: 1, 2 and 3 are placed, and
+        // 1 and 2 are read
+        cache.get(&0);
+        cache.get(&1);
+        cache.get(&2);
+        // Misses elsewhere prefetch 101 and 201, never read
+        cache.get(&100);
+        cache.get(&200);
+        // Warmed keys count too; 50 is read, prefetching 51
+        cache.prefetch_keys(&[50, 60], counting_loader(&loads));
+        cache.get(&50);
+        // A second read of a prefetched key is an ordinary hit
+        cache.get(&1);
+
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.total_prefetches, 8);
+        assert_eq!(stats.prefetch_hits, 3);
+        assert_eq!(stats.prefetch_waste(), 5);
+        assert!((stats.prefetch_efficiency() - 3.0 / 8.0).abs() < 1e-12);
+
+        assert_eq!(CacheStats::default().prefetch_efficiency(), 0.0);
+    }
+
+    #[test]
+    fn test_prefetch_hits_match_predicted_keys_requested() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(64), SequentialPrefetch::new())
+            .with_prefetch_fn(counting_loader(&loads));
+
+        // A scan with a gap: keys loaded before they were requested can only
+        // have been predicted
+        let requests: Vec<i32> = (0..20).chain(30..40).collect();
+        let mut predicted_then_requested = 0;
+        for key in &requests {
+            if loads.borrow().contains(key) {
+                predicted_then_requested += 1;
+            }
+            cache.get(key);
+        }
+        assert!(predicted_then_requested > 0);
+        assert_eq!(cache.stats().prefetch_hits, predicted_then_requested);
+        assert!(cache.stats().total_prefetches > predicted_then_requested);
+
+        // An explicit insert or remove takes a key out of the prefetched set
+        let hits = cache.stats().prefetch_hits;
+        let next = [40, 41].into_iter().find(|key| loads.borrow().contains(key)).unwrap();
+        cache.insert(next, 0);
+        cache.get(&next);
+        assert_eq!(cache.stats().prefetch_hits, hits);
+    }
+
+    #[test]
+    fn test_get_many_counts_each_key_and_prefetches_after_the_batch() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(64), SequentialPrefetch::new())
+            .with_prefetch_fn(counting_loader(&loads));
+
+        // A sequential batch is loaded in order, then prefetched past its end
+        let batch: Vec<i32> = (0..8).collect();
+        let values = cache.get_many(&batch);
+        assert_eq!(values, batch.iter().map(|&key| Some(key)).collect::<Vec<_>>());
+        assert_eq!(cache.stats().total_accesses, 8);
+        assert_eq!(cache.stats().misses, 8);
+        assert_eq!(loads.borrow()[..8], batch[..]);
+        assert!(batch.iter().all(|key| cache.cache.contains_key(key)));
+        assert!(cache.cache.contains_key(&8));
+        assert!(cache.stats().total_prefetches > 0);
+
+        // The next batch hits what the first one and prefetching placed
+        cache.insert_many(vec![(20, 200), (21, 210), (20, 201)]);
+        let values = cache.get_many(&[3, 8, 20, 21]);
+        assert_eq!(values, vec![Some(3), Some(8), Some(201), Some(210)]);
+        assert_eq!(cache.stats().total_accesses, 12);
+        assert_eq!(cache.stats().hits, 4);
+        assert_eq!(cache.stats().prefetch_hits, 1);
+    }
+
+    #[test]
+    fn test_access_distinguishes_prefetch_hits() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(4), PingPongPrefetch)
+            .with_prefetch_fn(counting_loader(&loads));
+
+        // Served by the loader, which also prefetches 2
+        assert_eq!(cache.access(&1), (Access::Miss, Some(1)));
+        assert_eq!(cache.access(&2), (Access::PrefetchHit, Some(2)));
+        // Only the first read of a prefetched key counts
+        assert_eq!(cache.access(&2), (Access::MainHit, Some(2)));
+        assert_eq!(cache.access(&1), (Access::MainHit, Some(1)));
+
+        cache.insert(10, 10);
+        assert_eq!(cache.access(&10).0, Access::MainHit);
+
+        // Policies report main-cache hits and misses directly
+        let mut lru = LruCache::new(2);
+        lru.insert(1, "one");
+        assert_eq!(lru.access(&1), (Access::MainHit, Some(&"one")));
+        assert_eq!(lru.access(&2), (Access::Miss, None));
+    }
+
+    #[test]
+    fn test_prefetch_sequential_single_slot_no_thrash() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(1), SequentialPrefetch::new())
+            .with_prefetch_fn(counting_loader(&loads));
+
+        for key in 0..10 {
+            loads.borrow_mut().clear();
+            cache.get(&key);
+            // Every key is loaded at most once within a single access
+            let mut seen = loads.borrow().clone();
+            seen.sort();
+            seen.dedup();
+            assert_eq!(seen.len(), loads.borrow().len());
+            assert_eq!(cache.len(), 1);
+        }
+    }
+
+    fn assert_insert_if_absent<C: CachePolicy<i32, String>>(mut cache: C) {
+        let (inserted, value) = cache.insert_if_absent_with(1, || "one".to_string());
+        assert!(inserted);
+        assert_eq!(value.map(String::as_str), Some("one"));
+
+        let (inserted, value) =
+            cache.insert_if_absent_with(1, || panic!("closure must not run for a present key"));
+        assert!(!inserted);
+        assert_eq!(value.map(String::as_str), Some("one"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_if_absent_with_every_policy() {
+        use crate::policies::*;
+        assert_insert_if_absent(LruCache::new(4));
+        assert_insert_if_absent(MruCache::new(4));
+        assert_insert_if_absent(FifoCache::new(4));
+        assert_insert_if_absent(LfuCache::new(4));
+        assert_insert_if_absent(RandomCache::new(4));
+        assert_insert_if_absent(ArcCache::new(4));
+        assert_insert_if_absent(ClockCache::new(4));
+        assert_insert_if_absent(TwoQCache::new(4));
+        assert_insert_if_absent(SlruCache::new(4));
+        assert_insert_if_absent(CarCache::new(4));
+        assert_insert_if_absent(SieveCache::new(4));
+        assert_insert_if_absent(LirsCache::new(4));
+        assert_insert_if_absent(S3FifoCache::new(4));
+    }
+
+    #[test]
+    fn test_insert_if_absent_with_is_a_single_access() {
+        use crate::policies::LfuCache;
+        let mut cache = LfuCache::new(2);
+        cache.insert_if_absent_with(1, || 1);
+        cache.insert(2, 2);
+        // Both keys were seen once, so the older key 1 is the LFU victim
+        cache.insert(3, 3);
+        assert!(!cache.contains_key(&1));
+        assert!(cache.contains_key(&2));
+    }
+
+    #[test]
+    fn test_insert_if_absent_with_rejected_value() {
+        let mut cache = LruCache::new(4);
+        cache.set_insert_transform(Box::new(|_, value: i32| (value >= 0).then_some(value)));
+        assert_eq!(cache.insert_if_absent_with(1, || -1), (true, None));
+        assert_eq!(cache.get_or_insert_with_ref(&1, || -1), None);
+        assert_eq!(cache.get_or_insert_with_ref(&1, || 1), Some(&1));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_fulgurance_resize_counts_evictions() {
+        let mut cache = FulgranceCache::new(LruCache::new(6), SequentialPrefetch::new());
+        for i in 0..6 {
+            cache.insert(i, i);
+        }
+        cache.get(&0);
+        cache.resize(2);
+        assert_eq!((cache.len(), cache.capacity()), (2, 2));
+        assert_eq!(cache.stats().evictions, 4);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&5), Some(5));
+        assert_eq!(cache.get(&4), None);
+
+        cache.resize(10);
+        assert_eq!(cache.stats().evictions, 4);
+    }
+
+    #[test]
+    fn test_fulgurance_get_or_insert_with_reads_through() {
+        struct SelfFirstPrefetch;
+        impl PrefetchStrategy<i32> for SelfFirstPrefetch {
+            fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
+                vec![*accessed_key, accessed_key + 1]
+            }
+            fn update_access_pattern(&mut self, _key: &i32) {}
+            fn reset(&mut self) {}
+        }
+
+        let mut cache = FulgranceCache::new(LruCache::new(2), SequentialPrefetch::new());
+        let mut computed = 0;
+        for _ in 0..3 {
+            let value = cache.get_or_insert_with(1, || {
+                computed += 1;
+                10
+            });
+            assert_eq!(value, Some(&10));
+        }
+        assert_eq!(computed, 1);
+        assert_eq!((cache.stats().hits, cache.stats().misses), (2, 1));
+
+        cache.get_or_insert_with(2, || 20);
+        cache.get_or_insert_with(3, || 30);
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.get(&1), None);
+
+        // The closure wins over the loader for the requested key, while
+        // predicted keys still come from the loader
+        let mut cache = FulgranceCache::new(LruCache::new(8), SelfFirstPrefetch).with_prefetch_fn(|k: &i32| Some(k * 100));
+        assert_eq!(cache.get_or_insert_with(5, || -5), Some(&-5));
+        assert_eq!(cache.get(&6), Some(600));
+        assert_eq!(cache.get(&5), Some(-5));
+
+        // A byte-bounded insert can push out several entries below the entry capacity
+        let mut lru = LruCache::new(8);
+        lru.set_byte_capacity(10, Box::new(|_: &i32, value: &usize| *value));
+        let mut cache = FulgranceCache::new(lru, crate::prefetch::NoPrefetch);
+        cache.get_or_insert_with(1, || 4);
+        cache.get_or_insert_with(2, || 4);
+        assert_eq!(cache.get_or_insert_with(3, || 8), Some(&8));
+        assert_eq!((cache.len(), cache.stats().evictions), (1, 2));
+
+        // A value the policy won't keep is reported instead of panicking
+        assert_eq!(cache.get_or_insert_with(4, || 11), None);
+        assert_eq!(cache.get(&4), None);
+    }
+
+    #[test]
+    fn test_resize_shrinks_every_policy() {
+        for &policy in PolicyType::all() {
+            let mut cache = create_cache_policy::<i32, i32>(policy, 10);
+            for i in 0..10 {
+                cache.insert(i, i);
+                cache.get(&i);
+            }
+            cache.resize(4);
+            assert!(cache.len() <= 4, "{} kept {} entries", policy.name(), cache.len());
+            assert_eq!(cache.capacity(), 4);
+
+            cache.resize(12);
+            assert_eq!(cache.capacity(), 12);
+            assert!(cache.len() <= 4);
+        }
+    }
+
+    #[test]
+    fn test_prefetch_keys_warms_cache() {
+        let loads = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = FulgranceCache::new(LruCache::new(4), SequentialPrefetch::new());
+        cache.insert(0, 0);
+
+        let keys = [0, 10, 20, 30, 40, 50];
+        assert_eq!(cache.prefetch_keys(&keys, counting_loader(&loads)), 3);
+        assert_eq!(cache.stats().explicit_prefetch_loads, 3);
+        // Key 0 was already resident and keys past the capacity are skipped
+        assert_eq!(*loads.borrow(), vec![10, 20, 30]);
+
+        for key in &keys[..4] {
+            assert_eq!(cache.get(key), Some(*key));
+        }
+        assert_eq!(cache.stats().hits, 4);
+        assert_eq!(cache.stats().misses, 0);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_ref_clones_only_on_miss() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(PartialEq, Eq, Hash)]
+        struct CountedKey(u32);
+        impl Clone for CountedKey {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::SeqCst);
+                CountedKey(self.0)
+            }
+        }
+
+        let mut cache = crate::policies::FifoCache::new(4);
+        let key = CountedKey(7);
+        assert_eq!(cache.get_or_insert_with_ref(&key, || 70), Some(&70));
+        let after_miss = CLONES.load(Ordering::SeqCst);
+        assert!(after_miss >= 1);
+
+        for _ in 0..10 {
+            assert_eq!(cache.get_or_insert_with_ref(&key, || unreachable!()), Some(&70));
+        }
+        assert_eq!(CLONES.load(Ordering::SeqCst), after_miss);
+    }
+
+    #[test]
+    fn test_sampled_stats_estimate_hit_ratio() {
+        use crate::prefetch::NoPrefetch;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut exact = FulgranceCache::new(LruCache::new(10), NoPrefetch);
+        let mut sampled = FulgranceCache::new(LruCache::new(10), NoPrefetch);
+        sampled.set_stats_sampling(10);
+        for key in 0..10 {
+            exact.insert(key, key);
+            sampled.insert(key, key);
+        }
+
+        // 70% of reads hit the resident keys, the rest miss
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..10_000 {
+            let key = if rng.gen_bool(0.7) { rng.gen_range(0..10) } else { rng.gen_range(100..1000) };
+            exact.get(&key);
+            sampled.get(&key);
+        }
+
+        assert_eq!(exact.stats().total_accesses, 10_000);
+        assert_eq!(sampled.stats().total_accesses, 1_000);
+        assert!((exact.hit_ratio() - 0.7).abs() < 0.01);
+        let error = (sampled.hit_ratio() - exact.hit_ratio()).abs();
+        assert!(error < 0.05, "sampled ratio off by {}", error);
+    }
+
+    #[test]
+    fn test_stat_history_records_bounded_snapshots() {
+        use crate::prefetch::NoPrefetch;
+
+        let mut cache = FulgranceCache::new(LruCache::new(50), NoPrefetch).with_prefetch_fn(|k: &i32| Some(*k));
+        cache.enable_stat_history(10, 5);
+
+        // Twenty keys in a loop: the first pass misses, everything after hits
+        for step in 0..100 {
+            cache.get(&(step % 20));
+        }
+
+        let history = cache.stat_history();
+        assert_eq!(history.len(), 5);
+        let accesses: Vec<u64> = history.iter().map(|s| s.total_accesses).collect();
+        assert_eq!(accesses, vec![60, 70, 80, 90, 100]);
+        assert!(history.windows(2).all(|pair| pair[1].hit_ratio() > pair[0].hit_ratio()));
+        assert_eq!(history[4].hits, 80);
+
+        cache.reset_stats();
+        assert!(cache.stat_history().is_empty());
+    }
+
+    #[test]
+    fn test_remove_variants_disagree_only_on_prefetched_keys() {
+        let mut cache = LruCache::with_custom_prefetch(4, Box::new(PingPongPrefetch));
+        cache.set_prefetch_loader(Box::new(|key| Some(key * 10)));
+        cache.insert(1, 10);
+        cache.insert(3, 30);
+        // 2 is staged in the prefetch buffer, 1 and 3 are resident
+        cache.get(&1);
+        assert!(!cache.contains_key(&2));
+
+        assert_eq!(cache.remove_from_cache_only(&2), None);
+        assert_eq!(cache.remove(&2), Some(20));
+        assert_eq!(cache.remove(&2), None);
+
+        assert_eq!(cache.remove_from_cache_only(&1), Some(10));
+        assert_eq!(cache.remove(&3), Some(30));
+        assert!(cache.is_empty());
+
+        for policy in PolicyType::all() {
+            let mut cache = create_cache_policy::<i32, i32>(*policy, 8);
+            cache.insert(5, 50);
+            assert_eq!(cache.remove_from_cache_only(&6), None);
+            assert_eq!(cache.remove_from_cache_only(&5), Some(50), "{:?}", policy);
+            assert!(!cache.contains_key(&5));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::marker::PhantomData;
+
+use crate::CachePolicy;
+
+/// Cache wrapper holding a set of values per key
+///
+/// The wrapped policy stores each key's values as one `Vec` and evicts
+/// whole keys, so an evicted key takes all its values with it. Appending a
+/// value or removing one counts as a use of the key. With a value cap, a
+/// key keeps only its most recently added values.
+pub struct MultiCache<K, V, C>
+where
+    K: Clone,
+    V: PartialEq + Clone,
+    C: CachePolicy<K, Vec<V>>,
+{
+    inner: C,
+    value_cap: Option<usize>,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> MultiCache<K, V, C>
+where
+    K: Clone,
+    V: PartialEq + Clone,
+    C: CachePolicy<K, Vec<V>>,
+{
+    /// Wrap a policy; keys hold any number of values
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            value_cap: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Limit each key to its `cap` most recently added values
+    pub fn with_value_cap(mut self, cap: usize) -> Self {
+        self.value_cap = Some(cap.max(1));
+        self
+    }
+
+    /// Values of a key, oldest first
+    pub fn get(&mut self, key: &K) -> Option<&[V]> {
+        self.inner.get(key).map(Vec::as_slice)
+    }
+
+    /// Adds `value` to the key's set, evicting another key if the key is new
+    /// and the cache is full
+    ///
+    /// A value already present is left where it is. Past the value cap the
+    /// oldest value of the key is dropped.
+    pub fn insert(&mut self, key: K, value: V) {
+        let mut values = self.inner.get(&key).cloned().unwrap_or_default();
+        if values.contains(&value) {
+            return;
+        }
+        values.push(value);
+        if let Some(cap) = self.value_cap
+            && values.len() > cap
+        {
+            values.drain(..values.len() - cap);
+        }
+        self.inner.insert(key, values);
+    }
+
+    /// Removes one value of a key, returning whether it was present
+    ///
+    /// A key left without values is removed.
+    pub fn remove_value(&mut self, key: &K, value: &V) -> bool {
+        let Some(mut values) = self.inner.get(key).cloned() else {
+            return false;
+        };
+        let Some(pos) = values.iter().position(|v| v == value) else {
+            return false;
+        };
+        values.remove(pos);
+        if values.is_empty() {
+            self.inner.remove(key);
+        } else {
+            self.inner.insert(key.clone(), values);
+        }
+        true
+    }
+
+    /// Removes a key with all its values
+    pub fn remove(&mut self, key: &K) -> Option<Vec<V>> {
+        self.inner.remove(key)
+    }
+
+    /// Number of keys in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of keys
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_multi_evicting_key_drops_all_values() {
+        let mut cache = MultiCache::new(LruCache::new(2));
+        for id in [10, 11, 12, 11] {
+            cache.insert("rust", id);
+        }
+        assert_eq!(cache.get(&"rust"), Some(&[10, 11, 12][..]));
+
+        cache.insert("go", 20);
+        cache.insert("zig", 30);
+        assert_eq!(cache.get(&"rust"), None);
+        assert_eq!(cache.len(), 2);
+
+        // A key coming back starts from an empty set
+        cache.insert("rust", 13);
+        assert_eq!(cache.get(&"rust"), Some(&[13][..]));
+
+        assert!(cache.remove_value(&"rust", &13));
+        assert!(!cache.remove_value(&"rust", &13));
+        assert!(!cache.inner().contains_key(&"rust"));
+    }
+
+    #[test]
+    fn test_multi_value_cap_keeps_newest() {
+        let mut cache = MultiCache::new(LruCache::new(4)).with_value_cap(2);
+        for id in 1..=5 {
+            cache.insert("tag", id);
+        }
+        assert_eq!(cache.get(&"tag"), Some(&[4, 5][..]));
+
+        cache.remove_value(&"tag", &4);
+        cache.insert("tag", 6);
+        assert_eq!(cache.get(&"tag"), Some(&[5, 6][..]));
+    }
+}
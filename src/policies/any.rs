@@ -0,0 +1,222 @@
+use std::hash::Hash;
+
+use crate::{Access, CacheMetrics, CachePolicy, PrefetchStrategy, Weigher};
+use super::{
+    ArcCache, BenchmarkablePolicy, CarCache, ClockCache, FifoCache, LfuCache, LruCache, MruCache,
+    LirsCache, PolicyType, RandomCache, S3FifoCache, SieveCache, SlruCache, TwoQCache,
+};
+
+/// Runtime-selected policy dispatched by `match` instead of a vtable
+///
+/// A drop-in for the boxed policies of
+/// [`create_cache_policy`](super::create_cache_policy) when the policy is
+/// only known at runtime but the call overhead matters: each call is one
+/// predictable branch into the concrete policy, which the compiler can
+/// inline.
+pub enum AnyCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    Lru(LruCache<K, V>),
+    Mru(MruCache<K, V>),
+    Fifo(FifoCache<K, V>),
+    Lfu(LfuCache<K, V>),
+    Random(RandomCache<K, V>),
+    Arc(ArcCache<K, V>),
+    Clock(ClockCache<K, V>),
+    TwoQ(TwoQCache<K, V>),
+    Slru(SlruCache<K, V>),
+    Car(CarCache<K, V>),
+    Sieve(SieveCache<K, V>),
+    Lirs(LirsCache<K, V>),
+    S3Fifo(S3FifoCache<K, V>),
+}
+
+/// Evaluates `$body` with `$cache` bound to the wrapped policy
+macro_rules! dispatch {
+    ($self:expr, $cache:ident => $body:expr) => {
+        match $self {
+            AnyCache::Lru($cache) => $body,
+            AnyCache::Mru($cache) => $body,
+            AnyCache::Fifo($cache) => $body,
+            AnyCache::Lfu($cache) => $body,
+            AnyCache::Random($cache) => $body,
+            AnyCache::Arc($cache) => $body,
+            AnyCache::Clock($cache) => $body,
+            AnyCache::TwoQ($cache) => $body,
+            AnyCache::Slru($cache) => $body,
+            AnyCache::Car($cache) => $body,
+            AnyCache::Sieve($cache) => $body,
+            AnyCache::Lirs($cache) => $body,
+            AnyCache::S3Fifo($cache) => $body,
+        }
+    };
+}
+
+impl<K, V> AnyCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Creates the policy named by `policy_type` with no prefetch
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn new(policy_type: PolicyType, capacity: usize) -> Self {
+        match policy_type {
+            PolicyType::Lru => AnyCache::Lru(LruCache::new(capacity)),
+            PolicyType::Mru => AnyCache::Mru(MruCache::new(capacity)),
+            PolicyType::Fifo => AnyCache::Fifo(FifoCache::new(capacity)),
+            PolicyType::Lfu => AnyCache::Lfu(LfuCache::new(capacity)),
+            PolicyType::Random => AnyCache::Random(RandomCache::new(capacity)),
+            PolicyType::Arc => AnyCache::Arc(ArcCache::new(capacity)),
+            PolicyType::Clock => AnyCache::Clock(ClockCache::new(capacity)),
+            PolicyType::TwoQ => AnyCache::TwoQ(TwoQCache::new(capacity)),
+            PolicyType::Slru => AnyCache::Slru(SlruCache::new(capacity)),
+            PolicyType::Car => AnyCache::Car(CarCache::new(capacity)),
+            PolicyType::Sieve => AnyCache::Sieve(SieveCache::new(capacity)),
+            PolicyType::Lirs => AnyCache::Lirs(LirsCache::new(capacity)),
+            PolicyType::S3Fifo => AnyCache::S3Fifo(S3FifoCache::new(capacity)),
+        }
+    }
+
+    /// Creates the policy named by `policy_type` running `prefetch_strategy`
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn with_custom_prefetch(
+        policy_type: PolicyType,
+        capacity: usize,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+    ) -> Self {
+        match policy_type {
+            PolicyType::Lru => AnyCache::Lru(LruCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Mru => AnyCache::Mru(MruCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Fifo => AnyCache::Fifo(FifoCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Lfu => AnyCache::Lfu(LfuCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Random => AnyCache::Random(RandomCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Arc => AnyCache::Arc(ArcCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Clock => AnyCache::Clock(ClockCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::TwoQ => AnyCache::TwoQ(TwoQCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Slru => AnyCache::Slru(SlruCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Car => AnyCache::Car(CarCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Sieve => AnyCache::Sieve(SieveCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::Lirs => AnyCache::Lirs(LirsCache::with_custom_prefetch(capacity, prefetch_strategy)),
+            PolicyType::S3Fifo => AnyCache::S3Fifo(S3FifoCache::with_custom_prefetch(capacity, prefetch_strategy)),
+        }
+    }
+}
+
+impl<K, V> CachePolicy<K, V> for AnyCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn get(&mut self, key: &K) -> Option<&V> {
+        dispatch!(self, cache => cache.get(key))
+    }
+
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        dispatch!(self, cache => cache.access(key))
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        dispatch!(self, cache => cache.insert(key, value))
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        dispatch!(self, cache => cache.remove(key))
+    }
+
+    fn len(&self) -> usize {
+        dispatch!(self, cache => cache.len())
+    }
+
+    fn clear(&mut self) {
+        dispatch!(self, cache => cache.clear())
+    }
+
+    fn capacity(&self) -> usize {
+        dispatch!(self, cache => cache.capacity())
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        dispatch!(self, cache => cache.metrics())
+    }
+
+    fn reset_metrics(&mut self) {
+        dispatch!(self, cache => cache.reset_metrics())
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        dispatch!(self, cache => cache.resize(new_capacity))
+    }
+
+    fn set_byte_capacity(&mut self, bytes: usize, weigher: Weigher<K, V>) {
+        dispatch!(self, cache => cache.set_byte_capacity(bytes, weigher))
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        dispatch!(self, cache => cache.contains_key(key))
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        dispatch!(self, cache => cache.peek(key))
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        dispatch!(self, cache => cache.get_mut(key))
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        dispatch!(self, cache => cache.evict_one())
+    }
+
+    /// Dispatches once for the whole batch
+    fn contains_many(&self, keys: &[K]) -> Vec<bool> {
+        dispatch!(self, cache => cache.contains_many(keys))
+    }
+
+    fn compact(&mut self) {
+        dispatch!(self, cache => cache.compact())
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        dispatch!(self, cache => cache.set_prefetch_enabled(enabled))
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        dispatch!(self, cache => cache.prefetch_enabled())
+    }
+
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        dispatch!(self, cache => cache.for_each_entry(f))
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        dispatch!(self, cache => cache.for_each_value_mut(f))
+    }
+}
+
+impl<K, V> BenchmarkablePolicy<K, V> for AnyCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn policy_type(&self) -> PolicyType {
+        dispatch!(self, cache => cache.policy_type())
+    }
+
+    fn benchmark_name(&self) -> String {
+        dispatch!(self, cache => cache.benchmark_name())
+    }
+
+    fn reset_for_benchmark(&mut self) {
+        dispatch!(self, cache => cache.reset_for_benchmark())
+    }
+
+    fn reset_all(&mut self) {
+        dispatch!(self, cache => cache.reset_all())
+    }
+}
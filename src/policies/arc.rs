@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::Hash;
 use std::ptr::NonNull;
 use std::marker::PhantomData;
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType, walk_linked_list};
 
 /// Adaptive Replacement Cache (ARC) implementation with integrated prefetch strategies
 ///
@@ -19,10 +20,10 @@ where
     t1: HashMap<K, NonNull<Node<K, V>>>,
     /// T2: Frequent cache entries
     t2: HashMap<K, NonNull<Node<K, V>>>,
-    /// B1: Ghost entries from T1 (keys only)
-    b1: HashMap<K, ()>,
-    /// B2: Ghost entries from T2 (keys only)
-    b2: HashMap<K, ()>,
+    /// B1: Ghost entries from T1 (key -> eviction sequence number)
+    b1: HashMap<K, u64>,
+    /// B2: Ghost entries from T2 (key -> eviction sequence number)
+    b2: HashMap<K, u64>,
     
     /// Linked list heads and tails for each segment
     t1_head: Option<NonNull<Node<K, V>>>,
@@ -32,6 +33,11 @@ where
     
     /// Adaptation parameter (target size of T1)
     p: usize,
+    /// Value `p` starts from and returns to on `clear`, capped at the
+    /// current capacity after a shrink
+    initial_p: usize,
+    /// When set, ghost hits no longer move `p`
+    adaptation_frozen: bool,
     /// Total cache capacity
     capacity: usize,
     /// Current sizes
@@ -43,10 +49,39 @@ where
     prefetch_buffer: HashMap<K, V>,
     prefetch_buffer_size: usize,
     prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+
+    /// Hit and ghost-hit history used to estimate other capacities
+    ghost_tracker: GhostHitTracker,
     
     _marker: PhantomData<Box<Node<K, V>>>,
 }
 
+/// Snapshot of ARC's four lists, from [`ArcCache::debug_lists`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArcListsDebug<K>
+where
+    K: Hash + Eq,
+{
+    /// T1 keys, most recently used first
+    pub t1: Vec<K>,
+    /// T2 keys, most recently used first
+    pub t2: Vec<K>,
+    /// Ghost keys evicted from T1
+    pub b1: HashSet<K>,
+    /// Ghost keys evicted from T2
+    pub b2: HashSet<K>,
+}
+
 /// Internal node structure for the doubly-linked lists
 struct Node<K, V> {
     key: K,
@@ -75,6 +110,53 @@ impl<K, V> Node<K, V> {
     }
 }
 
+/// Hit history shared by the ghost-list policies (ARC, CAR)
+///
+/// Every demotion to a ghost list gets a sequence number, so on a ghost hit
+/// the number of evictions since that key left the cache is known. A hit at
+/// depth `d` would have been served by a cache `d + 1` entries larger.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GhostHitTracker {
+    /// Evictions into ghost lists so far (next sequence number)
+    evictions: u64,
+    /// Lookups through `get`
+    lookups: u64,
+    /// Lookups served from the resident lists
+    hits: u64,
+    /// Ghost hits keyed by eviction depth
+    ghost_hit_depths: BTreeMap<u64, u64>,
+}
+
+impl GhostHitTracker {
+    /// Sequence number to stamp on a key entering a ghost list
+    pub(crate) fn next_stamp(&mut self) -> u64 {
+        let stamp = self.evictions;
+        self.evictions += 1;
+        stamp
+    }
+
+    pub(crate) fn record_lookup(&mut self, hit: bool) {
+        self.lookups += 1;
+        if hit {
+            self.hits += 1;
+        }
+    }
+
+    pub(crate) fn record_ghost_hit(&mut self, stamp: u64) {
+        let depth = self.evictions.saturating_sub(stamp + 1);
+        *self.ghost_hit_depths.entry(depth).or_insert(0) += 1;
+    }
+
+    pub(crate) fn estimate(&self, capacity: usize, hypothetical_capacity: usize) -> Option<f64> {
+        if self.lookups == 0 || hypothetical_capacity < capacity {
+            return None;
+        }
+        let extra = (hypothetical_capacity - capacity) as u64;
+        let recovered: u64 = self.ghost_hit_depths.range(..extra).map(|(_, &n)| n).sum();
+        Some(((self.hits + recovered) as f64 / self.lookups as f64).min(1.0))
+    }
+}
+
 impl<K, V> ArcCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -89,8 +171,26 @@ where
     pub fn with_custom_prefetch(
         capacity: usize,
         prefetch_strategy: Box<dyn PrefetchStrategy<K>>
+    ) -> Self {
+        Self::with_initial_p(capacity, 0, prefetch_strategy)
+    }
+
+    /// Creates a new ARC cache whose target T1 size starts at `p`
+    ///
+    /// The default start of 0 favors T2 until ghost hits teach ARC
+    /// otherwise; a caller expecting recency to matter can seed a larger
+    /// target, e.g. `capacity / 2` for an even split. `clear` returns to
+    /// this value.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0 or `p` exceeds capacity
+    pub fn with_initial_p(
+        capacity: usize,
+        p: usize,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>
     ) -> Self {
         assert!(capacity > 0, "ARC cache capacity must be greater than 0");
+        assert!(p <= capacity, "ARC initial p {} exceeds capacity {}", p, capacity);
 
         Self {
             t1: HashMap::new(),
@@ -101,7 +201,9 @@ where
             t1_tail: None,
             t2_head: None,
             t2_tail: None,
-            p: 0,
+            p,
+            initial_p: p,
+            adaptation_frozen: false,
             capacity,
             t1_size: 0,
             t2_size: 0,
@@ -109,29 +211,204 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            prefetch_loader: None,
+            ghost_tracker: GhostHitTracker::default(),
             _marker: PhantomData,
         }
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries: T2, then T1, each most recently used first
+    ///
+    /// Which list loses entries first depends on the adaptive target `p`,
+    /// so the order only approximates survival. Ghost keys have no values
+    /// and are not included.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let list = |head: Option<NonNull<Node<K, V>>>| {
+            std::iter::successors(head, |node| unsafe { node.as_ref().next }).map(|node| {
+                let node = unsafe { &*node.as_ptr() };
+                (&node.key, &node.value)
+            })
+        };
+        list(self.t2_head).chain(list(self.t1_head))
+    }
+
     pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
         &self.prefetch_stats
     }
 
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: T1/T2 maps, lists and sizes agree,
+    /// nodes carry the right list tag, resident keys are not also ghosts,
+    /// `p` and the resident size respect capacity and the ghost lists stay
+    /// within the directory bound of `2 * capacity`
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for (list_type, map, head, tail, size) in [
+            (ListType::T1, &self.t1, self.t1_head, self.t1_tail, self.t1_size),
+            (ListType::T2, &self.t2, self.t2_head, self.t2_tail, self.t2_size),
+        ] {
+            if map.len() != size {
+                return Err(format!("{:?} map holds {} keys but size is {}", list_type, map.len(), size));
+            }
+            let nodes = unsafe {
+                walk_linked_list(&format!("{:?}", list_type), head, tail, size, |n| (n.prev, n.next))?
+            };
+            for node in nodes {
+                let node_ref = unsafe { node.as_ref() };
+                if node_ref.list_type != list_type {
+                    return Err(format!("node in {:?} is tagged {:?}", list_type, node_ref.list_type));
+                }
+                if map.get(&node_ref.key) != Some(&node) {
+                    return Err(format!("{:?} map entry does not point at its list node", list_type));
+                }
+                if self.b1.contains_key(&node_ref.key) || self.b2.contains_key(&node_ref.key) {
+                    return Err(format!("resident {:?} key is also a ghost", list_type));
+                }
+            }
+        }
+        if self.t1_size + self.t2_size > self.capacity {
+            return Err(format!("T1 + T2 = {} exceeds capacity {}", self.t1_size + self.t2_size, self.capacity));
+        }
+        if self.p > self.capacity {
+            return Err(format!("p {} exceeds capacity {}", self.p, self.capacity));
+        }
+        let directory = self.t1_size + self.t2_size + self.b1.len() + self.b2.len();
+        if directory > 2 * self.capacity {
+            return Err(format!("directory holds {} keys, over 2 * capacity {}", directory, self.capacity));
+        }
+        Ok(())
+    }
+
+    /// Marks `key` as recently used without reading its value
+    ///
+    /// A T1 entry is promoted to T2 and a T2 entry moves to its front,
+    /// exactly as on a hit. Runs no prefetch and leaves the hit history
+    /// untouched. Returns whether the key was resident.
+    pub fn touch(&mut self, key: &K) -> bool {
+        if let Some(node_ptr) = self.t1.remove(key) {
+            unsafe {
+                self.remove_from_list(node_ptr);
+                self.t2.insert(key.clone(), node_ptr);
+                self.add_to_front(node_ptr, ListType::T2);
+            }
+            self.t1_size -= 1;
+            self.t2_size += 1;
+            return true;
+        }
+        if let Some(&node_ptr) = self.t2.get(key) {
+            unsafe {
+                self.remove_from_list(node_ptr);
+                self.add_to_front(node_ptr, ListType::T2);
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Dumps the contents of T1, T2, B1 and B2 for tests and debugging
+    pub fn debug_lists(&self) -> ArcListsDebug<K> {
+        ArcListsDebug {
+            t1: Self::list_keys(self.t1_head),
+            t2: Self::list_keys(self.t2_head),
+            b1: self.b1.keys().cloned().collect(),
+            b2: self.b2.keys().cloned().collect(),
+        }
+    }
+
+    /// Keys of the list starting at `head`, in list order
+    fn list_keys(head: Option<NonNull<Node<K, V>>>) -> Vec<K> {
+        let mut keys = Vec::new();
+        let mut current = head;
+        while let Some(node) = current {
+            let node_ref = unsafe { node.as_ref() };
+            keys.push(node_ref.key.clone());
+            current = node_ref.next;
+        }
+        keys
+    }
+
+    /// Current target size of T1
+    pub fn p(&self) -> usize {
+        self.p
+    }
+
+    /// Override the target size of T1 (clamped to capacity)
+    pub fn set_p(&mut self, p: usize) {
+        self.p = p.min(self.capacity);
+    }
+
+    /// Stop (or resume) adapting `p` on ghost hits
+    ///
+    /// A frozen ARC behaves as a fixed-split cache, which is handy for
+    /// comparing against the adaptive behavior.
+    pub fn freeze_adaptation(&mut self, frozen: bool) {
+        self.adaptation_frozen = frozen;
+    }
+
+    /// Whether adaptation of `p` is currently frozen
+    pub fn is_adaptation_frozen(&self) -> bool {
+        self.adaptation_frozen
+    }
+
+    /// Estimate the hit ratio this cache would reach at another capacity
+    ///
+    /// This is an approximation built from ghost hits: a miss whose key was
+    /// still in B1/B2 within `hypothetical_capacity - capacity` evictions
+    /// would have been a hit in the larger cache. Returns `None` before any
+    /// lookup or when `hypothetical_capacity` is below the current capacity,
+    /// since ghosts say nothing about a smaller cache.
+    pub fn estimated_hit_ratio_at(&self, hypothetical_capacity: usize) -> Option<f64> {
+        self.ghost_tracker.estimate(self.capacity, hypothetical_capacity)
+    }
+
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
             if !self.t1.contains_key(&predicted_key) && 
                !self.t2.contains_key(&predicted_key) &&
                !self.prefetch_buffer.contains_key(&predicted_key) {
-                // Prefetch prediction made but not loaded for demonstration
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
             }
         }
         
@@ -204,47 +481,66 @@ where
     }
 
     /// Replace operation for ARC algorithm
-    fn replace(&mut self, in_b2: bool) {
+    ///
+    /// Falls back to T1 when T2 is empty so a victim is always found.
+    /// Returns the evicted entry.
+    fn replace(&mut self, in_b2: bool) -> Option<(K, V)> {
         if self.t1_size >= 1 && 
-           ((in_b2 && self.t1_size == self.p) || self.t1_size > self.p) {
-            // Demote LRU page in T1 to B1
-            if let Some(lru_ptr) = self.t1_tail {
-                unsafe {
-                    let lru_node = Box::from_raw(lru_ptr.as_ptr());
-                    let key = lru_node.key.clone();
-                    
-                    self.t1.remove(&key);
-                    self.b1.insert(key, ());
-                    
-                    self.t1_tail = lru_node.prev;
-                    if let Some(mut new_tail) = self.t1_tail {
-                        new_tail.as_mut().next = None;
-                    } else {
-                        self.t1_head = None;
-                    }
-                    self.t1_size -= 1;
+           ((in_b2 && self.t1_size == self.p) || self.t1_size > self.p || self.t2_size == 0) {
+            self.demote_t1_lru()
+        } else {
+            self.demote_t2_lru()
+        }
+    }
+
+    /// Demote LRU page in T1 to B1
+    fn demote_t1_lru(&mut self) -> Option<(K, V)> {
+        if let Some(lru_ptr) = self.t1_tail {
+            unsafe {
+                let lru_node = Box::from_raw(lru_ptr.as_ptr());
+                let key = lru_node.key.clone();
+                
+                self.t1.remove(&key);
+                let stamp = self.ghost_tracker.next_stamp();
+                self.b1.insert(key.clone(), stamp);
+                
+                self.t1_tail = lru_node.prev;
+                if let Some(mut new_tail) = self.t1_tail {
+                    new_tail.as_mut().next = None;
+                } else {
+                    self.t1_head = None;
                 }
+                self.t1_size -= 1;
+                self.metrics.evictions += 1;
+                return Some((key, lru_node.value));
             }
-        } else {
-            // Demote LRU page in T2 to B2
-            if let Some(lru_ptr) = self.t2_tail {
-                unsafe {
-                    let lru_node = Box::from_raw(lru_ptr.as_ptr());
-                    let key = lru_node.key.clone();
-                    
-                    self.t2.remove(&key);
-                    self.b2.insert(key, ());
-                    
-                    self.t2_tail = lru_node.prev;
-                    if let Some(mut new_tail) = self.t2_tail {
-                        new_tail.as_mut().next = None;
-                    } else {
-                        self.t2_head = None;
-                    }
-                    self.t2_size -= 1;
+        }
+        None
+    }
+
+    /// Demote LRU page in T2 to B2
+    fn demote_t2_lru(&mut self) -> Option<(K, V)> {
+        if let Some(lru_ptr) = self.t2_tail {
+            unsafe {
+                let lru_node = Box::from_raw(lru_ptr.as_ptr());
+                let key = lru_node.key.clone();
+                
+                self.t2.remove(&key);
+                let stamp = self.ghost_tracker.next_stamp();
+                self.b2.insert(key.clone(), stamp);
+                
+                self.t2_tail = lru_node.prev;
+                if let Some(mut new_tail) = self.t2_tail {
+                    new_tail.as_mut().next = None;
+                } else {
+                    self.t2_head = None;
                 }
+                self.t2_size -= 1;
+                self.metrics.evictions += 1;
+                return Some((key, lru_node.value));
             }
         }
+        None
     }
 
     /// Update adaptation parameter p
@@ -263,6 +559,10 @@ where
     V: Clone,
 {
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -272,55 +572,47 @@ where
             }
         }
 
-        // Check T1
-        if let Some(&node_ptr) = self.t1.get(key) {
-            unsafe {
-                // Move from T1 to T2 (promote to frequent)
-                self.remove_from_list(node_ptr);
-                self.t1.remove(key);
-                self.t2.insert(key.clone(), node_ptr);
-                self.add_to_front(node_ptr, ListType::T2);
-                self.t1_size -= 1;
-                self.t2_size += 1;
-
-                self.perform_prefetch(key);
-                return Some(&node_ptr.as_ref().value);
-            }
-        }
-
-        // Check T2
-        if let Some(&node_ptr) = self.t2.get(key) {
-            unsafe {
-                // Move to front of T2
-                self.remove_from_list(node_ptr);
-                self.add_to_front(node_ptr, ListType::T2);
-
-                self.perform_prefetch(key);
-                return Some(&node_ptr.as_ref().value);
-            }
+        // A hit in T1 promotes to T2, a hit in T2 moves to its front
+        if let Some(&node_ptr) = self.t1.get(key).or(self.t2.get(key)) {
+            self.ghost_tracker.record_lookup(true);
+            self.touch(key);
+            self.perform_prefetch(key);
+            return Some(unsafe { &node_ptr.as_ref().value });
         }
 
+        self.ghost_tracker.record_lookup(false);
         None
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         // Remove from prefetch buffer if exists
         self.prefetch_buffer.remove(&key);
 
-        // Case 1: x is in T1 or T2 (cache hit)
+        // Case 1: x is in T1 or T2 (cache hit), promoted like a hit in get()
         if let Some(&node_ptr) = self.t1.get(&key).or(self.t2.get(&key)) {
             unsafe {
                 (*node_ptr.as_ptr()).value = value;
-                // This will be handled by get() call that typically follows
             }
+            self.touch(&key);
             return;
         }
 
         // Case 2: x is in B1 (recent history hit)
-        if self.b1.contains_key(&key) {
+        if let Some(&stamp) = self.b1.get(&key) {
+            self.ghost_tracker.record_ghost_hit(stamp);
             // Adapt: increase p
-            let delta = (self.b2.len() as f32 / self.b1.len() as f32).ceil() as i32;
-            self.update_p(delta);
+            if !self.adaptation_frozen {
+                let delta = ((self.b2.len() as f32 / self.b1.len().max(1) as f32).ceil() as i32).max(1);
+                self.update_p(delta);
+            }
             
             // Replace
             self.replace(false);
@@ -333,14 +625,18 @@ where
             self.t2.insert(key, node_ptr);
             unsafe { self.add_to_front(node_ptr, ListType::T2); }
             self.t2_size += 1;
+            self.metrics.insertions += 1;
             return;
         }
 
         // Case 3: x is in B2 (frequent history hit)
-        if self.b2.contains_key(&key) {
+        if let Some(&stamp) = self.b2.get(&key) {
+            self.ghost_tracker.record_ghost_hit(stamp);
             // Adapt: decrease p
-            let delta = (self.b1.len() as f32 / self.b2.len() as f32).ceil() as i32;
-            self.update_p(-delta);
+            if !self.adaptation_frozen {
+                let delta = ((self.b1.len() as f32 / self.b2.len().max(1) as f32).ceil() as i32).max(1);
+                self.update_p(-delta);
+            }
             
             // Replace
             self.replace(true);
@@ -353,6 +649,7 @@ where
             self.t2.insert(key, node_ptr);
             unsafe { self.add_to_front(node_ptr, ListType::T2); }
             self.t2_size += 1;
+            self.metrics.insertions += 1;
             return;
         }
 
@@ -380,16 +677,28 @@ where
                 }
             }
         } else {
-            // Cache is full
+            // Cache is full: drop a ghost first so T1 + B1 stays within
+            // capacity and the whole directory within 2 * capacity
+            if self.t1_size + self.b1.len() >= self.capacity
+                && let Some(key_to_remove) = self.b1.keys().next().cloned()
+            {
+                self.b1.remove(&key_to_remove);
+            } else if total_cache + total_history >= 2 * self.capacity
+                && let Some(key_to_remove) = self.b2.keys().next().cloned()
+            {
+                self.b2.remove(&key_to_remove);
+            }
             self.replace(false);
         }
         
         self.t1.insert(key, node_ptr);
         unsafe { self.add_to_front(node_ptr, ListType::T1); }
         self.t1_size += 1;
+        self.metrics.insertions += 1;
     }
 
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         // Check prefetch buffer first
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
@@ -430,6 +739,89 @@ where
         self.capacity
     }
 
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "ARC cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        self.p = self.p.min(new_capacity);
+
+        // Demote from whichever list exceeds its target, falling back to the
+        // other list when one is empty
+        while self.t1_size + self.t2_size > self.capacity {
+            if self.t1_size > 0 && (self.t1_size > self.p || self.t2_size == 0) {
+                self.demote_t1_lru();
+            } else {
+                self.demote_t2_lru();
+            }
+        }
+
+        // T1 and its ghosts together stay within the capacity
+        while self.t1_size + self.b1.len() > self.capacity {
+            let Some(key) = self.b1.keys().next().cloned() else { break };
+            self.b1.remove(&key);
+        }
+
+        // Ghost history never needs to track more than `capacity` keys
+        while self.b1.len() + self.b2.len() > self.capacity {
+            if let Some(key) = self.b1.keys().next().cloned() {
+                self.b1.remove(&key);
+            } else if let Some(key) = self.b2.keys().next().cloned() {
+                self.b2.remove(&key);
+            }
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        if self.t1_size + self.t2_size == 0 {
+            return None;
+        }
+        self.replace(false)
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.t1.contains_key(key) || self.t2.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let node_ptr = *self.t1.get(key).or(self.t2.get(key))?;
+        Some(unsafe { &mut (*node_ptr.as_ptr()).value })
+    }
+
+    /// Leaves T1 entries in T1, unlike `get`
+    fn peek(&self, key: &K) -> Option<&V> {
+        let node_ptr = self.t1.get(key).or(self.t2.get(key))?;
+        Some(unsafe { &node_ptr.as_ref().value })
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, node) in self.t1.iter().chain(self.t2.iter()) {
+            unsafe { f(key, &node.as_ref().value) }
+        }
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, node) in self.t1.iter().chain(self.t2.iter()) {
+            unsafe { f(key, &mut (*node.as_ptr()).value) }
+        }
+    }
+
     fn clear(&mut self) {
         // Deallocate all T1 nodes
         while let Some(node_ptr) = self.t1_head {
@@ -457,7 +849,11 @@ where
         self.t2_tail = None;
         self.t1_size = 0;
         self.t2_size = 0;
-        self.p = 0;
+        self.ghost_tracker = GhostHitTracker::default();
+        // A frozen split is a deliberate setting, keep it across clears
+        if !self.adaptation_frozen {
+            self.p = self.initial_p.min(self.capacity);
+        }
         self.prefetch_buffer.clear();
     }
 }
@@ -479,6 +875,15 @@ where
         self.clear();
         self.reset_prefetch_stats();
     }
+
+    /// Also unfreezes adaptation and drops `p` back to its initial value,
+    /// which `clear` keeps for a frozen cache
+    fn reset_all(&mut self) {
+        self.reset_for_benchmark();
+        self.reset_metrics();
+        self.adaptation_frozen = false;
+        self.p = self.initial_p.min(self.capacity);
+    }
 }
 
 impl<K, V> Drop for ArcCache<K, V>
@@ -516,6 +921,22 @@ impl ArcCache<usize, String> {
     }
 }
 
+impl ArcCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "ARC cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl ArcCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "ARC cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 unsafe impl<K, V> Send for ArcCache<K, V>
 where
     K: Hash + Eq + Clone + Send,
@@ -529,3 +950,241 @@ where
     V: Clone + Sync,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loops over more keys than fit, touching each twice so entries reach
+    /// T2 and evictions keep producing ghost hits in both B1 and B2
+    fn ghost_heavy_workload(cache: &mut ArcCache<i32, i32>) {
+        for round in 0..20 {
+            for key in 0..12 {
+                cache.insert(key, round);
+                cache.get(&key);
+            }
+            for key in 0..3 {
+                cache.insert(100 + round * 3 + key, round);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arc_estimated_hit_ratio_grows_with_capacity() {
+        let mut cache = ArcCache::new(50);
+        assert_eq!(cache.estimated_hit_ratio_at(100), None);
+
+        // Touch each key twice so it settles in T2, overflow by 30 keys,
+        // then come back for the ones pushed out. They were evicted about
+        // 30 evictions earlier, so a cache 50 entries larger keeps them.
+        for i in 0..80 {
+            cache.insert(i, i);
+            cache.get(&i);
+        }
+        for i in 0..30 {
+            if cache.get(&i).is_none() {
+                cache.insert(i, i);
+            }
+        }
+
+        let actual = cache.estimated_hit_ratio_at(50).unwrap();
+        let larger = cache.estimated_hit_ratio_at(100).unwrap();
+        assert!(larger > actual, "estimate {} not above actual {}", larger, actual);
+        assert!(larger <= 1.0);
+        assert_eq!(cache.estimated_hit_ratio_at(10), None);
+    }
+
+    #[test]
+    fn test_arc_set_p_clamped() {
+        let mut cache: ArcCache<i32, i32> = ArcCache::new(8);
+        cache.set_p(3);
+        assert_eq!(cache.p(), 3);
+        cache.set_p(100);
+        assert_eq!(cache.p(), 8);
+    }
+
+    #[test]
+    fn test_arc_initial_p_balances_warmup() {
+        // Six keys read twice land in T2, then a stream of one-off keys
+        // competes with them for the remaining room
+        fn warm(cache: &mut ArcCache<i32, i32>) {
+            for key in 0..6 {
+                cache.insert(key, key);
+                cache.get(&key);
+            }
+            for key in 100..110 {
+                cache.insert(key, key);
+            }
+        }
+
+        let mut default = ArcCache::new(8);
+        warm(&mut default);
+        let lists = default.debug_lists();
+        assert_eq!((lists.t1.len(), lists.t2.len()), (2, 6));
+
+        let mut seeded = ArcCache::with_initial_p(8, 4, Box::new(NoPrefetch));
+        assert_eq!(seeded.p(), 4);
+        warm(&mut seeded);
+        // T1 grows to its target before T2 gives up entries; the incoming
+        // key joins after the victim is chosen, so T1 ends one above it
+        let lists = seeded.debug_lists();
+        assert_eq!((lists.t1.len(), lists.t2.len()), (5, 3));
+
+        seeded.clear();
+        assert_eq!(seeded.p(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds capacity")]
+    fn test_arc_initial_p_above_capacity_panics() {
+        let _: ArcCache<i32, i32> = ArcCache::with_initial_p(8, 9, Box::new(NoPrefetch));
+    }
+
+    #[test]
+    fn test_arc_clear_after_shrink_caps_initial_p() {
+        let mut cache: ArcCache<i32, i32> = ArcCache::with_initial_p(10, 8, Box::new(NoPrefetch));
+        cache.resize(4);
+        cache.clear();
+        assert_eq!(cache.p(), 4);
+        cache.check_invariants().unwrap();
+
+        cache.reset_all();
+        assert_eq!(cache.p(), 4);
+        cache.check_invariants().unwrap();
+
+        cache.resize(10);
+        cache.clear();
+        assert_eq!(cache.p(), 8);
+    }
+
+    #[test]
+    fn test_arc_frozen_p_stays_fixed() {
+        // Sanity check: the workload moves p when adaptation is on
+        let mut adaptive = ArcCache::new(8);
+        adaptive.set_p(4);
+        ghost_heavy_workload(&mut adaptive);
+        assert_ne!(adaptive.p(), 4);
+
+        let mut frozen = ArcCache::new(8);
+        frozen.set_p(4);
+        frozen.freeze_adaptation(true);
+        ghost_heavy_workload(&mut frozen);
+        assert_eq!(frozen.p(), 4);
+        assert!(frozen.len() <= 8);
+    }
+
+    /// Asserts the exact list contents; ghosts are compared as sets
+    fn assert_lists(cache: &ArcCache<i32, i32>, t1: &[i32], t2: &[i32], b1: &[i32], b2: &[i32]) {
+        let lists = cache.debug_lists();
+        assert_eq!(lists.t1, t1);
+        assert_eq!(lists.t2, t2);
+        assert_eq!(lists.b1, b1.iter().copied().collect());
+        assert_eq!(lists.b2, b2.iter().copied().collect());
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_arc_debug_lists_follow_scripted_sequence() {
+        let mut cache = ArcCache::new(4);
+        for key in 1..=4 {
+            cache.insert(key, key);
+        }
+        assert_lists(&cache, &[4, 3, 2, 1], &[], &[], &[]);
+
+        // A second reference promotes to T2
+        cache.get(&1);
+        cache.get(&2);
+        assert_lists(&cache, &[4, 3], &[2, 1], &[], &[]);
+
+        // A miss on a full cache demotes T1's LRU page to B1
+        cache.insert(5, 5);
+        assert_lists(&cache, &[5, 4], &[2, 1], &[3], &[]);
+
+        // A B1 hit re-enters straight into T2, pushing out T1's LRU page
+        cache.insert(3, 3);
+        assert_lists(&cache, &[5], &[3, 2, 1], &[4], &[]);
+
+        cache.get(&5);
+        assert_lists(&cache, &[], &[5, 3, 2, 1], &[4], &[]);
+
+        // With T1 empty the victim comes from T2 and lands in B2
+        cache.insert(6, 6);
+        assert_lists(&cache, &[6], &[5, 3, 2], &[4], &[1]);
+
+        // A B2 hit re-enters T2, and T1 being over target gives up 6
+        cache.insert(1, 1);
+        assert_lists(&cache, &[], &[1, 5, 3, 2], &[4, 6], &[]);
+    }
+
+    #[test]
+    fn test_arc_insert_on_resident_key_counts_as_hit() {
+        let mut cache = ArcCache::new(4);
+        cache.insert(1, 0);
+        cache.insert(2, 0);
+        cache.insert(1, 1);
+        assert_lists(&cache, &[2], &[1], &[], &[]);
+
+        // Updated without ever being read, key 1 outlives a flood of new keys
+        for key in 10..200 {
+            cache.insert(key, 0);
+            if key % 8 == 0 {
+                cache.insert(1, key);
+            }
+            assert!(cache.check_invariants().is_ok());
+        }
+        assert_eq!(cache.peek(&1), Some(&192));
+        assert_eq!(cache.debug_lists().t2, vec![1]);
+    }
+
+    #[test]
+    fn test_arc_shrink_then_ghost_insert_keeps_p_sane() {
+        let mut cache = ArcCache::new(16);
+        ghost_heavy_workload(&mut cache);
+        let lists = cache.debug_lists();
+        assert!(!lists.b1.is_empty() || !lists.b2.is_empty());
+
+        cache.resize(3);
+        assert!(cache.p() <= 3);
+        assert!(cache.check_invariants().is_ok());
+        let lists = cache.debug_lists();
+        assert!(lists.t1.len() + lists.b1.len() <= 3);
+
+        // Re-inserting every remaining ghost adapts p without leaving [0, 3]
+        for key in lists.b1.iter().chain(lists.b2.iter()) {
+            cache.insert(*key, 0);
+            assert!(cache.p() <= 3);
+            assert!(cache.check_invariants().is_ok());
+        }
+        assert!(cache.len() <= 3);
+    }
+
+    #[test]
+    fn test_arc_ghost_hit_moves_p_by_at_least_one() {
+        let mut cache = ArcCache::new(2);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        assert_eq!(cache.p(), 0);
+
+        // B2 is empty, yet a B1 hit still grows the T1 target
+        assert!(cache.debug_lists().b1.contains(&1));
+        cache.insert(1, 1);
+        assert_eq!(cache.p(), 1);
+    }
+
+    #[test]
+    fn test_arc_contains_key_skips_ghosts() {
+        let mut cache = ArcCache::new(2);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        assert_eq!(cache.debug_lists().b1, [1].into_iter().collect());
+
+        // Probing the ghost neither reports it nor consumes the ghost hit
+        assert!(!cache.contains_key(&1));
+        assert!(cache.contains_key(&2) && cache.contains_key(&3));
+        assert_lists(&cache, &[3, 2], &[], &[1], &[]);
+        cache.insert(1, 1);
+        assert_eq!(cache.p(), 1);
+    }
+}
@@ -2,9 +2,11 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
+use super::lru::PrefetchLoader;
 use super::{BenchmarkablePolicy, PolicyType};
+use super::arc::GhostHitTracker;
 
 /// Clock with Adaptive Replacement (CAR) cache
 ///
@@ -22,16 +24,18 @@ where
     t1_map: HashMap<K, usize>,
     t1_hand: usize,
     t1_size: usize,
+    t1_free: Vec<usize>, // Indices of empty T1 slots
 
     // T2: Frequent entries (Clock-managed)
     t2: Vec<Option<CarEntry<K, V>>>,
     t2_map: HashMap<K, usize>,
     t2_hand: usize,
     t2_size: usize,
+    t2_free: Vec<usize>, // Indices of empty T2 slots
 
     // Ghost buffers store only keys, used for adaptation
-    b1: HashMap<K, ()>, // Ghost buffer for T1 evictions
-    b2: HashMap<K, ()>, // Ghost buffer for T2 evictions
+    b1: HashMap<K, u64>, // Ghost buffer for T1 evictions (key -> eviction sequence)
+    b2: HashMap<K, u64>, // Ghost buffer for T2 evictions (key -> eviction sequence)
 
     // Adaptation parameter (target size of T1)
     p: usize,
@@ -45,6 +49,19 @@ where
     prefetch_buffer: HashMap<K, V>,
     prefetch_buffer_size: usize,
     prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+
+    // Hit and ghost-hit history used to estimate other capacities
+    ghost_tracker: GhostHitTracker,
 
     _marker: PhantomData<(K, V)>,
 }
@@ -59,6 +76,9 @@ struct CarEntry<K, V> {
     list_type: ListType, // Indicates whether in T1 or T2
 }
 
+/// A repacked clock array and its key-to-slot map
+type PackedClock<K, V> = (Vec<Option<CarEntry<K, V>>>, HashMap<K, usize>);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ListType {
     T1,
@@ -101,11 +121,13 @@ where
             t1_map: HashMap::new(),
             t1_hand: 0,
             t1_size: 0,
+            t1_free: Self::all_free(t1_capacity, 0),
 
             t2: vec![None; t2_capacity],
             t2_map: HashMap::new(),
             t2_hand: 0,
             t2_size: 0,
+            t2_free: Self::all_free(t2_capacity, 0),
 
             b1: HashMap::new(),
             b2: HashMap::new(),
@@ -118,32 +140,128 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            prefetch_loader: None,
+            ghost_tracker: GhostHitTracker::default(),
 
             _marker: PhantomData,
         }
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries: T2 slots, then T1 slots
+    ///
+    /// Reference bits decide which entry the clock hands take next, so the
+    /// order only approximates survival. Empty slots and ghost keys are
+    /// skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.t2.iter().chain(self.t1.iter()).flatten().map(|entry| (&entry.key, &entry.value))
+    }
+
     pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
         &self.prefetch_stats
     }
 
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: maps, occupied slots and sizes agree
+    /// for T1 and T2, `len()` equals `T1 + T2`, resident keys are not also
+    /// ghosts and capacity and ghost bounds are respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for (name, slots, map, size, free) in [
+            ("T1", &self.t1, &self.t1_map, self.t1_size, &self.t1_free),
+            ("T2", &self.t2, &self.t2_map, self.t2_size, &self.t2_free),
+        ] {
+            let occupied = slots.iter().filter(|slot| slot.is_some()).count();
+            if occupied != size || map.len() != size {
+                return Err(format!("{} slots {} / map {} / size {} disagree", name, occupied, map.len(), size));
+            }
+            if free.len() + occupied != slots.len() {
+                return Err(format!("{} free list holds {} of {} empty slots", name, free.len(), slots.len() - occupied));
+            }
+            if free.iter().any(|&index| !matches!(slots.get(index), Some(None))) {
+                return Err(format!("{} free list points at an occupied slot", name));
+            }
+            for (key, &index) in map {
+                match slots.get(index) {
+                    Some(Some(entry)) if &entry.key == key => {}
+                    _ => return Err(format!("{} map index {} does not hold its key", name, index)),
+                }
+                if self.b1.contains_key(key) || self.b2.contains_key(key) {
+                    return Err(format!("resident {} key is also a ghost", name));
+                }
+            }
+        }
+        if self.current_size != self.t1_size + self.t2_size {
+            return Err(format!(
+                "len {} but T1 + T2 = {}",
+                self.current_size, self.t1_size + self.t2_size
+            ));
+        }
+        if self.t1_size + self.t2_size > self.capacity {
+            return Err(format!("T1 + T2 = {} exceeds capacity {}", self.t1_size + self.t2_size, self.capacity));
+        }
+        if self.b1.len() > self.capacity || self.b2.len() > self.capacity {
+            return Err("ghost buffer exceeds capacity".to_string());
+        }
+        Ok(())
+    }
+
+    /// Estimate the hit ratio this cache would reach at another capacity
+    ///
+    /// Approximation from ghost hits, see [`ArcCache::estimated_hit_ratio_at`](super::ArcCache::estimated_hit_ratio_at).
+    pub fn estimated_hit_ratio_at(&self, hypothetical_capacity: usize) -> Option<f64> {
+        self.ghost_tracker.estimate(self.capacity, hypothetical_capacity)
+    }
+
     /// Execute prefetch prediction after an access
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
             if !self.t1_map.contains_key(&predicted_key)
                 && !self.t2_map.contains_key(&predicted_key)
                     && !self.prefetch_buffer.contains_key(&predicted_key)
             {
-                // Prediction stub (not actually loading entry)
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
             }
         }
         self.trim_prefetch_buffer();
@@ -169,12 +287,14 @@ where
     }
 
     /// Advance T1 hand (Clock algorithm)
+    ///
+    /// Sweeps up to twice around: when every reference bit is set, the
+    /// first pass clears them all and the second finds the victim.
     fn advance_t1_hand(&mut self) -> Option<usize> {
         if self.t1_size == 0 {
             return None;
         }
-        let start_pos = self.t1_hand;
-        loop {
+        for _ in 0..2 * self.t1.len() {
             let cur = self.t1_hand;
             self.t1_hand = (self.t1_hand + 1) % self.t1.len();
 
@@ -185,20 +305,19 @@ where
                     return Some(cur);
                 }
             }
-            if self.t1_hand == start_pos {
-                break;
-            }
         }
         None
     }
 
     /// Advance T2 hand (Clock algorithm)
+    ///
+    /// Sweeps up to twice around: when every reference bit is set, the
+    /// first pass clears them all and the second finds the victim.
     fn advance_t2_hand(&mut self) -> Option<usize> {
         if self.t2_size == 0 {
             return None;
         }
-        let start_pos = self.t2_hand;
-        loop {
+        for _ in 0..2 * self.t2.len() {
             let cur = self.t2_hand;
             self.t2_hand = (self.t2_hand + 1) % self.t2.len();
 
@@ -209,45 +328,113 @@ where
                     return Some(cur);
                 }
             }
-            if self.t2_hand == start_pos {
-                break;
-            }
         }
         None
     }
 
-    /// Find empty slots for T1 or T2
-    fn find_empty_t1_slot(&self) -> Option<usize> {
-        self.t1.iter().position(|s| s.is_none())
+    /// Claim an empty slot for T1 or T2 from its free list
+    fn find_empty_t1_slot(&mut self) -> Option<usize> {
+        self.t1_free.pop()
+    }
+    fn find_empty_t2_slot(&mut self) -> Option<usize> {
+        self.t2_free.pop()
+    }
+
+    /// Empty a T1 or T2 slot, returning it to the free list
+    fn take_t1_slot(&mut self, index: usize) -> Option<CarEntry<K, V>> {
+        let entry = self.t1[index].take();
+        if entry.is_some() {
+            self.t1_free.push(index);
+        }
+        entry
     }
-    fn find_empty_t2_slot(&self) -> Option<usize> {
-        self.t2.iter().position(|s| s.is_none())
+    fn take_t2_slot(&mut self, index: usize) -> Option<CarEntry<K, V>> {
+        let entry = self.t2[index].take();
+        if entry.is_some() {
+            self.t2_free.push(index);
+        }
+        entry
+    }
+
+    /// Free list for slots `used..slots`, lowest index handed out first
+    fn all_free(slots: usize, used: usize) -> Vec<usize> {
+        (used..slots).rev().collect()
     }
 
     /// Replacement procedure (eviction) for CAR
-    fn replace(&mut self, in_b2: bool) -> bool {
-        if self.t1_size >= 1 && ((in_b2 && self.t1_size == self.p) || self.t1_size > self.p) {
-            if let Some(victim) = self.advance_t1_hand() {
-                if let Some(entry) = self.t1[victim].take() {
-                    self.t1_map.remove(&entry.key);
-                    self.b1.insert(entry.key, ());
-                    self.t1_size -= 1;
-                    self.current_size -= 1;
-                    return true;
-                }
-            }
+    ///
+    /// Falls back to T1 when T2 is empty so a victim is always found.
+    /// Returns the evicted entry.
+    fn replace(&mut self, in_b2: bool) -> Option<(K, V)> {
+        if self.t1_size >= 1
+            && ((in_b2 && self.t1_size == self.p) || self.t1_size > self.p || self.t2_size == 0)
+        {
+            self.demote_t1()
         } else {
-            if let Some(victim) = self.advance_t2_hand() {
-                if let Some(entry) = self.t2[victim].take() {
-                    self.t2_map.remove(&entry.key);
-                    self.b2.insert(entry.key, ());
-                    self.t2_size -= 1;
-                    self.current_size -= 1;
-                    return true;
-                }
+            self.demote_t2()
+        }
+    }
+
+    /// Evict the T1 clock victim into B1
+    fn demote_t1(&mut self) -> Option<(K, V)> {
+        let victim = self.advance_t1_hand()?;
+        let entry = self.take_t1_slot(victim)?;
+        self.t1_map.remove(&entry.key);
+        let stamp = self.ghost_tracker.next_stamp();
+        self.b1.insert(entry.key.clone(), stamp);
+        self.t1_size -= 1;
+        self.current_size -= 1;
+        self.metrics.evictions += 1;
+        Some((entry.key, entry.value))
+    }
+
+    /// Evict the T2 clock victim into B2
+    fn demote_t2(&mut self) -> Option<(K, V)> {
+        let victim = self.advance_t2_hand()?;
+        let entry = self.take_t2_slot(victim)?;
+        self.t2_map.remove(&entry.key);
+        let stamp = self.ghost_tracker.next_stamp();
+        self.b2.insert(entry.key.clone(), stamp);
+        self.t2_size -= 1;
+        self.current_size -= 1;
+        self.metrics.evictions += 1;
+        Some((entry.key, entry.value))
+    }
+
+    /// Rebuild both clock arrays with `slots` entries each
+    ///
+    /// Live entries are packed to the front in clock order starting at the
+    /// current hand, so the hands restart at 0 without changing who is next.
+    fn rebuild_slots(&mut self, slots: usize) {
+        let (t1, t1_map) = Self::pack_clock(&mut self.t1, self.t1_hand, slots);
+        let (t2, t2_map) = Self::pack_clock(&mut self.t2, self.t2_hand, slots);
+        self.t1_free = Self::all_free(slots, t1_map.len());
+        self.t2_free = Self::all_free(slots, t2_map.len());
+        self.t1 = t1;
+        self.t1_map = t1_map;
+        self.t1_hand = 0;
+        self.t2 = t2;
+        self.t2_map = t2_map;
+        self.t2_hand = 0;
+    }
+
+    /// Pack one clock array into a new array of `slots` entries
+    fn pack_clock(
+        ring: &mut [Option<CarEntry<K, V>>],
+        hand: usize,
+        slots: usize,
+    ) -> PackedClock<K, V> {
+        let mut packed: Vec<Option<CarEntry<K, V>>> = Vec::with_capacity(slots);
+        let mut map = HashMap::new();
+        let len = ring.len();
+        for i in 0..len {
+            if let Some(entry) = ring[(hand + i) % len].take() {
+                map.insert(entry.key.clone(), packed.len());
+                packed.push(Some(entry));
             }
         }
-        false
+        packed.resize_with(slots, || None);
+        (packed, map)
     }
 
     /// Trim ghost buffers to at most capacity
@@ -276,6 +463,9 @@ where
     V: Clone,
 {
     fn get(&mut self, key: &K) -> Option<&V> {
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Prefetch buffer
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(val) = self.prefetch_buffer.remove(key) {
@@ -287,7 +477,8 @@ where
 
         // T1
         if let Some(&idx) = self.t1_map.get(key) {
-            if let Some(entry) = self.t1[idx].take() {
+            self.ghost_tracker.record_lookup(true);
+            if let Some(entry) = self.take_t1_slot(idx) {
                 // Promote to T2
                 self.t1_map.remove(key);
                 self.t1_size -= 1;
@@ -305,14 +496,17 @@ where
                 } else if let Some(victim) = self.advance_t2_hand() {
                     if let Some(old) = self.t2[victim].take() {
                         self.t2_map.remove(&old.key);
-                        self.b2.insert(old.key, ());
+                        let stamp = self.ghost_tracker.next_stamp();
+                        self.b2.insert(old.key, stamp);
                         self.t2_size -= 1;
                         self.current_size -= 1;
+                        self.metrics.evictions += 1;
                     }
+                    // The promoted entry only moves from T1 to T2, so
+                    // current_size is unchanged by it
                     self.t2[victim] = Some(new_entry);
                     self.t2_map.insert(key.clone(), victim);
                     self.t2_size += 1;
-                    self.current_size += 1;
                     self.perform_prefetch(key);
                     return self.t2[victim].as_ref().map(|e| &e.value);
                 }
@@ -321,6 +515,7 @@ where
 
         // T2
         if let Some(&idx) = self.t2_map.get(key) {
+            self.ghost_tracker.record_lookup(true);
             // Borrow-scope trick to avoid conflict
             let value_ptr: *const V;
             {
@@ -335,9 +530,17 @@ where
             return Some(unsafe { &*value_ptr });
         }
 
+        self.ghost_tracker.record_lookup(false);
         None
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     fn insert(&mut self, key: K, value: V) {
         self.prefetch_buffer.remove(&key);
 
@@ -358,7 +561,8 @@ where
         }
 
         // Case 2: History hits (B1 or B2)
-        if self.b1.contains_key(&key) {
+        if let Some(&stamp) = self.b1.get(&key) {
+            self.ghost_tracker.record_ghost_hit(stamp);
             let delta = (self.b2.len() as f32 / self.b1.len().max(1) as f32).ceil() as i32;
             self.update_p(delta);
             if self.current_size >= self.capacity {
@@ -372,12 +576,14 @@ where
                 self.t2_map.insert(key, slot);
                 self.t2_size += 1;
                 self.current_size += 1;
+                self.metrics.insertions += 1;
             }
             self.trim_ghost_buffers();
             return;
         }
 
-        if self.b2.contains_key(&key) {
+        if let Some(&stamp) = self.b2.get(&key) {
+            self.ghost_tracker.record_ghost_hit(stamp);
             let delta = (self.b1.len() as f32 / self.b2.len().max(1) as f32).ceil() as i32;
             self.update_p(-delta);
             if self.current_size >= self.capacity {
@@ -391,6 +597,7 @@ where
                 self.t2_map.insert(key, slot);
                 self.t2_size += 1;
                 self.current_size += 1;
+                self.metrics.insertions += 1;
             }
             self.trim_ghost_buffers();
             return;
@@ -415,6 +622,7 @@ where
                 self.t1_map.insert(key, slot);
                 self.t1_size += 1;
                 self.current_size += 1;
+                self.metrics.insertions += 1;
             }
         } else {
             self.replace(false);
@@ -424,6 +632,7 @@ where
                 self.t1_map.insert(key, slot);
                 self.t1_size += 1;
                 self.current_size += 1;
+                self.metrics.insertions += 1;
             }
         }
         self.trim_ghost_buffers();
@@ -434,14 +643,14 @@ where
             return Some(val);
         }
         if let Some(idx) = self.t1_map.remove(key) {
-            if let Some(entry) = self.t1[idx].take() {
+            if let Some(entry) = self.take_t1_slot(idx) {
                 self.t1_size -= 1;
                 self.current_size -= 1;
                 return Some(entry.value);
             }
         }
         if let Some(idx) = self.t2_map.remove(key) {
-            if let Some(entry) = self.t2[idx].take() {
+            if let Some(entry) = self.take_t2_slot(idx) {
                 self.t2_size -= 1;
                 self.current_size -= 1;
                 return Some(entry.value);
@@ -460,6 +669,99 @@ where
         self.capacity
     }
 
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "CAR cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        self.p = self.p.min(new_capacity);
+
+        // A pass that only clears reference bits evicts nothing; the loop
+        // simply sweeps again until a victim is found
+        while self.t1_size + self.t2_size > self.capacity {
+            if self.t1_size > 0 && (self.t1_size > self.p || self.t2_size == 0) {
+                self.demote_t1();
+            } else {
+                self.demote_t2();
+            }
+        }
+
+        self.rebuild_slots(new_capacity);
+        self.trim_ghost_buffers();
+
+        // Restore the directory bounds for the new capacity: T1 + B1 within
+        // the capacity, everything together within twice the capacity
+        while self.t1_size + self.b1.len() > self.capacity {
+            let Some(k) = self.b1.keys().next().cloned() else { break };
+            self.b1.remove(&k);
+        }
+        while self.t1_size + self.t2_size + self.b1.len() + self.b2.len() > 2 * self.capacity {
+            let Some(k) = self.b2.keys().next().cloned() else { break };
+            self.b2.remove(&k);
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        if self.current_size == 0 {
+            return None;
+        }
+        self.replace(false)
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.t1_map.contains_key(key) || self.t2_map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        if let Some(&idx) = self.t1_map.get(key) {
+            return self.t1[idx].as_mut().map(|entry| &mut entry.value);
+        }
+        let idx = *self.t2_map.get(key)?;
+        self.t2[idx].as_mut().map(|entry| &mut entry.value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        if let Some(&idx) = self.t1_map.get(key) {
+            return self.t1[idx].as_ref().map(|entry| &entry.value);
+        }
+        let idx = *self.t2_map.get(key)?;
+        self.t2[idx].as_ref().map(|entry| &entry.value)
+    }
+
+    /// Packs live entries to the front of both clock arrays in clock order
+    /// and rebuilds the free lists from scratch
+    fn compact(&mut self) {
+        self.rebuild_slots(self.capacity);
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for entry in self.t1.iter().chain(self.t2.iter()).flatten() {
+            f(&entry.key, &entry.value);
+        }
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for entry in self.t1.iter_mut().chain(self.t2.iter_mut()).flatten() {
+            f(&entry.key, &mut entry.value);
+        }
+    }
+
     fn clear(&mut self) {
         for s in &mut self.t1 {
             *s = None;
@@ -471,12 +773,15 @@ where
         self.t2_map.clear();
         self.b1.clear();
         self.b2.clear();
+        self.t1_free = Self::all_free(self.t1.len(), 0);
+        self.t2_free = Self::all_free(self.t2.len(), 0);
         self.t1_hand = 0;
         self.t2_hand = 0;
         self.t1_size = 0;
         self.t2_size = 0;
         self.current_size = 0;
         self.p = 0;
+        self.ghost_tracker = GhostHitTracker::default();
         self.prefetch_buffer.clear();
     }
 }
@@ -529,6 +834,18 @@ impl CarCache<usize, String> {
         Self::with_custom_prefetch(capacity, strat)
     }
 }
+impl CarCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl CarCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
 
 unsafe impl<K, V> Send for CarCache<K, V>
 where
@@ -541,3 +858,151 @@ where
     V: Clone + Sync,
 {}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_car_estimated_hit_ratio_grows_with_capacity() {
+        let mut cache = CarCache::new(50);
+        assert_eq!(cache.estimated_hit_ratio_at(100), None);
+
+        // Touch each key twice so it settles in T2, overflow by 30 keys,
+        // then come back for the ones pushed out. They were evicted about
+        // 30 evictions earlier, so a cache 50 entries larger keeps them.
+        for i in 0..80 {
+            cache.insert(i, i);
+            cache.get(&i);
+        }
+        for i in 0..30 {
+            if cache.get(&i).is_none() {
+                cache.insert(i, i);
+            }
+        }
+
+        let actual = cache.estimated_hit_ratio_at(50).unwrap();
+        let larger = cache.estimated_hit_ratio_at(100).unwrap();
+        assert!(larger > actual, "estimate {} not above actual {}", larger, actual);
+        assert!(larger <= 1.0);
+    }
+
+    #[test]
+    fn test_car_free_lists_stay_consistent() {
+        // Stays below capacity so no eviction runs, only slot reuse
+        let mut cache = CarCache::new(32);
+        for key in 0..16 {
+            cache.insert(key, key);
+        }
+        // Promote a few keys so both clock arrays have holes
+        for key in 0..4 {
+            cache.get(&key);
+        }
+        for key in (0..16).step_by(3) {
+            cache.remove(&key);
+        }
+        cache.check_invariants().unwrap();
+
+        for key in 100..110 {
+            cache.insert(key, key);
+            cache.check_invariants().unwrap();
+        }
+        cache.compact();
+        cache.check_invariants().unwrap();
+        assert!(cache.contains_key(&109));
+        assert_eq!(cache.get(&1), Some(&1));
+    }
+
+    #[test]
+    fn test_car_insert_after_removals_does_not_scan() {
+        // Empty the back half of the slots, then refill them; each insert
+        // must take the slot on top of the free list rather than search
+        let mut cache = CarCache::new(1_000);
+        for key in 0..1_000 {
+            cache.insert(key, key);
+        }
+        for key in 500..1_000 {
+            cache.remove(&key);
+        }
+        assert_eq!(cache.t1_free.len(), 500);
+        assert!(cache.t1_free.iter().all(|&index| cache.t1[index].is_none()));
+
+        for key in 0..500 {
+            let next_free = *cache.t1_free.last().unwrap();
+            cache.insert(1_000 + key, key);
+            assert_eq!(cache.t1_map[&(1_000 + key)], next_free);
+            assert_eq!(cache.t1_free.len(), 499 - key as usize);
+        }
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_car_shrink_trims_ghosts_to_new_bound() {
+        let mut cache = CarCache::new(16);
+        for round in 0..10 {
+            for key in 0..24 {
+                cache.insert(key, round);
+                cache.get(&key);
+            }
+        }
+        let ghosts: Vec<i32> = cache.b1.keys().chain(cache.b2.keys()).copied().collect();
+        assert!(!ghosts.is_empty());
+
+        cache.resize(4);
+        assert!(cache.p <= 4);
+        assert!(cache.t1_size + cache.b1.len() <= 4);
+        assert!(cache.t1_size + cache.t2_size + cache.b1.len() + cache.b2.len() <= 8);
+
+        // Ghost hits after the shrink keep p within the new capacity
+        for key in ghosts {
+            cache.insert(key, 0);
+            assert!(cache.p <= 4);
+        }
+    }
+
+    #[test]
+    fn test_car_contains_key_skips_ghosts() {
+        let mut cache = CarCache::new(4);
+        for key in 0..8 {
+            cache.insert(key, key);
+        }
+        let ghosts: Vec<i32> = cache.b1.keys().chain(cache.b2.keys()).copied().collect();
+        assert!(!ghosts.is_empty());
+        for key in &ghosts {
+            assert!(!cache.contains_key(key));
+        }
+        assert_eq!((0..8).filter(|key| cache.contains_key(key)).count(), cache.len());
+
+        // Probing leaves the ghosts in place, so reinserting one is a ghost
+        // hit landing in T2 rather than a fresh entry in T1
+        assert!(cache.b1.contains_key(&ghosts[0]) || cache.b2.contains_key(&ghosts[0]));
+        cache.insert(ghosts[0], 0);
+        assert!(cache.t2_map.contains_key(&ghosts[0]));
+    }
+
+    #[test]
+    fn test_car_len_tracks_resident_entries() {
+        let mut cache = CarCache::new(8);
+        let mut seed: u32 = 3;
+        for step in 0..5_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let key = (seed >> 8) % 24;
+            match (seed >> 4) % 4 {
+                0 => {
+                    cache.remove(&key);
+                }
+                1 => {
+                    cache.get(&key);
+                }
+                _ => {
+                    if cache.get(&key).is_none() {
+                        cache.insert(key, step);
+                    }
+                }
+            }
+            assert_eq!(cache.len(), cache.t1_size + cache.t2_size, "step {}", step);
+            assert_eq!(cache.len(), cache.t1_map.len() + cache.t2_map.len(), "step {}", step);
+            assert!(cache.len() <= cache.capacity(), "step {}", step);
+        }
+        assert!(cache.t2_size > 0);
+    }
+}
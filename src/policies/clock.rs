@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
 use super::{BenchmarkablePolicy, PolicyType};
 
@@ -43,6 +43,14 @@ where
 
     /// Prefetch statistics
     prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
 
     /// PhantomData to bind generic types
     _marker: PhantomData<(K, V)>,
@@ -94,26 +102,71 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
             _marker: PhantomData,
         }
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Iterates resident entries in slot order
+    ///
+    /// Which entry the hand takes next depends on reference bits, so no
+    /// order predicts eviction; empty slots are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buffer.iter().flatten().map(|entry| (&entry.key, &entry.value))
+    }
+
     pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
         &self.prefetch_stats
     }
 
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: map, occupied slots and `len` agree,
+    /// every map index points at its own entry and the hand is in range
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let occupied = self.buffer.iter().filter(|slot| slot.is_some()).count();
+        if occupied != self.len || self.map.len() != self.len {
+            return Err(format!("slots {} / map {} / len {} disagree", occupied, self.map.len(), self.len));
+        }
+        if self.buffer.len() != self.capacity || self.hand >= self.capacity {
+            return Err("buffer size or hand out of step with capacity".to_string());
+        }
+        for (key, &index) in &self.map {
+            match self.buffer.get(index) {
+                Some(Some(entry)) if &entry.key == key => {}
+                _ => return Err(format!("map index {} does not hold its key", index)),
+            }
+        }
+        Ok(())
+    }
+
     /// Update prefetch strategy with new access patterns
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
             if !self.map.contains_key(&predicted_key)
                 && !self.prefetch_buffer.contains_key(&predicted_key)
             {
@@ -174,6 +227,10 @@ where
     V: Clone,
 {
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -204,7 +261,15 @@ where
         None
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         // Invalidate prefetch
         self.prefetch_buffer.remove(&key);
 
@@ -219,9 +284,11 @@ where
         let victim_index = self.find_victim_slot();
         if let Some(ref old_entry) = self.buffer[victim_index] {
             self.map.remove(&old_entry.key);
+            self.metrics.evictions += 1;
         } else {
             self.len += 1;
         }
+        self.metrics.insertions += 1;
 
         let new_entry = ClockEntry::new(key.clone(), value);
         self.buffer[victim_index] = Some(new_entry);
@@ -229,6 +296,7 @@ where
     }
 
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
         }
@@ -249,6 +317,92 @@ where
         self.capacity
     }
 
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "Clock cache capacity must be greater than 0");
+
+        // Sweep the hand to pick victims exactly as insertion would
+        while self.len > new_capacity {
+            let victim = self.advance_clock_hand();
+            if let Some(entry) = self.buffer[victim].take() {
+                self.map.remove(&entry.key);
+                self.len -= 1;
+                self.metrics.evictions += 1;
+            }
+        }
+
+        // Repack survivors in clock order starting at the hand
+        let mut buffer: Vec<Option<ClockEntry<K, V>>> = Vec::with_capacity(new_capacity);
+        for i in 0..self.capacity {
+            if let Some(entry) = self.buffer[(self.hand + i) % self.capacity].take() {
+                self.map.insert(entry.key.clone(), buffer.len());
+                buffer.push(Some(entry));
+            }
+        }
+        buffer.resize_with(new_capacity, || None);
+
+        self.buffer = buffer;
+        self.hand = 0;
+        self.capacity = new_capacity;
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        // Sweep the hand to pick the victim exactly as insertion would
+        while self.len > 0 {
+            let victim = self.advance_clock_hand();
+            if let Some(entry) = self.buffer[victim].take() {
+                self.map.remove(&entry.key);
+                self.len -= 1;
+                self.metrics.evictions += 1;
+                return Some((entry.key, entry.value));
+            }
+        }
+        None
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let index = *self.map.get(key)?;
+        self.buffer[index].as_mut().map(|entry| &mut entry.value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let index = *self.map.get(key)?;
+        self.buffer[index].as_ref().map(|entry| &entry.value)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for entry in self.buffer.iter().flatten() {
+            f(&entry.key, &entry.value);
+        }
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for entry in self.buffer.iter_mut().flatten() {
+            f(&entry.key, &mut entry.value);
+        }
+    }
+
     fn clear(&mut self) {
         self.map.clear();
         for slot in &mut self.buffer {
@@ -301,6 +455,20 @@ impl ClockCache<usize, String> {
         Self::with_custom_prefetch(capacity, strat)
     }
 }
+impl ClockCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "Clock cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl ClockCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "Clock cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
 
 /// Ensure thread-safety for parallel benchmarks
 unsafe impl<K, V> Send for ClockCache<K, V>
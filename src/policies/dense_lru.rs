@@ -0,0 +1,305 @@
+use crate::{CacheMetrics, CachePolicy};
+
+/// Link value marking the end of the recency list
+const NIL: usize = usize::MAX;
+
+/// LRU cache for dense `usize` keys, indexed directly instead of hashed
+///
+/// Key `k` lives in slot `k` of a table, and the recency list threads
+/// through the occupied slots by index, so lookups do no hashing and no
+/// per-entry allocation. The table grows to the largest key ever inserted:
+/// memory is proportional to that key, not to the capacity, so this only
+/// pays off when keys come from a dense range such as `0..n`. Keys must
+/// stay below the cache's [`key_bound`](Self::key_bound), fixed at
+/// construction, so a stray large key can't grow the table without limit.
+/// Eviction order matches [`LruCache`](super::LruCache) without prefetch.
+pub struct DenseLruCache<V> {
+    /// Slot `k` holds key `k`'s value and recency links
+    slots: Vec<Slot<V>>,
+    /// Most recently used key, `NIL` when empty
+    head: usize,
+    /// Least recently used key, `NIL` when empty
+    tail: usize,
+    len: usize,
+    capacity: usize,
+    /// Keys must be below this; the table never grows past it
+    key_bound: usize,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+}
+
+struct Slot<V> {
+    value: Option<V>,
+    prev: usize,
+    next: usize,
+}
+
+impl<V> Slot<V> {
+    fn empty() -> Self {
+        Self {
+            value: None,
+            prev: NIL,
+            next: NIL,
+        }
+    }
+}
+
+impl<V> DenseLruCache<V> {
+    /// Key bound of caches built with [`new`](Self::new)
+    pub const DEFAULT_KEY_BOUND: usize = 1 << 20;
+
+    /// Creates an empty cache for keys below
+    /// [`DEFAULT_KEY_BOUND`](Self::DEFAULT_KEY_BOUND); the table grows as
+    /// keys arrive
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        let mut cache = Self::with_key_bound(capacity, 0);
+        cache.key_bound = Self::DEFAULT_KEY_BOUND;
+        cache
+    }
+
+    /// Creates an empty cache for keys below `key_bound`, with the table
+    /// preallocated for all of them
+    ///
+    /// Inserting a key at or above the bound panics.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn with_key_bound(capacity: usize, key_bound: usize) -> Self {
+        assert!(capacity > 0, "Dense LRU cache capacity must be greater than 0");
+        let mut slots = Vec::with_capacity(key_bound);
+        slots.resize_with(key_bound, Slot::empty);
+        Self {
+            slots,
+            head: NIL,
+            tail: NIL,
+            len: 0,
+            capacity,
+            key_bound,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Keys must be below this bound, which also caps the table's size
+    pub fn key_bound(&self) -> usize {
+        self.key_bound
+    }
+
+    fn is_resident(&self, key: usize) -> bool {
+        self.slots.get(key).is_some_and(|slot| slot.value.is_some())
+    }
+
+    /// Detaches a resident key from the recency list
+    fn unlink(&mut self, key: usize) {
+        let Slot { prev, next, .. } = self.slots[key];
+        match prev {
+            NIL => self.head = next,
+            prev => self.slots[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.slots[next].prev = prev,
+        }
+    }
+
+    /// Links a key in as the most recently used
+    fn push_front(&mut self, key: usize) {
+        self.slots[key].prev = NIL;
+        self.slots[key].next = self.head;
+        match self.head {
+            NIL => self.tail = key,
+            head => self.slots[head].prev = key,
+        }
+        self.head = key;
+    }
+
+    /// Removes the least recently used entry
+    fn evict_lru(&mut self) -> Option<(usize, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let key = self.tail;
+        self.unlink(key);
+        let value = self.slots[key].value.take()?;
+        self.len -= 1;
+        self.metrics.evictions += 1;
+        Some((key, value))
+    }
+}
+
+impl<V> CachePolicy<usize, V> for DenseLruCache<V> {
+    fn get(&mut self, key: &usize) -> Option<&V> {
+        if !self.is_resident(*key) {
+            return None;
+        }
+        if self.head != *key {
+            self.unlink(*key);
+            self.push_front(*key);
+        }
+        self.slots[*key].value.as_ref()
+    }
+
+    /// # Panics
+    /// Panics if `key` is not below [`key_bound`](DenseLruCache::key_bound)
+    fn insert(&mut self, key: usize, value: V) {
+        assert!(
+            key < self.key_bound,
+            "key {} is outside the dense LRU key bound {}",
+            key,
+            self.key_bound
+        );
+        if self.is_resident(key) {
+            self.slots[key].value = Some(value);
+            self.unlink(key);
+            self.push_front(key);
+            return;
+        }
+        if self.len >= self.capacity {
+            self.evict_lru();
+        }
+        if key >= self.slots.len() {
+            self.slots.resize_with(key + 1, Slot::empty);
+        }
+        self.slots[key].value = Some(value);
+        self.push_front(key);
+        self.len += 1;
+        self.metrics.insertions += 1;
+    }
+
+    fn remove(&mut self, key: &usize) -> Option<V> {
+        if !self.is_resident(*key) {
+            return None;
+        }
+        self.unlink(*key);
+        self.len -= 1;
+        self.slots[*key].value.take()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Empties the cache, keeping the table allocated
+    fn clear(&mut self) {
+        for slot in &mut self.slots {
+            *slot = Slot::empty();
+        }
+        self.head = NIL;
+        self.tail = NIL;
+        self.len = 0;
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "Dense LRU cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.len > self.capacity {
+            self.evict_lru();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(usize, V)> {
+        self.evict_lru()
+    }
+
+    fn contains_key(&self, key: &usize) -> bool {
+        self.is_resident(*key)
+    }
+
+    fn get_mut(&mut self, key: &usize) -> Option<&mut V> {
+        self.get(key)?;
+        self.slots[*key].value.as_mut()
+    }
+
+    fn peek(&self, key: &usize) -> Option<&V> {
+        self.slots.get(*key)?.value.as_ref()
+    }
+
+    fn for_each_entry(&self, f: &mut dyn FnMut(&usize, &V)) {
+        for (key, slot) in self.slots.iter().enumerate() {
+            if let Some(value) = &slot.value {
+                f(&key, value);
+            }
+        }
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&usize, &mut V)) {
+        for (key, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(value) = &mut slot.value {
+                f(&key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_dense_lru_matches_lru_on_dense_trace() {
+        let mut dense = DenseLruCache::new(50);
+        let mut lru = LruCache::new(50);
+        let mut rng = StdRng::seed_from_u64(11);
+        for step in 0..20_000 {
+            // Starting from an empty table exercises table growth
+            let key = rng.gen_range(0..200usize);
+            match rng.gen_range(0..10) {
+                0..=5 => assert_eq!(dense.get(&key), lru.get(&key), "get {} at step {}", key, step),
+                6..=8 => {
+                    dense.insert(key, step);
+                    lru.insert(key, step);
+                }
+                _ => assert_eq!(dense.remove(&key), lru.remove(&key)),
+            }
+            assert_eq!(dense.len(), lru.len());
+        }
+        assert!((0..200).all(|key| dense.contains_key(&key) == lru.contains_key(&key)));
+        assert_eq!(dense.key_bound(), DenseLruCache::<usize>::DEFAULT_KEY_BOUND);
+        assert_eq!(dense.slots.len(), 200);
+
+        dense.resize(10);
+        lru.resize(10);
+        assert!((0..200).all(|key| dense.contains_key(&key) == lru.contains_key(&key)));
+        dense.clear();
+        assert!(dense.is_empty());
+        assert_eq!(dense.get(&0), None);
+    }
+
+    #[test]
+    fn test_dense_lru_key_bound_caps_the_table() {
+        let mut dense = DenseLruCache::with_key_bound(4, 16);
+        dense.insert(15, "last");
+        assert_eq!(dense.get(&15), Some(&"last"));
+        assert_eq!(dense.slots.len(), 16);
+
+        // Keys beyond the bound, the end-of-list marker included, are absent
+        for key in [16, 1 << 40, usize::MAX] {
+            assert_eq!(dense.get(&key), None);
+            assert!(!dense.contains_key(&key));
+            assert_eq!(dense.remove(&key), None);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the dense LRU key bound")]
+    fn test_dense_lru_rejects_key_past_bound() {
+        DenseLruCache::new(4).insert(usize::MAX, 0);
+    }
+}
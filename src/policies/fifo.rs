@@ -1,9 +1,9 @@
 use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
 
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy, Weigher};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::{BenchmarkablePolicy, ByteBudget, PolicyType};
 
 /// A First-In-First-Out (FIFO) cache implementation with optional prefetch strategies.
 ///
@@ -40,6 +40,26 @@ where
 
     /// Statistics that track prefetch efficiency
     prefetch_stats: PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+
+    /// Weight tracking, present only in byte-capacity mode
+    byte_budget: Option<ByteBudget<K, V>>,
+
+    /// Operations a fresh entry is shielded from capacity eviction
+    min_residency: usize,
+
+    /// Operation counter, advanced on every hit and insert
+    op_seq: u64,
+
+    /// Insert sequence number of entries added while residency is enforced
+    inserted_at: HashMap<K, u64>,
 }
 
 /// Statistics for evaluating the effectiveness of prefetching
@@ -106,6 +126,14 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            byte_budget: None,
+            min_residency: 0,
+            op_seq: 0,
+            inserted_at: HashMap::new(),
         }
     }
 
@@ -114,6 +142,19 @@ where
         Self::new(100)
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Iterate resident entries, newest first
+    ///
+    /// This is the reverse of eviction order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.order.iter().rev().filter_map(|key| self.map.get_key_value(key))
+    }
+
     /// Return the current prefetch statistics
     pub fn prefetch_stats(&self) -> &PrefetchStats {
         &self.prefetch_stats
@@ -122,9 +163,49 @@ where
     /// Reset prefetch statistics and strategy state
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: the insertion order and the map
+    /// hold the same keys and the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.order.len() != self.map.len() {
+            return Err(format!("order holds {} keys but map holds {}", self.order.len(), self.map.len()));
+        }
+        if self.map.len() > self.capacity + self.min_residency {
+            return Err(format!("len {} exceeds capacity {}", self.map.len(), self.capacity));
+        }
+        if self.order.iter().any(|k| !self.map.contains_key(k)) {
+            return Err("order references a key missing from the map".to_string());
+        }
+        if let Some(budget) = &self.byte_budget {
+            budget.check(self.map.iter())?;
+        }
+        Ok(())
+    }
+
+    /// Shields each new entry from capacity eviction for `accesses` operations
+    ///
+    /// Operations are hits and inserts. Eviction on insert skips protected
+    /// entries and takes the oldest unprotected one instead. If every entry
+    /// is protected nothing is evicted and the cache transiently exceeds its
+    /// capacity, by at most `accesses` entries; the excess is evicted by
+    /// later inserts once it ages out. Entries already resident when this is
+    /// called are not protected, and `resize` and the byte budget ignore the
+    /// protection. Zero, the default, turns it off.
+    pub fn set_min_residency(&mut self, accesses: usize) {
+        self.min_residency = accesses;
+        if accesses == 0 {
+            self.inserted_at.clear();
+        }
+    }
+
+    /// Total weight of the resident entries, `None` outside byte-capacity mode
+    pub fn total_bytes(&self) -> Option<usize> {
+        self.byte_budget.as_ref().map(ByteBudget::total)
+    }
+
     /// Set a new maximum size for the prefetch buffer
     pub fn set_prefetch_buffer_size(&mut self, size: usize) {
         self.prefetch_buffer_size = size.max(1);
@@ -144,13 +225,20 @@ where
 
     /// Perform prefetching based on the current access and update the buffer
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
 
         // Ask the strategy for predicted next keys
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             // Only store prediction if it's not already in cache or buffer
             if !self.map.contains_key(&predicted_key)
@@ -166,7 +254,59 @@ where
     /// Evict the **oldest** key (front of the queue)
     fn evict_oldest(&mut self) {
         if let Some(oldest_key) = self.order.pop_front() {
-            self.map.remove(&oldest_key);
+            self.metrics.evictions += 1;
+            self.inserted_at.remove(&oldest_key);
+            if let Some(value) = self.map.remove(&oldest_key)
+                && let Some(budget) = self.byte_budget.as_mut()
+            {
+                budget.sub(&oldest_key, &value);
+            }
+        }
+    }
+
+    /// Evict the oldest key past its minimum residency
+    ///
+    /// Returns false if every key is still protected.
+    fn evict_unprotected(&mut self) -> bool {
+        self.take_unprotected().is_some()
+    }
+
+    /// [`evict_unprotected`](Self::evict_unprotected), returning the evicted entry
+    fn take_unprotected(&mut self) -> Option<(K, V)> {
+        let protected = |k: &K| {
+            self.inserted_at
+                .get(k)
+                .is_some_and(|at| self.op_seq - at < self.min_residency as u64)
+        };
+        let pos = self.order.iter().position(|k| !protected(k))?;
+        let key = self.order.remove(pos)?;
+        self.metrics.evictions += 1;
+        self.inserted_at.remove(&key);
+        let value = self.map.remove(&key)?;
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.sub(&key, &value);
+        }
+        Some((key, value))
+    }
+
+    /// Evict the oldest keys until the byte budget is met, sparing `protect`
+    ///
+    /// Inserts drop entries that can't fit alone, so evicting the others
+    /// always makes room for the protected one.
+    fn evict_over_budget(&mut self, protect: Option<&K>) {
+        while matches!(&self.byte_budget, Some(budget) if budget.is_over()) {
+            let Some(pos) = self.order.iter().position(|k| Some(k) != protect) else {
+                break;
+            };
+            if let Some(key) = self.order.remove(pos) {
+                self.metrics.evictions += 1;
+                self.inserted_at.remove(&key);
+                if let Some(value) = self.map.remove(&key)
+                    && let Some(budget) = self.byte_budget.as_mut()
+                {
+                    budget.sub(&key, &value);
+                }
+            }
         }
     }
 
@@ -201,6 +341,22 @@ impl FifoCache<usize, String> {
     }
 }
 
+impl FifoCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "FIFO cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl FifoCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "FIFO cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 impl<K, V> CachePolicy<K, V> for FifoCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -211,6 +367,10 @@ where
     /// - If the item exists in the prefetch buffer, it is moved into the main cache first.
     /// - If found in the main cache, prefetching is triggered.
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -222,6 +382,7 @@ where
 
         // Safe version: check presence, then evaluate again after prefetch
         if self.map.contains_key(key) {
+            self.op_seq += 1;
             self.perform_prefetch(key);
             self.map.get(key)
         } else {
@@ -229,32 +390,63 @@ where
         }
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Insert a new key-value pair into the cache.
     ///
     /// - If the key already exists, update its value without changing order.
     /// - Evicts the oldest item if capacity is exceeded.
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        if matches!(&self.byte_budget, Some(budget) if !budget.fits(&key, &value)) {
+            self.remove(&key);
+            return;
+        }
         self.prefetch_buffer.remove(&key);
+        self.op_seq += 1;
 
         if !self.map.contains_key(&key) {
-            if self.map.len() == self.capacity {
-                self.evict_oldest();
+            while self.map.len() >= self.capacity && self.evict_unprotected() {}
+            if self.min_residency > 0 {
+                self.inserted_at.insert(key.clone(), self.op_seq);
             }
             self.order.push_back(key.clone());
+            self.metrics.insertions += 1;
+        }
+        match self.byte_budget.as_mut() {
+            None => {
+                self.map.insert(key, value);
+            }
+            Some(budget) => {
+                budget.add(&key, &value);
+                if let Some(old) = self.map.insert(key.clone(), value) {
+                    budget.sub(&key, &old);
+                }
+                self.evict_over_budget(Some(&key));
+            }
         }
-        self.map.insert(key, value);
     }
 
     /// Remove a key and return its value if present
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
         }
 
         if let Some(value) = self.map.remove(key) {
+            self.inserted_at.remove(key);
             if let Some(pos) = self.order.iter().position(|k| k == key) {
                 self.order.remove(pos);
             }
+            if let Some(budget) = self.byte_budget.as_mut() {
+                budget.sub(key, &value);
+            }
             Some(value)
         } else {
             None
@@ -268,12 +460,88 @@ where
     fn clear(&mut self) {
         self.map.clear();
         self.order.clear();
+        self.inserted_at.clear();
         self.prefetch_buffer.clear();
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.clear();
+        }
     }
 
     fn capacity(&self) -> usize {
         self.capacity
     }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Change the capacity, evicting the oldest inserted items if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "FIFO cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.map.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.take_unprotected()
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        self.map.get_mut(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in &self.map {
+            f(key, value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    ///
+    /// In byte-capacity mode the entries are reweighed afterwards and the
+    /// oldest ones evicted if the rewrite overran the budget.
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, value) in self.map.iter_mut() {
+            f(key, value);
+        }
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.reweigh(self.map.iter());
+            self.evict_over_budget(None);
+        }
+    }
+
+    /// Evicts the oldest entries until the weighed total fits
+    fn set_byte_capacity(&mut self, bytes: usize, weigher: Weigher<K, V>) {
+        let mut budget = ByteBudget::new(bytes, weigher);
+        budget.reweigh(self.map.iter());
+        self.byte_budget = Some(budget);
+        self.evict_over_budget(None);
+    }
 }
 
 impl<K, V> BenchmarkablePolicy<K, V> for FifoCache<K, V>
@@ -316,3 +584,34 @@ where
     K: Hash + Eq + Clone + Sync,
     V: Clone + Sync,
 {}
+
+#[cfg(feature = "serde")]
+impl<K, V> serde::Serialize for FifoCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Serialize,
+    V: Clone + serde::Serialize,
+{
+    /// Writes the capacity and the entries, oldest first
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(&K, &V)> = self.order.iter().filter_map(|key| self.map.get_key_value(key)).collect();
+        super::Snapshot { capacity: self.capacity, entries }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K, V> serde::Deserialize<'de> for FifoCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Deserialize<'de>,
+    V: Clone + serde::Deserialize<'de>,
+{
+    /// Re-inserts the entries in their original order
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = super::Snapshot::<(K, V)>::deserialize(deserializer)?;
+        snapshot.validate()?;
+        let mut cache = Self::new(snapshot.capacity);
+        for (key, value) in snapshot.entries {
+            cache.insert(key, value);
+        }
+        Ok(cache)
+    }
+}
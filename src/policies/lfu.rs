@@ -1,13 +1,18 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, BTreeMap};
 use std::hash::Hash;
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy, Weigher};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::{BenchmarkablePolicy, ByteBudget, PolicyType};
+
+/// Orders two equal-frequency entries for eviction, see [`LfuCache::set_lfu_tiebreak`]
+pub type LfuTiebreak<K, V> = Box<dyn Fn(&K, &V, &K, &V) -> Ordering + Send>;
 
 /// A Least Frequently Used (LFU) cache implementation with integrated prefetch strategies
 ///
 /// This cache evicts the item with the lowest access frequency.
-/// When multiple keys have the same frequency, the oldest inserted among them is evicted.
+/// When multiple keys have the same frequency, the least recently accessed among them
+/// is evicted, unless [`set_lfu_tiebreak`](LfuCache::set_lfu_tiebreak) says otherwise.
 /// The cache integrates with prefetch strategies to predict and preload
 /// likely future accesses, improving performance for predictable access patterns.
 pub struct LfuCache<K, V>
@@ -31,6 +36,26 @@ where
     prefetch_buffer_size: usize,
     /// Statistics for prefetch effectiveness
     prefetch_stats: PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Sequence number of each key's most recent access
+    access_seqs: HashMap<K, u64>,
+    /// Monotonic operation counter feeding `access_seqs`
+    access_counter: u64,
+    /// Weight tracking, present only in byte-capacity mode
+    byte_budget: Option<ByteBudget<K, V>>,
+    /// Victim choice among minimum-frequency keys, least recent first when unset
+    tiebreak: Option<LfuTiebreak<K, V>>,
+    /// Periodic decay as `(factor, period)`, see [`set_aging`](LfuCache::set_aging)
+    aging: Option<(f64, u64)>,
+    /// Gets and inserts since the last periodic decay
+    ops_since_aging: u64,
 }
 
 /// Statistics tracking prefetch effectiveness
@@ -105,6 +130,16 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            access_seqs: HashMap::new(),
+            access_counter: 0,
+            byte_budget: None,
+            tiebreak: None,
+            aging: None,
+            ops_since_aging: 0,
         }
     }
 
@@ -113,6 +148,65 @@ where
         Self::new(100)
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Overrides the tie-break among the keys of minimum frequency
+    ///
+    /// `cmp(a_key, a_value, b_key, b_value)` returning `Less` makes `a` the
+    /// better victim; among equal candidates the least recently accessed is
+    /// still evicted.
+    /// Frequency always decides first.
+    pub fn set_lfu_tiebreak(&mut self, cmp: LfuTiebreak<K, V>) {
+        self.tiebreak = Some(cmp);
+    }
+
+    /// Decays every count by `factor` once per `period` gets and inserts
+    ///
+    /// Keys that were hot long ago otherwise keep counts no newcomer can
+    /// reach and are never evicted. With aging their counts shrink back
+    /// towards 1 once they stop being read, so fresh keys with a few hits
+    /// outrank them. Runs [`age`](Self::age) inline; a `period` of 0 is
+    /// treated as 1.
+    pub fn set_aging(&mut self, factor: f64, period: u64) {
+        self.aging = Some((factor, period.max(1)));
+        self.ops_since_aging = 0;
+    }
+
+    /// Builder form of [`set_aging`](Self::set_aging)
+    pub fn with_aging(mut self, factor: f64, period: u64) -> Self {
+        self.set_aging(factor, period);
+        self
+    }
+
+    /// Counts one operation towards the aging period, decaying when it is reached
+    fn tick_aging(&mut self) {
+        let Some((factor, period)) = self.aging else {
+            return;
+        };
+        self.ops_since_aging += 1;
+        if self.ops_since_aging >= period {
+            self.ops_since_aging = 0;
+            self.age(factor);
+        }
+    }
+
+    /// Iterates resident entries, most frequently used first
+    ///
+    /// Within a frequency the most recently promoted key comes first. A
+    /// custom tiebreak may evict in a different order among equal counts.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.freq_list
+            .values()
+            .rev()
+            .flat_map(|keys| keys.iter().rev())
+            .filter_map(|key| self.map.get_key_value(key))
+            .map(|(key, (value, _))| (key, value))
+    }
+
     /// Returns current prefetch statistics
     pub fn prefetch_stats(&self) -> &PrefetchStats {
         &self.prefetch_stats
@@ -121,9 +215,57 @@ where
     /// Resets prefetch statistics
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: every key sits in exactly the
+    /// frequency bucket recorded for it and the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.map.len(), self.capacity));
+        }
+        let mut bucketed = 0;
+        for (&freq, keys) in &self.freq_list {
+            if keys.is_empty() {
+                return Err(format!("empty bucket left for frequency {}", freq));
+            }
+            for key in keys {
+                match self.map.get(key) {
+                    Some((_, f)) if *f == freq => bucketed += 1,
+                    Some((_, f)) => return Err(format!("key with frequency {} filed under {}", f, freq)),
+                    None => return Err(format!("bucket {} references a missing key", freq)),
+                }
+            }
+        }
+        if bucketed != self.map.len() {
+            return Err(format!("buckets hold {} keys but map holds {}", bucketed, self.map.len()));
+        }
+        if let Some(budget) = &self.byte_budget {
+            budget.check(self.map.iter().map(|(key, (value, _))| (key, value)))?;
+        }
+        Ok(())
+    }
+
+    /// Total weight of the resident entries, `None` outside byte-capacity mode
+    pub fn total_bytes(&self) -> Option<usize> {
+        self.byte_budget.as_ref().map(ByteBudget::total)
+    }
+
+    /// Returns the sequence number of the most recent get or insert of `key`
+    ///
+    /// Numbers come from a single counter shared by all keys, so among keys
+    /// of equal frequency the smaller value marks the less recent one.
+    pub fn last_access_seq(&self, key: &K) -> Option<u64> {
+        self.access_seqs.get(key).copied()
+    }
+
+    /// Stamps `key` with the next value of the access counter
+    fn record_access(&mut self, key: &K) {
+        self.access_counter += 1;
+        self.access_seqs.insert(key.clone(), self.access_counter);
+    }
+
     /// Sets the prefetch buffer size
     pub fn set_prefetch_buffer_size(&mut self, size: usize) {
         self.prefetch_buffer_size = size.max(1);
@@ -143,14 +285,21 @@ where
 
     /// Performs prefetch predictions and populates the prefetch buffer
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         // Update prefetch strategy with the accessed key
         self.prefetch_strategy.update_access_pattern(accessed_key);
 
         // Get predictions from the strategy
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             // Only prefetch if the key is not already in main cache or prefetch buffer
             if !self.map.contains_key(&predicted_key) &&
@@ -195,23 +344,159 @@ where
             *freq += 1;
             // Add key to new frequency list
             self.freq_list.entry(*freq).or_default().push(key.clone());
+            self.record_access(key);
         }
     }
 
-    /// Evicts one key with the lowest frequency (min_freq)
+    /// Evicts one key with the lowest frequency
+    fn evict(&mut self) -> Option<(K, V)> {
+        let victim = self.victim(None)?;
+        let value = self.remove_resident(&victim)?;
+        self.metrics.evictions += 1;
+        Some((victim, value))
+    }
+
+    /// Picks the eviction victim among the lowest-frequency keys other than
+    /// `protect`: the first per the tie-break when one is set, then the
+    /// least recently accessed
+    fn victim(&self, protect: Option<&K>) -> Option<K> {
+        let seq = |key: &K| self.access_seqs.get(key).copied().unwrap_or(0);
+        for keys in self.freq_list.values() {
+            let candidates = keys.iter().filter(|k| Some(*k) != protect);
+            let victim = match &self.tiebreak {
+                Some(cmp) => candidates.min_by(|a, b| {
+                    cmp(a, &self.map[*a].0, b, &self.map[*b].0).then_with(|| seq(a).cmp(&seq(b)))
+                }),
+                None => candidates.min_by_key(|k| seq(k)),
+            };
+            if victim.is_some() {
+                return victim.cloned();
+            }
+        }
+        None
+    }
+
+    /// Evicts least frequently used keys until the byte budget is met,
+    /// sparing `protect`
     ///
-    /// Chooses the oldest inserted key among those with minimal frequency.
-    fn evict(&mut self) {
-        if let Some(keys) = self.freq_list.get_mut(&self.min_freq) {
-            if let Some(oldest_key) = keys.first().cloned() {
-                // Remove from freq_list and map
-                keys.remove(0);
-                if keys.is_empty() {
-                    self.freq_list.remove(&self.min_freq);
+    /// Inserts drop entries that can't fit alone, so evicting the others
+    /// always makes room for the protected one.
+    fn evict_over_budget(&mut self, protect: Option<&K>) {
+        while matches!(&self.byte_budget, Some(budget) if budget.is_over()) {
+            let Some(victim) = self.victim(protect) else {
+                break;
+            };
+            self.remove_resident(&victim);
+            self.metrics.evictions += 1;
+        }
+        // Buckets may have emptied below min_freq
+        self.min_freq = self.freq_list.keys().next().copied().unwrap_or(0);
+    }
+
+    /// Removes a resident key from the map, its bucket and the byte budget
+    fn remove_resident(&mut self, key: &K) -> Option<V> {
+        let (value, freq) = self.map.remove(key)?;
+        self.access_seqs.remove(key);
+        if let Some(keys) = self.freq_list.get_mut(&freq) {
+            if let Some(pos) = keys.iter().position(|k| k == key) {
+                keys.remove(pos);
+            }
+            if keys.is_empty() {
+                self.freq_list.remove(&freq);
+            }
+        }
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.sub(key, &value);
+        }
+        Some(value)
+    }
+
+    /// Decays every frequency count by `factor` so stale popularity fades
+    ///
+    /// Each count becomes `floor(freq * factor)`, never dropping below 1.
+    /// Relative order among keys is preserved; keys merged into one bucket
+    /// are evicted least recently accessed first. `factor` is clamped to `[0.0, 1.0]`.
+    pub fn age(&mut self, factor: f64) {
+        let factor = factor.clamp(0.0, 1.0);
+        let old_list = std::mem::take(&mut self.freq_list);
+
+        // Walk buckets in ascending order so keys merged into the same
+        // bucket keep their old relative age
+        for (_, keys) in old_list {
+            for key in keys {
+                if let Some((_, freq)) = self.map.get_mut(&key) {
+                    *freq = ((*freq as f64 * factor) as usize).max(1);
+                    self.freq_list.entry(*freq).or_default().push(key);
                 }
-                self.map.remove(&oldest_key);
             }
         }
+
+        self.min_freq = self.freq_list.keys().next().copied().unwrap_or(0);
+    }
+}
+
+/// Handle to a background aging thread started by [`LfuCache::spawn_aging`]
+///
+/// Dropping the handle stops the thread as well.
+#[cfg(feature = "aging")]
+pub struct AgingHandle {
+    stop_tx: Option<std::sync::mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "aging")]
+impl AgingHandle {
+    /// Stops the aging thread and waits for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes the thread immediately
+        self.stop_tx.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(feature = "aging")]
+impl Drop for AgingHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(feature = "aging")]
+impl<K, V> LfuCache<K, V>
+where
+    K: Hash + Eq + Clone + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    /// Spawns a thread calling [`age`](Self::age) with `factor` every `interval`
+    ///
+    /// The thread runs until the returned handle is stopped or dropped.
+    pub fn spawn_aging(
+        cache: std::sync::Arc<std::sync::Mutex<Self>>,
+        interval: std::time::Duration,
+        factor: f64,
+    ) -> AgingHandle {
+        use std::sync::mpsc::{self, RecvTimeoutError};
+
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                match cache.lock() {
+                    Ok(mut guard) => guard.age(factor),
+                    Err(_) => break,
+                }
+            }
+        });
+
+        AgingHandle {
+            stop_tx: Some(stop_tx),
+            thread: Some(thread),
+        }
     }
 }
 
@@ -243,6 +528,24 @@ impl LfuCache<usize, String> {
     }
 }
 
+impl LfuCache<u32, String> {
+    /// Creates a new u32 LFU cache with specified prefetch strategy
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LFU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl LfuCache<u64, String> {
+    /// Creates a new u64 LFU cache with specified prefetch strategy
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LFU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 impl<K, V> CachePolicy<K, V> for LfuCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -250,6 +553,10 @@ where
 {
     /// Gets value by key and increases its frequency
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check if it's in the prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             // Move from prefetch buffer to main cache
@@ -260,6 +567,7 @@ where
             }
         }
 
+        self.tick_aging();
         if self.map.contains_key(key) {
             self.increase_freq(key);
             // Perform prefetch predictions
@@ -270,13 +578,27 @@ where
         }
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Inserts or updates a key-value pair
     ///
     /// Evicts least frequently used when capacity exceeded.
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         if self.capacity == 0 {
             return;
         }
+        self.tick_aging();
+
+        if matches!(&self.byte_budget, Some(budget) if !budget.fits(&key, &value)) {
+            self.remove(&key);
+            return;
+        }
 
         // Remove from prefetch buffer if it exists there
         self.prefetch_buffer.remove(&key);
@@ -284,9 +606,14 @@ where
         if self.map.contains_key(&key) {
             // Update value and increase frequency
             if let Some((v, _)) = self.map.get_mut(&key) {
+                if let Some(budget) = self.byte_budget.as_mut() {
+                    budget.sub(&key, v);
+                    budget.add(&key, &value);
+                }
                 *v = value;
             }
             self.increase_freq(&key);
+            self.evict_over_budget(Some(&key));
             return;
         }
 
@@ -295,31 +622,26 @@ where
         }
 
         // Insert with freq 1
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.add(&key, &value);
+        }
         self.map.insert(key.clone(), (value, 1));
-        self.freq_list.entry(1).or_default().push(key);
+        self.metrics.insertions += 1;
+        self.record_access(&key);
+        self.freq_list.entry(1).or_default().push(key.clone());
         self.min_freq = 1; // Reset min_freq as new key added with freq 1
+        self.evict_over_budget(Some(&key));
     }
 
     /// Removes a key, returning its value if present
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         // Check prefetch buffer first
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
         }
 
-        if let Some((value, freq)) = self.map.remove(key) {
-            if let Some(keys) = self.freq_list.get_mut(&freq) {
-                if let Some(pos) = keys.iter().position(|k| k == key) {
-                    keys.remove(pos);
-                }
-                if keys.is_empty() {
-                    self.freq_list.remove(&freq);
-                }
-            }
-            Some(value)
-        } else {
-            None
-        }
+        self.remove_resident(key)
     }
 
     /// Returns number of items currently stored
@@ -331,14 +653,98 @@ where
     fn clear(&mut self) {
         self.map.clear();
         self.freq_list.clear();
+        self.access_seqs.clear();
         self.min_freq = 0;
         self.prefetch_buffer.clear();
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.clear();
+        }
     }
 
     /// Returns maximal capacity allowed
     fn capacity(&self) -> usize {
         self.capacity
     }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Changes capacity, evicting least frequently used keys if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "LFU cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.map.len() > self.capacity {
+            // min_freq may be stale after removals, so resync before evicting
+            match self.freq_list.keys().next() {
+                Some(&freq) => self.min_freq = freq,
+                None => break,
+            }
+            self.evict();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        let evicted = self.evict()?;
+        // The victim's bucket may have emptied
+        self.min_freq = self.freq_list.keys().next().copied().unwrap_or(0);
+        Some(evicted)
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        self.map.get_mut(key).map(|(value, _)| value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|(value, _)| value)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, (value, _)) in &self.map {
+            f(key, value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    ///
+    /// In byte-capacity mode the entries are reweighed afterwards and the
+    /// least frequently used ones evicted if the rewrite overran the budget.
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, (value, _)) in self.map.iter_mut() {
+            f(key, value);
+        }
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.reweigh(self.map.iter().map(|(key, (value, _))| (key, value)));
+            self.evict_over_budget(None);
+        }
+    }
+
+    /// Evicts least frequently used entries until the weighed total fits
+    fn set_byte_capacity(&mut self, bytes: usize, weigher: Weigher<K, V>) {
+        let mut budget = ByteBudget::new(bytes, weigher);
+        budget.reweigh(self.map.iter().map(|(key, (value, _))| (key, value)));
+        self.byte_budget = Some(budget);
+        self.evict_over_budget(None);
+    }
 }
 
 impl<K, V> BenchmarkablePolicy<K, V> for LfuCache<K, V>
@@ -361,6 +767,14 @@ where
         self.clear();
         self.reset_prefetch_stats();
     }
+
+    /// Also restarts access sequence numbers and the aging period, which survive `clear`
+    fn reset_all(&mut self) {
+        self.reset_for_benchmark();
+        self.reset_metrics();
+        self.access_counter = 0;
+        self.ops_since_aging = 0;
+    }
 }
 
 /// Safe cleanup via drop
@@ -387,3 +801,217 @@ where
     V: Clone + Sync,
 {
 }
+
+#[cfg(feature = "serde")]
+impl<K, V> serde::Serialize for LfuCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Serialize,
+    V: Clone + serde::Serialize,
+{
+    /// Writes the capacity and `(key, value, frequency)` entries, next victim first
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(&K, &V, usize)> = self
+            .freq_list
+            .values()
+            .flatten()
+            .filter_map(|key| self.map.get_key_value(key))
+            .map(|(key, (value, freq))| (key, value, *freq))
+            .collect();
+        super::Snapshot { capacity: self.capacity, entries }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K, V> serde::Deserialize<'de> for LfuCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Deserialize<'de>,
+    V: Clone + serde::Deserialize<'de>,
+{
+    /// Refiles every entry under its saved frequency, keeping bucket order
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let snapshot = super::Snapshot::<(K, V, usize)>::deserialize(deserializer)?;
+        snapshot.validate()?;
+        let mut cache = Self::new(snapshot.capacity);
+        for (key, value, freq) in snapshot.entries {
+            if freq == 0 {
+                return Err(D::Error::custom("entry frequency must be at least 1"));
+            }
+            if cache.map.insert(key.clone(), (value, freq)).is_some() {
+                return Err(D::Error::custom("duplicate key in snapshot"));
+            }
+            cache.record_access(&key);
+            cache.freq_list.entry(freq).or_default().push(key);
+        }
+        cache.min_freq = cache.freq_list.keys().next().copied().unwrap_or(0);
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfu_age_decays_counts() {
+        let mut cache = LfuCache::new(3);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        for _ in 0..9 {
+            cache.get(&1);
+        }
+        cache.age(0.5);
+
+        assert_eq!(cache.map[&1].1, 5);
+        assert_eq!(cache.map[&2].1, 1);
+        assert_eq!(cache.min_freq, 1);
+
+        // Key 2 is still the eviction victim
+        cache.insert(3, "c");
+        cache.insert(4, "d");
+        assert!(cache.get(&2).is_none());
+        assert!(cache.get(&1).is_some());
+    }
+
+    #[test]
+    fn test_lfu_periodic_aging_evicts_stale_hot_key() {
+        // Key 0 is hot early on, then only fresh keys read twice each arrive
+        fn run(mut cache: LfuCache<i32, i32>) -> bool {
+            cache.insert(0, 0);
+            for _ in 0..1000 {
+                cache.get(&0);
+            }
+            for key in 1..500 {
+                cache.insert(key, key);
+                cache.get(&key);
+            }
+            cache.contains_key(&0)
+        }
+
+        assert!(run(LfuCache::new(4)), "plain LFU keeps the stale key forever");
+        assert!(!run(LfuCache::new(4).with_aging(0.5, 50)));
+
+        let mut cache = LfuCache::new(4).with_aging(0.5, 2);
+        cache.insert(1, 1);
+        for _ in 0..7 {
+            cache.get(&1);
+        }
+        assert!(cache.map[&1].1 < 8);
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lfu_serde_round_trip_keeps_frequencies() {
+        let mut original = LfuCache::new(3);
+        original.insert(1, 10);
+        original.insert(2, 20);
+        original.insert(3, 30);
+        original.get(&1);
+        original.get(&1);
+        original.get(&3);
+
+        let json = serde_json::to_string(&original).unwrap();
+        let mut restored: LfuCache<i32, i32> = serde_json::from_str(&json).unwrap();
+        assert!(restored.check_invariants().is_ok());
+        assert_eq!(restored.map[&1].1, 3);
+        assert_eq!(restored.map[&3].1, 2);
+
+        original.insert(4, 40);
+        restored.insert(4, 40);
+        assert!(!restored.contains_key(&2));
+        let keys = |cache: &LfuCache<i32, i32>| {
+            let mut keys: Vec<i32> = cache.iter().map(|(k, _)| *k).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&restored), keys(&original));
+    }
+
+    #[cfg(feature = "aging")]
+    #[test]
+    fn test_lfu_background_aging() {
+        use std::sync::{Arc, Mutex};
+        use std::time::{Duration, Instant};
+
+        let mut cache = LfuCache::new(4);
+        cache.insert(1, 1);
+        for _ in 0..1000 {
+            cache.get(&1);
+        }
+        let cache = Arc::new(Mutex::new(cache));
+
+        let handle = LfuCache::spawn_aging(Arc::clone(&cache), Duration::from_millis(1), 0.5);
+        // Wait for the first aging pass rather than sleeping a fixed time
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while cache.lock().unwrap().map[&1].1 == 1001 {
+            assert!(Instant::now() < deadline, "count did not decay");
+            std::thread::yield_now();
+        }
+        handle.stop();
+
+        // The thread has exited, so the count no longer changes
+        let cache = Arc::try_unwrap(cache).ok().unwrap().into_inner().unwrap();
+        assert!(cache.map[&1].1 <= 500);
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_lfu_last_access_seq_follows_access_order() {
+        let mut cache = LfuCache::new(3);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.get(&1);
+        cache.insert(3, "c");
+        cache.get(&2);
+
+        let seqs: Vec<u64> = [1, 3, 2].iter().map(|k| cache.last_access_seq(k).unwrap()).collect();
+        assert!(seqs.windows(2).all(|w| w[0] < w[1]), "{:?}", seqs);
+
+        cache.remove(&3);
+        assert_eq!(cache.last_access_seq(&3), None);
+        // Key 3 went away, so the next insert keeps counting upward
+        cache.insert(4, "d");
+        assert!(cache.last_access_seq(&4).unwrap() > seqs[2]);
+    }
+
+    #[test]
+    fn test_lfu_tiebreak_evicts_longest_value() {
+        let mut cache = LfuCache::new(3);
+        cache.set_lfu_tiebreak(Box::new(|_, a: &String, _, b: &String| b.len().cmp(&a.len())));
+        cache.insert(1, "xx".to_string());
+        cache.insert(2, "xxxxx".to_string());
+        cache.insert(3, "x".to_string());
+
+        // All at frequency 1: the longest value goes, not the oldest key
+        cache.insert(4, "xxx".to_string());
+        assert!(!cache.contains_key(&2));
+        assert!(cache.contains_key(&1));
+
+        // Frequency still decides first: 1 is spared despite being longer than 3
+        cache.get(&1);
+        cache.insert(5, "x".to_string());
+        assert!(!cache.contains_key(&4));
+        assert!(cache.contains_key(&1));
+        assert!(cache.contains_key(&3));
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_lfu_equal_counts_evict_least_recent() {
+        let mut cache = LfuCache::new(2);
+        cache.insert(1, 1);
+        cache.get(&1);
+        cache.insert(2, 2);
+        // Aging merges both into frequency 1 with key 2, the lower count,
+        // ahead in the bucket, though key 1 was accessed less recently
+        cache.age(0.0);
+        assert!(cache.last_access_seq(&1) < cache.last_access_seq(&2));
+
+        cache.insert(3, 3);
+        assert!(!cache.contains_key(&1));
+        assert!(cache.contains_key(&2));
+        assert!(cache.check_invariants().is_ok());
+    }
+}
@@ -0,0 +1,894 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
+use crate::prefetch::{PrefetchType, NoPrefetch};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType};
+
+/// LIRS (Low Inter-reference Recency Set) cache implementation with prefetch strategies
+///
+/// Blocks are ranked by reuse distance rather than recency. Most of the
+/// capacity holds LIR blocks, those re-referenced within a short distance;
+/// the rest is a small queue of resident HIR blocks, and every eviction takes
+/// the oldest of those. The recency stack S also remembers recently seen
+/// HIR blocks, resident or not: a HIR block referenced again while still in
+/// S has proven a shorter reuse distance than the oldest LIR block and swaps
+/// places with it. Loops over a set larger than the cache, which make LRU
+/// miss on every access, leave the LIR set untouched.
+pub struct LirsCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Map from key to node slot, for resident and non-resident blocks
+    map: HashMap<K, usize>,
+
+    /// Node storage; slots of dropped blocks are reused via `free`
+    nodes: Vec<Option<LirsNode<K, V>>>,
+
+    /// Indices of empty slots in `nodes`
+    free: Vec<usize>,
+
+    /// Recency stack S, most recent at the head; the tail is always LIR
+    stack: Chain,
+
+    /// Resident HIR blocks, newest at the head, next victim at the tail
+    hir_queue: Chain,
+
+    /// Non-resident HIR blocks still in S, newest at the head
+    ghosts: Chain,
+
+    /// Number of LIR blocks
+    lir_len: usize,
+
+    /// Maximum number of LIR blocks
+    lir_capacity: usize,
+
+    /// Maximum cache capacity
+    capacity: usize,
+
+    /// Prefetch strategy implementation (pluggable)
+    prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+
+    /// Prefetched but not yet used items
+    prefetch_buffer: HashMap<K, V>,
+
+    /// Limit for prefetch buffer size
+    prefetch_buffer_size: usize,
+
+    /// Prefetch statistics
+    prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+}
+
+/// Block state; `value` is `None` for a non-resident HIR block
+struct LirsNode<K, V> {
+    key: K,
+    value: Option<V>,
+    lir: bool,
+    in_stack: bool,
+    /// Position in the stack S
+    stack: Links,
+    /// Position in the HIR queue when resident, in the ghost list otherwise
+    queue: Links,
+}
+
+/// Neighbours in one list; `prev` points towards the head, `next` towards the tail
+#[derive(Clone, Copy, Default)]
+struct Links {
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// Ends and length of one intrusive list over `nodes`
+#[derive(Clone, Copy, Default)]
+struct Chain {
+    head: Option<usize>,
+    tail: Option<usize>,
+    len: usize,
+}
+
+/// Names one of the three lists threaded through the nodes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum List {
+    Stack,
+    HirQueue,
+    Ghosts,
+}
+
+/// Share of the capacity given to resident HIR blocks, in percent
+const HIR_PERCENT: usize = 1;
+
+impl<K, V> LirsCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Creates a new LIRS cache using the default "no prefetch" strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_custom_prefetch(capacity, Box::new(NoPrefetch))
+    }
+
+    /// Creates a LIRS cache with a custom prefetch strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn with_custom_prefetch(
+        capacity: usize,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+    ) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+
+        Self {
+            map: HashMap::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            stack: Chain::default(),
+            hir_queue: Chain::default(),
+            ghosts: Chain::default(),
+            lir_len: 0,
+            lir_capacity: Self::lir_capacity_for(capacity),
+            capacity,
+            prefetch_strategy,
+            prefetch_buffer: HashMap::new(),
+            prefetch_buffer_size: (capacity / 4).max(1),
+            prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            prefetch_loader: None,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// LIR share of `capacity`, leaving at least one slot to HIR blocks
+    /// unless the cache holds a single entry
+    fn lir_capacity_for(capacity: usize) -> usize {
+        let hir = (capacity * HIR_PERCENT / 100).max(1).min(capacity - 1);
+        capacity - hir
+    }
+
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries, LIR blocks in stack order followed by
+    /// resident HIR blocks newest first
+    ///
+    /// The last entry is the next one evicted.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let lir = std::iter::successors(self.stack.head, |&index| self.node(index).stack.next)
+            .filter(|&index| self.node(index).lir);
+        let hir = std::iter::successors(self.hir_queue.head, |&index| self.node(index).queue.next);
+        lir.chain(hir).filter_map(|index| {
+            let node = self.node(index);
+            node.value.as_ref().map(|value| (&node.key, value))
+        })
+    }
+
+    /// Whether `key` is a resident LIR block
+    pub fn is_lir(&self, key: &K) -> bool {
+        self.map.get(key).is_some_and(|&index| self.node(index).lir)
+    }
+
+    /// Number of resident LIR blocks
+    pub fn lir_len(&self) -> usize {
+        self.lir_len
+    }
+
+    pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
+        &self.prefetch_stats
+    }
+
+    pub fn reset_prefetch_stats(&mut self) {
+        self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
+        self.prefetch_strategy.reset();
+    }
+
+    /// Verifies internal consistency: the three lists agree with each
+    /// node's flags, counts match, the stack bottom is LIR and the
+    /// resident and non-resident populations stay within their bounds
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let stack = self.walk(List::Stack)?;
+        let hir = self.walk(List::HirQueue)?;
+        let ghosts = self.walk(List::Ghosts)?;
+
+        let occupied = self.nodes.iter().flatten().count();
+        if occupied != self.map.len() {
+            return Err(format!("{} occupied slots but map holds {}", occupied, self.map.len()));
+        }
+        if occupied + self.free.len() != self.nodes.len() {
+            return Err(format!(
+                "{} nodes and {} free slots don't cover {} slots",
+                occupied,
+                self.free.len(),
+                self.nodes.len()
+            ));
+        }
+        for (key, &index) in &self.map {
+            match self.nodes.get(index) {
+                Some(Some(node)) if node.key == *key => {}
+                _ => return Err(format!("map does not point at a node holding its key at slot {}", index)),
+            }
+        }
+
+        let mut lir = 0;
+        for node in self.nodes.iter().flatten() {
+            if node.lir {
+                lir += 1;
+                if node.value.is_none() || !node.in_stack {
+                    return Err("LIR block must be resident and in the stack".to_string());
+                }
+            } else if node.value.is_none() && !node.in_stack {
+                return Err("non-resident block left outside the stack".to_string());
+            }
+        }
+        if lir != self.lir_len {
+            return Err(format!("{} LIR blocks but lir_len is {}", lir, self.lir_len));
+        }
+        if stack.iter().filter(|&&index| self.node(index).in_stack).count() != stack.len()
+            || self.nodes.iter().flatten().filter(|node| node.in_stack).count() != stack.len()
+        {
+            return Err("in_stack flags disagree with the stack".to_string());
+        }
+        if hir.iter().any(|&index| self.node(index).lir || self.node(index).value.is_none()) {
+            return Err("HIR queue holds a LIR or non-resident block".to_string());
+        }
+        if ghosts.iter().any(|&index| self.node(index).value.is_some()) {
+            return Err("ghost list holds a resident block".to_string());
+        }
+        if self.lir_len + hir.len() + ghosts.len() != occupied {
+            return Err("a block is in neither the LIR set, the HIR queue nor the ghost list".to_string());
+        }
+        if stack.last().is_some_and(|&bottom| !self.node(bottom).lir) {
+            return Err("stack bottom is not a LIR block".to_string());
+        }
+        if self.lir_len > self.lir_capacity {
+            return Err(format!("{} LIR blocks exceed the LIR capacity {}", self.lir_len, self.lir_capacity));
+        }
+        if self.len() > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.len(), self.capacity));
+        }
+        if ghosts.len() > self.capacity {
+            return Err(format!("{} ghosts exceed capacity {}", ghosts.len(), self.capacity));
+        }
+        Ok(())
+    }
+
+    /// Walks one list head to tail, checking links, flags and the recorded length
+    fn walk(&self, list: List) -> Result<Vec<usize>, String> {
+        let chain = self.chain(list);
+        let mut walked = Vec::with_capacity(chain.len);
+        let mut prev = None;
+        let mut current = chain.head;
+        while let Some(index) = current {
+            if walked.len() == chain.len {
+                return Err(format!("list is longer than its recorded length {}", chain.len));
+            }
+            let node = match self.nodes.get(index) {
+                Some(Some(node)) => node,
+                _ => return Err(format!("list links to empty slot {}", index)),
+            };
+            let links = Self::links(node, list);
+            if links.prev != prev {
+                return Err(format!("list has a broken prev link at slot {}", index));
+            }
+            walked.push(index);
+            prev = current;
+            current = links.next;
+        }
+        if walked.len() != chain.len {
+            return Err(format!("list holds {} nodes but records {}", walked.len(), chain.len));
+        }
+        if chain.tail != prev {
+            return Err("list tail does not point at its last node".to_string());
+        }
+        Ok(walked)
+    }
+
+    /// Update prefetch strategy with new access patterns
+    fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
+        self.prefetch_strategy.update_access_pattern(accessed_key);
+        let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
+
+        for predicted_key in predictions {
+            self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
+            if !self.contains_key(&predicted_key)
+                && !self.prefetch_buffer.contains_key(&predicted_key)
+            {
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
+            }
+        }
+        self.trim_prefetch_buffer();
+    }
+
+    /// Ensure the prefetch buffer does not exceed the configured size
+    fn trim_prefetch_buffer(&mut self) {
+        while self.prefetch_buffer.len() > self.prefetch_buffer_size {
+            if let Some(key) = self.prefetch_buffer.keys().next().cloned() {
+                self.prefetch_buffer.remove(&key);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn node(&self, index: usize) -> &LirsNode<K, V> {
+        self.nodes[index].as_ref().expect("linked slot is occupied")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut LirsNode<K, V> {
+        self.nodes[index].as_mut().expect("linked slot is occupied")
+    }
+
+    fn links(node: &LirsNode<K, V>, list: List) -> Links {
+        match list {
+            List::Stack => node.stack,
+            List::HirQueue | List::Ghosts => node.queue,
+        }
+    }
+
+    fn links_mut(&mut self, index: usize, list: List) -> &mut Links {
+        let node = self.node_mut(index);
+        match list {
+            List::Stack => &mut node.stack,
+            List::HirQueue | List::Ghosts => &mut node.queue,
+        }
+    }
+
+    fn chain(&self, list: List) -> &Chain {
+        match list {
+            List::Stack => &self.stack,
+            List::HirQueue => &self.hir_queue,
+            List::Ghosts => &self.ghosts,
+        }
+    }
+
+    fn chain_mut(&mut self, list: List) -> &mut Chain {
+        match list {
+            List::Stack => &mut self.stack,
+            List::HirQueue => &mut self.hir_queue,
+            List::Ghosts => &mut self.ghosts,
+        }
+    }
+
+    /// Links the node at `index` in at the head of `list`
+    fn push_head(&mut self, list: List, index: usize) {
+        let old_head = self.chain(list).head;
+        *self.links_mut(index, list) = Links { prev: None, next: old_head };
+        match old_head {
+            Some(old_head) => self.links_mut(old_head, list).prev = Some(index),
+            None => self.chain_mut(list).tail = Some(index),
+        }
+        let chain = self.chain_mut(list);
+        chain.head = Some(index);
+        chain.len += 1;
+    }
+
+    /// Unlinks the node at `index` from `list`
+    fn unlink(&mut self, list: List, index: usize) {
+        let Links { prev, next } = std::mem::take(self.links_mut(index, list));
+        match prev {
+            Some(prev) => self.links_mut(prev, list).next = next,
+            None => self.chain_mut(list).head = next,
+        }
+        match next {
+            Some(next) => self.links_mut(next, list).prev = prev,
+            None => self.chain_mut(list).tail = prev,
+        }
+        self.chain_mut(list).len -= 1;
+    }
+
+    /// Moves a node to the top of the stack, pushing it if it is not there
+    fn move_to_stack_top(&mut self, index: usize) {
+        if self.node(index).in_stack {
+            self.unlink(List::Stack, index);
+        }
+        self.push_head(List::Stack, index);
+        self.node_mut(index).in_stack = true;
+    }
+
+    /// Stores a new node and returns its slot
+    fn alloc(&mut self, key: K, value: V) -> usize {
+        let node = LirsNode {
+            key: key.clone(),
+            value: Some(value),
+            lir: false,
+            in_stack: false,
+            stack: Links::default(),
+            queue: Links::default(),
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.map.insert(key, index);
+        index
+    }
+
+    /// Unlinks a node from every list it is in and frees its slot
+    fn release(&mut self, index: usize) -> LirsNode<K, V> {
+        let node = self.node(index);
+        let (lir, in_stack, resident) = (node.lir, node.in_stack, node.value.is_some());
+        if in_stack {
+            self.unlink(List::Stack, index);
+        }
+        if lir {
+            self.lir_len -= 1;
+        } else if resident {
+            self.unlink(List::HirQueue, index);
+        } else {
+            self.unlink(List::Ghosts, index);
+        }
+        let node = self.nodes[index].take().expect("linked slot is occupied");
+        self.map.remove(&node.key);
+        self.free.push(index);
+        node
+    }
+
+    /// Stack pruning: pops HIR blocks off the bottom of S until a LIR
+    /// block is there, forgetting the non-resident ones
+    fn prune(&mut self) {
+        while let Some(bottom) = self.stack.tail {
+            let node = self.node(bottom);
+            if node.lir {
+                break;
+            }
+            if node.value.is_some() {
+                self.unlink(List::Stack, bottom);
+                self.node_mut(bottom).in_stack = false;
+            } else {
+                self.release(bottom);
+            }
+        }
+    }
+
+    /// Turns the bottom LIR block into a resident HIR block at the head of
+    /// the queue, then prunes
+    fn demote_bottom(&mut self) {
+        let Some(bottom) = self.stack.tail else {
+            return;
+        };
+        self.unlink(List::Stack, bottom);
+        let node = self.node_mut(bottom);
+        node.in_stack = false;
+        node.lir = false;
+        self.lir_len -= 1;
+        self.push_head(List::HirQueue, bottom);
+        self.prune();
+    }
+
+    /// Makes a HIR block in S a LIR block at the top of the stack, demoting
+    /// bottom LIR blocks as needed to stay within the LIR capacity
+    fn promote(&mut self, index: usize) {
+        self.move_to_stack_top(index);
+        self.node_mut(index).lir = true;
+        self.lir_len += 1;
+        while self.lir_len > self.lir_capacity {
+            self.demote_bottom();
+        }
+    }
+
+    /// Records a hit on a resident block
+    fn touch(&mut self, index: usize) {
+        let node = self.node(index);
+        if node.lir {
+            let was_bottom = self.stack.tail == Some(index);
+            self.move_to_stack_top(index);
+            if was_bottom {
+                self.prune();
+            }
+        } else if node.in_stack {
+            // Re-referenced within the stack: a shorter reuse distance than the bottom LIR
+            self.unlink(List::HirQueue, index);
+            self.promote(index);
+        } else {
+            self.move_to_stack_top(index);
+            self.unlink(List::HirQueue, index);
+            self.push_head(List::HirQueue, index);
+        }
+    }
+
+    /// Evicts the oldest resident HIR block, falling back to the bottom LIR
+    /// block when no HIR block is resident
+    fn evict(&mut self) -> Option<(K, V)> {
+        if let Some(victim) = self.hir_queue.tail {
+            if !self.node(victim).in_stack {
+                let node = self.release(victim);
+                return node.value.map(|value| (node.key, value));
+            }
+            // Stays in S as a non-resident block to catch a quick return
+            self.unlink(List::HirQueue, victim);
+            let node = self.node_mut(victim);
+            let evicted = node.value.take().map(|value| (node.key.clone(), value));
+            self.push_head(List::Ghosts, victim);
+            self.trim_ghosts();
+            return evicted;
+        }
+        let bottom = self.stack.tail?;
+        let node = self.release(bottom);
+        self.prune();
+        node.value.map(|value| (node.key, value))
+    }
+
+    /// Forgets the oldest non-resident blocks beyond one per unit of capacity
+    fn trim_ghosts(&mut self) {
+        while self.ghosts.len > self.capacity {
+            let Some(oldest) = self.ghosts.tail else {
+                break;
+            };
+            self.release(oldest);
+        }
+    }
+
+    /// Empties every list and slot storage without touching prefetch state
+    fn clear_links(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.stack = Chain::default();
+        self.hir_queue = Chain::default();
+        self.ghosts = Chain::default();
+        self.lir_len = 0;
+    }
+}
+
+impl<K, V> CachePolicy<K, V> for LirsCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Gets a value, moving its block up the stack and possibly into the LIR set
+    fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
+        // Check prefetch buffer first
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            self.prefetch_stats.cache_hits_from_prefetch += 1;
+            self.insert(key.clone(), value);
+            return self.get(key);
+        }
+
+        let index = *self.map.get(key)?;
+        self.node(index).value.as_ref()?;
+        self.touch(index);
+        self.perform_prefetch(key);
+        self.node(index).value.as_ref()
+    }
+
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
+    /// Inserts a block, or updates a resident one in place as a hit
+    ///
+    /// A new block joins the LIR set while it has room and is otherwise a
+    /// resident HIR block; one remembered as non-resident in the stack
+    /// comes back as LIR.
+    fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        // Invalidate prefetch
+        self.prefetch_buffer.remove(&key);
+
+        if let Some(&index) = self.map.get(&key).filter(|&&index| self.node(index).value.is_some()) {
+            self.node_mut(index).value = Some(value);
+            self.touch(index);
+            return;
+        }
+
+        // Eviction may prune or trim the key's own non-resident block
+        if self.len() >= self.capacity && self.evict().is_some() {
+            self.metrics.evictions += 1;
+        }
+        self.metrics.insertions += 1;
+
+        match self.map.get(&key) {
+            Some(&index) => {
+                self.unlink(List::Ghosts, index);
+                self.node_mut(index).value = Some(value);
+                self.promote(index);
+            }
+            None => {
+                let index = self.alloc(key, value);
+                if self.lir_len < self.lir_capacity {
+                    self.move_to_stack_top(index);
+                    self.node_mut(index).lir = true;
+                    self.lir_len += 1;
+                } else {
+                    self.move_to_stack_top(index);
+                    self.push_head(List::HirQueue, index);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            return Some(value);
+        }
+        let index = *self.map.get(key)?;
+        let value = self.release(index).value;
+        self.prune();
+        value
+    }
+
+    fn len(&self) -> usize {
+        self.lir_len + self.hir_queue.len
+    }
+
+    fn clear(&mut self) {
+        self.clear_links();
+        self.prefetch_buffer.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Changes the capacity, re-splitting it between LIR and HIR blocks
+    ///
+    /// Shrinking evicts HIR blocks first and demotes the oldest LIR blocks
+    /// beyond the new LIR share.
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "LIRS cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        self.lir_capacity = Self::lir_capacity_for(new_capacity);
+        while self.lir_len > self.lir_capacity {
+            self.demote_bottom();
+        }
+        while self.len() > self.capacity {
+            if self.evict().is_some() {
+                self.metrics.evictions += 1;
+            }
+        }
+        self.trim_ghosts();
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        let evicted = self.evict()?;
+        self.metrics.evictions += 1;
+        Some(evicted)
+    }
+
+    /// Checks residency without touching the stack or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.get(key).is_some_and(|&index| self.node(index).value.is_some())
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let index = *self.map.get(key)?;
+        self.node_mut(index).value.as_mut()
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let index = *self.map.get(key)?;
+        self.node(index).value.as_ref()
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving the stack untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for node in self.nodes.iter().flatten() {
+            if let Some(value) = &node.value {
+                f(&node.key, value);
+            }
+        }
+    }
+
+    /// Visits every resident value in place, leaving the stack untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for node in self.nodes.iter_mut().flatten() {
+            if let Some(value) = &mut node.value {
+                f(&node.key, value);
+            }
+        }
+    }
+}
+
+impl<K, V> BenchmarkablePolicy<K, V> for LirsCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn policy_type(&self) -> PolicyType {
+        PolicyType::Lirs
+    }
+
+    fn benchmark_name(&self) -> String {
+        format!("{}_cap_{}_prefetch", self.policy_type().name(), self.capacity())
+    }
+
+    fn reset_for_benchmark(&mut self) {
+        self.clear();
+        self.reset_prefetch_stats();
+    }
+}
+
+/// Specialized constructors for concrete key types
+impl LirsCache<i32, String> {
+    pub fn with_prefetch_i32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl LirsCache<i64, String> {
+    pub fn with_prefetch_i64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl LirsCache<usize, String> {
+    pub fn with_prefetch_usize(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_usize(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl LirsCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl LirsCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LIRS cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+
+/// Ensure thread-safety for parallel benchmarks
+unsafe impl<K, V> Send for LirsCache<K, V>
+where
+    K: Hash + Eq + Clone + Send,
+    V: Clone + Send,
+{
+}
+unsafe impl<K, V> Sync for LirsCache<K, V>
+where
+    K: Hash + Eq + Clone + Sync,
+    V: Clone + Sync,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    /// Read-through replay returning the hit ratio
+    fn hit_ratio<C: CachePolicy<i32, i32>>(cache: &mut C, trace: &[i32]) -> f64 {
+        let mut hits = 0;
+        for &key in trace {
+            if cache.get(&key).is_some() {
+                hits += 1;
+            } else {
+                cache.insert(key, key);
+            }
+        }
+        hits as f64 / trace.len() as f64
+    }
+
+    #[test]
+    fn test_lirs_keeps_lir_set_on_loop_larger_than_cache() {
+        // A loop over 12 keys through 10 slots: LRU always evicts the key
+        // that comes back next
+        let trace: Vec<i32> = (0..100).flat_map(|_| 0..12).collect();
+
+        let lru = hit_ratio(&mut LruCache::new(10), &trace);
+        let mut lirs = LirsCache::new(10);
+        let lirs_ratio = hit_ratio(&mut lirs, &trace);
+        assert_eq!(lru, 0.0);
+        assert!(lirs_ratio > 0.7, "LIRS hit ratio {:.3}", lirs_ratio);
+
+        // The first nine keys claimed the LIR set and never left it
+        assert!((0..9).all(|key| lirs.is_lir(&key)));
+        assert_eq!(lirs.lir_len(), 9);
+        assert!(lirs.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_lirs_hir_block_reused_in_stack_becomes_lir() {
+        let mut cache = LirsCache::new(3);
+        // Two LIR slots and one HIR slot
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        assert!(cache.is_lir(&1) && cache.is_lir(&2) && !cache.is_lir(&3));
+
+        // 4 evicts the resident HIR block 3, which stays in S as a ghost
+        cache.insert(4, 4);
+        assert!(!cache.contains_key(&3));
+        assert!(cache.check_invariants().is_ok());
+
+        // 3 returns while still in S and takes the place of the bottom LIR block 1
+        cache.insert(3, 3);
+        assert!(cache.is_lir(&3));
+        assert!(!cache.is_lir(&1));
+        assert!(cache.contains_key(&1));
+        assert!(!cache.contains_key(&4));
+        assert!(cache.check_invariants().is_ok());
+
+        // 1 is now the resident HIR block and goes next
+        cache.insert(5, 5);
+        assert!(!cache.contains_key(&1));
+        assert_eq!(cache.len(), 3);
+
+        cache.remove(&2);
+        cache.resize(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.check_invariants().is_ok());
+    }
+}
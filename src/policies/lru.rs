@@ -1,10 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::ptr::NonNull;
 use std::marker::PhantomData;
-use crate::{CachePolicy, PrefetchStrategy};
+use std::sync::Arc;
+use std::time::Instant;
+use crate::{
+    Access, CacheMetrics, CachePolicy, EvictionListener, EvictionReason, PredictionObserver,
+    PrefetchStrategy, Weigher,
+};
+use crate::clock::Clock;
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::{BenchmarkablePolicy, ByteBudget, PolicyType, walk_linked_list};
 
 /// A Least Recently Used (LRU) cache implementation with integrated prefetch strategies
 ///
@@ -36,9 +42,66 @@ where
     prefetch_buffer_size: usize,
     /// Statistics for prefetch effectiveness
     prefetch_stats: PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Optional hook told about every entry evicted or invalidated
+    eviction_listener: Option<EvictionListener<K, V>>,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Monotonic operation counter stamped onto nodes on every access
+    access_counter: u64,
+    /// Whether `get` maintains per-entry hit counters
+    count_frequency: bool,
+    /// Time source for per-entry access times, set when rate tracking is on
+    rate_clock: Option<Arc<dyn Clock + Send + Sync>>,
+    /// Number of access times kept per entry
+    rate_window: usize,
+    /// Optional hook rewriting or rejecting values before they are stored
+    insert_transform: Option<InsertTransform<K, V>>,
+    /// Weight tracking, present only in byte-capacity mode
+    byte_budget: Option<ByteBudget<K, V>>,
+    /// Operations a fresh entry is shielded from capacity eviction
+    min_residency: usize,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+    /// Where loaded predictions are placed
+    prefetch_target: PrefetchTarget,
     _marker: PhantomData<Box<Node<K, V>>>,
 }
 
+/// Hook rewriting a value before it is stored, or rejecting it with `None`
+pub type InsertTransform<K, V> = Box<dyn Fn(&K, V) -> Option<V> + Send>;
+
+/// Loads the value of a predicted key, or `None` if it has none
+pub type PrefetchLoader<K, V> = Box<dyn Fn(&K) -> Option<V> + Send>;
+
+/// Where values loaded for predicted keys are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefetchTarget {
+    /// Staged in the prefetch buffer and promoted on first access
+    #[default]
+    Buffer,
+    /// Inserted straight into the main cache as the next eviction candidate
+    MainCache,
+}
+
+/// Outcome of [`LruCache::verify_and_repair`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Whether the list or length was found inconsistent and rebuilt
+    pub repaired: bool,
+    /// Nodes reachable from the head before the walk hit a bad link
+    pub reachable: usize,
+    /// Entries in the map, which the rebuilt list holds
+    pub resident: usize,
+    /// Length the cache recorded before the check
+    pub recorded_len: usize,
+}
+
 /// Statistics tracking prefetch effectiveness
 #[derive(Debug, Clone, Default)]
 pub struct PrefetchStats {
@@ -76,16 +139,28 @@ impl PrefetchStats {
 struct Node<K, V> {
     key: K,
     value: V,
+    /// Sequence number of the most recent access
+    seq: u64,
+    /// Sequence number of the insert that created the node
+    inserted: u64,
+    /// Hits served by `get`, maintained only with frequency counting on
+    hits: u64,
+    /// Most recent access times, oldest first, kept only with rate tracking on
+    recent: VecDeque<Instant>,
     prev: Option<NonNull<Node<K, V>>>,
     next: Option<NonNull<Node<K, V>>>,
 }
 
 impl<K, V> Node<K, V> {
     /// Creates a new node with the given key-value pair
-    fn new(key: K, value: V) -> Self {
+    fn new(key: K, value: V, seq: u64) -> Self {
         Self {
             key,
             value,
+            seq,
+            inserted: seq,
+            hits: 0,
+            recent: VecDeque::new(),
             prev: None,
             next: None,
         }
@@ -132,6 +207,20 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            eviction_listener: None,
+            metrics: CacheMetrics::default(),
+            access_counter: 0,
+            count_frequency: false,
+            rate_clock: None,
+            rate_window: 0,
+            insert_transform: None,
+            byte_budget: None,
+            min_residency: 0,
+            prefetch_loader: None,
+            prefetch_target: PrefetchTarget::Buffer,
             _marker: PhantomData,
         }
     }
@@ -141,6 +230,30 @@ where
         Self::new(100)
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs a hook called with every entry the cache evicts or invalidates
+    ///
+    /// Explicit `remove` and `clear` are not reported.
+    pub fn set_eviction_listener(&mut self, listener: EvictionListener<K, V>) {
+        self.eviction_listener = Some(listener);
+    }
+
+    /// Iterates resident entries, most recently used first
+    ///
+    /// This is the reverse of eviction order. Prefetched entries not yet
+    /// read are skipped, and the walk leaves recency untouched.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        std::iter::successors(self.head, |node| unsafe { node.as_ref().next }).map(|node| {
+            let node = unsafe { &*node.as_ptr() };
+            (&node.key, &node.value)
+        })
+    }
+
     /// Returns current prefetch statistics
     pub fn prefetch_stats(&self) -> &PrefetchStats {
         &self.prefetch_stats
@@ -149,9 +262,365 @@ where
     /// Resets prefetch statistics
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: map and list agree in size and
+    /// contents, links are intact and the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() != self.len {
+            return Err(format!("map holds {} keys but len is {}", self.map.len(), self.len));
+        }
+        if self.len > self.capacity + self.min_residency {
+            return Err(format!("len {} exceeds capacity {}", self.len, self.capacity));
+        }
+        let nodes = unsafe {
+            walk_linked_list("LRU list", self.head, self.tail, self.len, |n| (n.prev, n.next))?
+        };
+        for node in nodes {
+            let key = unsafe { &node.as_ref().key };
+            if self.map.get(key) != Some(&node) {
+                return Err("map entry does not point at its list node".to_string());
+            }
+        }
+        if let Some(budget) = &self.byte_budget {
+            budget.check(self.entries())?;
+        }
+        Ok(())
+    }
+
+    /// Cross-checks the map against the list and rebuilds the list on mismatch
+    ///
+    /// A safety net against corruption of the unsafe list links, not a
+    /// substitute for correctness. The map owns every node, so the walk only
+    /// follows links to nodes the map holds and the rebuilt list contains
+    /// exactly the map's entries, ordered by their last access sequence,
+    /// most recent first. That order matches the list's own except for
+    /// entries placed with [`insert_cold`](Self::insert_cold), which move
+    /// to their insert's position. The length and any byte total are
+    /// recomputed as well.
+    pub fn verify_and_repair(&mut self) -> RepairReport {
+        let owned: HashSet<NonNull<Node<K, V>>> = self.map.values().copied().collect();
+        let mut reachable = 0;
+        let mut prev = None;
+        let mut current = self.head;
+        let mut intact = true;
+        while let Some(node_ptr) = current {
+            if !owned.contains(&node_ptr) || reachable == owned.len() {
+                intact = false;
+                break;
+            }
+            let node = unsafe { node_ptr.as_ref() };
+            if node.prev != prev {
+                intact = false;
+                break;
+            }
+            reachable += 1;
+            prev = current;
+            current = node.next;
+        }
+        let report = RepairReport {
+            repaired: !(intact && reachable == owned.len() && self.tail == prev && self.len == owned.len()),
+            reachable,
+            resident: owned.len(),
+            recorded_len: self.len,
+        };
+        if report.repaired {
+            self.rebuild_list();
+        }
+        report
+    }
+
+    /// Relinks every map entry, most recently accessed first
+    fn rebuild_list(&mut self) {
+        let mut nodes: Vec<NonNull<Node<K, V>>> = self.map.values().copied().collect();
+        nodes.sort_by_key(|node| std::cmp::Reverse(unsafe { node.as_ref().seq }));
+        self.head = None;
+        self.tail = None;
+        for node_ptr in nodes {
+            unsafe { self.add_to_back(node_ptr) };
+        }
+        self.len = self.map.len();
+        if let Some(mut budget) = self.byte_budget.take() {
+            budget.reweigh(self.entries());
+            self.byte_budget = Some(budget);
+        }
+    }
+
+    /// Points the tail at the head and drops one from the recorded length
+    #[cfg(test)]
+    pub(crate) fn corrupt_list_for_test(&mut self) {
+        self.tail = self.head;
+        self.len -= 1;
+    }
+
+    /// Total weight of the resident entries, `None` outside byte-capacity mode
+    pub fn total_bytes(&self) -> Option<usize> {
+        self.byte_budget.as_ref().map(ByteBudget::total)
+    }
+
+    /// Returns the sequence number of the most recent get or insert of `key`
+    ///
+    /// Numbers come from a single counter shared by all keys, so a larger
+    /// value always means a more recent access. The counter survives
+    /// `clear`, keeping numbers unique for the lifetime of the cache.
+    pub fn last_access_seq(&self, key: &K) -> Option<u64> {
+        self.map.get(key).map(|node| unsafe { node.as_ref().seq })
+    }
+
+    /// Marks `key` as most recently used without reading its value
+    ///
+    /// Runs no prefetch and counts as no hit. Prefetched entries not yet
+    /// read are not resident and are left alone. Returns whether the key was
+    /// resident.
+    pub fn touch(&mut self, key: &K) -> bool {
+        let Some(&node_ptr) = self.map.get(key) else {
+            return false;
+        };
+        let seq = self.next_seq();
+        unsafe {
+            (*node_ptr.as_ptr()).seq = seq;
+            self.move_to_front(node_ptr);
+        }
+        true
+    }
+
+    /// Position of `key` in eviction order, 0 being the most recently used
+    pub fn recency_rank(&self, key: &K) -> Option<usize> {
+        let target = *self.map.get(key)?;
+        let mut cursor = self.head;
+        let mut rank = 0;
+        while let Some(node_ptr) = cursor {
+            if node_ptr == target {
+                return Some(rank);
+            }
+            rank += 1;
+            cursor = unsafe { node_ptr.as_ref().next };
+        }
+        None
+    }
+
+    /// Shields each new entry from capacity eviction for `accesses` operations
+    ///
+    /// Operations are counted by the access sequence counter, which advances
+    /// on every hit and insert. Eviction on insert skips protected entries
+    /// and takes the least recently used unprotected one instead. If every
+    /// entry is protected nothing is evicted and the cache transiently
+    /// exceeds its capacity, by at most `accesses` entries; the excess is
+    /// evicted by later inserts once it ages out. `resize` and the byte
+    /// budget ignore the protection. Zero, the default, turns it off.
+    pub fn set_min_residency(&mut self, accesses: usize) {
+        self.min_residency = accesses;
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Without a loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Chooses where loaded predictions go
+    ///
+    /// [`PrefetchTarget::MainCache`] skips the promotion from the buffer on
+    /// a correct prediction, at the cost of evicting a resident entry for
+    /// every prediction loaded into a full cache. Predictions are then
+    /// inserted with [`insert_cold`](Self::insert_cold) and are never
+    /// allowed to evict the key whose access triggered them.
+    pub fn set_prefetch_target(&mut self, target: PrefetchTarget) {
+        self.prefetch_target = target;
+    }
+
+    /// Inserts an entry at the least recently used end
+    ///
+    /// The entry is the next eviction candidate unless it is read first. An
+    /// existing key has its value replaced without changing its position.
+    /// Capacity, byte budget and insert transform apply as for `insert`.
+    pub fn insert_cold(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        self.place_cold(key, value, None);
+    }
+
+    /// Installs a hook applied to every value before it is stored
+    ///
+    /// Returning `Some(v)` stores `v` in place of the original value;
+    /// returning `None` rejects the insert. The hook runs before any
+    /// capacity check, so a rejected insert never evicts or updates anything.
+    pub fn set_insert_transform(&mut self, f: InsertTransform<K, V>) {
+        self.insert_transform = Some(f);
+    }
+
+    /// Removes all but the `keep` most recently used entries
+    ///
+    /// The removed entries are returned coldest first, i.e. in the order
+    /// eviction would have dropped them. Unlike `resize`, capacity is left
+    /// unchanged.
+    pub fn split_off_cold(&mut self, keep: usize) -> Vec<(K, V)> {
+        let mut cold = Vec::with_capacity(self.len.saturating_sub(keep));
+        while self.len > keep {
+            match self.pop_lru() {
+                Some(entry) => cold.push(entry),
+                None => break,
+            }
+        }
+        cold
+    }
+
+    /// Moves every entry of `other` into this cache
+    ///
+    /// `other`'s entries are drained coldest first and inserted as most
+    /// recently used, keeping their relative order; this cache's capacity
+    /// applies, so its own coldest entries are evicted first. For a key
+    /// present in both, `resolve(key, mine, theirs)` picks the stored value.
+    pub fn merge_from<F>(&mut self, mut other: Self, mut resolve: F)
+    where
+        F: FnMut(&K, &V, &V) -> V,
+    {
+        for (key, theirs) in other.split_off_cold(0) {
+            let value = match self.map.get(&key) {
+                Some(node_ptr) => resolve(&key, unsafe { &node_ptr.as_ref().value }, &theirs),
+                None => theirs,
+            };
+            self.insert(key, value);
+        }
+    }
+
+    /// Starts counting `get` hits per entry for analytics
+    ///
+    /// Eviction order is unaffected. Counting starts at zero for every
+    /// entry, including ones already resident, and a counter is dropped
+    /// together with its entry.
+    pub fn enable_frequency_counting(&mut self) {
+        self.count_frequency = true;
+    }
+
+    /// Returns how many `get` hits `key` has served since counting was enabled
+    ///
+    /// `None` if the key is not resident or counting is disabled.
+    pub fn frequency_of(&self, key: &K) -> Option<u64> {
+        if !self.count_frequency {
+            return None;
+        }
+        self.map.get(key).map(|node| unsafe { node.as_ref().hits })
+    }
+
+    /// Starts recording the last `window` access times of every entry
+    ///
+    /// Gets that hit and inserts both count as accesses; the window is at
+    /// least 2. Entries already resident start with an empty record, and a
+    /// record is dropped together with its entry. Eviction order is
+    /// unaffected.
+    pub fn enable_access_rate(&mut self, clock: Arc<dyn Clock + Send + Sync>, window: usize) {
+        self.rate_clock = Some(clock);
+        self.rate_window = window.max(2);
+    }
+
+    /// Accesses per second of `key` over its recorded window
+    ///
+    /// Computed as the number of gaps between the recorded accesses divided
+    /// by the time they span, so a steadily hot key reads the same whatever
+    /// the window while a burst after a long pause reads low. `None` if the
+    /// key is not resident, rate tracking is off, or fewer than two accesses
+    /// spanning a nonzero time are recorded.
+    pub fn access_rate(&self, key: &K) -> Option<f64> {
+        self.rate_clock.as_ref()?;
+        let recent = unsafe { &self.map.get(key)?.as_ref().recent };
+        let span = recent.back()?.duration_since(*recent.front()?).as_secs_f64();
+        if span == 0.0 {
+            return None;
+        }
+        Some((recent.len() - 1) as f64 / span)
+    }
+
+    /// Appends the current time to a node's access record if tracking is on
+    fn record_access(&mut self, node_ptr: NonNull<Node<K, V>>) {
+        if let Some(clock) = &self.rate_clock {
+            let recent = unsafe { &mut (*node_ptr.as_ptr()).recent };
+            if recent.len() == self.rate_window {
+                recent.pop_front();
+            }
+            recent.push_back(clock.now());
+        }
+    }
+
+    /// Removes every entry for which `pred` returns true, reporting each to
+    /// the eviction listener as [`EvictionReason::Invalidated`]
+    ///
+    /// Prefetched entries not yet read are checked and dropped too, so a
+    /// stale value can't be promoted later, but only resident entries count
+    /// towards the returned number of entries removed.
+    pub fn invalidate_where<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let stale: Vec<K> = self
+            .prefetch_buffer
+            .iter()
+            .filter(|(key, value)| pred(key, value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            if let Some(value) = self.prefetch_buffer.remove(&key) {
+                self.notify_evicted(&key, &value, EvictionReason::Invalidated);
+            }
+        }
+
+        let stale: Vec<NonNull<Node<K, V>>> = self
+            .map
+            .iter()
+            .filter(|(key, node)| pred(key, unsafe { &node.as_ref().value }))
+            .map(|(_, &node_ptr)| node_ptr)
+            .collect();
+        let removed = stale.len();
+        for node_ptr in stale {
+            let node = unsafe { self.detach(node_ptr) };
+            self.notify_evicted(&node.key, &node.value, EvictionReason::Invalidated);
+        }
+        removed
+    }
+
+    /// Counts a capacity eviction and passes the dropped entry to the
+    /// eviction listener, if any
+    fn notify_evicted(&mut self, key: &K, value: &V, reason: EvictionReason) {
+        if reason == EvictionReason::Capacity {
+            self.metrics.evictions += 1;
+        }
+        if let Some(listener) = self.eviction_listener.as_mut() {
+            listener(key, value, reason);
+        }
+    }
+
+    /// Iterates resident entries in no particular order
+    fn entries(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().map(|(key, node)| (key, unsafe { &node.as_ref().value }))
+    }
+
+    /// Evicts least recently used items until the byte budget is met
+    ///
+    /// Inserts drop entries that can't fit alone, so the entry just inserted
+    /// or updated, being the most recent, is never the one evicted.
+    fn evict_over_budget(&mut self) {
+        while matches!(&self.byte_budget, Some(budget) if budget.is_over()) {
+            match self.pop_lru() {
+                Some((key, value)) => self.notify_evicted(&key, &value, EvictionReason::Capacity),
+                None => break,
+            }
+        }
+    }
+
+    /// Advances the access counter and returns the new sequence number
+    fn next_seq(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
     /// Sets the prefetch buffer size
     pub fn set_prefetch_buffer_size(&mut self, size: usize) {
         self.prefetch_buffer_size = size.max(1);
@@ -171,28 +640,38 @@ where
 
     /// Performs prefetch predictions and populates the prefetch buffer
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         // Update prefetch strategy with the accessed key
         self.prefetch_strategy.update_access_pattern(accessed_key);
 
         // Get predictions from the strategy
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             // Only prefetch if the key is not already in main cache or prefetch buffer
             if !self.map.contains_key(&predicted_key) &&
                !self.prefetch_buffer.contains_key(&predicted_key) {
-
-                // Here you would typically load the value from your data source
-                // For now, we'll simulate with a placeholder
-                // In a real implementation, this would be:
-                // if let Some(value) = self.load_from_source(&predicted_key) {
-                //     self.prefetch_buffer.insert(predicted_key, value);
-                // }
-
-                // For demonstration, we'll skip actual prefetch loading
-                // but track the prediction
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                match self.prefetch_target {
+                    PrefetchTarget::Buffer => {
+                        self.prefetch_buffer.insert(predicted_key, value);
+                    }
+                    PrefetchTarget::MainCache => {
+                        // The accessed entry's value is about to be returned
+                        let spare = self.map.get(accessed_key).copied();
+                        self.place_cold(predicted_key, value, spare);
+                    }
+                }
             }
         }
 
@@ -245,6 +724,22 @@ where
         }
     }
 
+    /// Unlinks a node and takes it out of the map and the byte budget
+    ///
+    /// # Safety
+    /// The caller must ensure that node_ptr is a valid pointer to a node
+    /// that exists in the current cache's linked list.
+    unsafe fn detach(&mut self, node_ptr: NonNull<Node<K, V>>) -> Box<Node<K, V>> {
+        unsafe { self.remove_from_list(node_ptr) };
+        let node = unsafe { Box::from_raw(node_ptr.as_ptr()) };
+        self.map.remove(&node.key);
+        self.len -= 1;
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.sub(&node.key, &node.value);
+        }
+        node
+    }
+
     /// Adds a node to the front of the linked list
     ///
     /// # Safety
@@ -265,17 +760,119 @@ where
         self.head = Some(node_ptr);
     }
 
+    /// Adds a node to the back of the linked list
+    ///
+    /// # Safety
+    /// The caller must ensure that node_ptr is a valid pointer to a node
+    /// that is not currently in any linked list.
+    unsafe fn add_to_back(&mut self, mut node_ptr: NonNull<Node<K, V>>) {
+        let node = unsafe { node_ptr.as_mut() };
+        node.next = None;
+        node.prev = self.tail;
+
+        if let Some(mut old_tail) = self.tail {
+            unsafe { old_tail.as_mut() }.next = Some(node_ptr);
+        } else {
+            // List was empty
+            self.head = Some(node_ptr);
+        }
+
+        self.tail = Some(node_ptr);
+    }
+
     /// Removes and deallocates the least recently used item (tail)
     ///
     /// Returns the key of the evicted item, or None if the cache is empty.
     fn evict_lru(&mut self) -> Option<K> {
+        self.pop_lru().map(|(key, _)| key)
+    }
+
+    /// Evicts the least recently used entry past its minimum residency,
+    /// never choosing `spare`
+    ///
+    /// Returns false if every other entry is still protected.
+    fn evict_unprotected(&mut self, spare: Option<NonNull<Node<K, V>>>) -> bool {
+        self.take_unprotected(spare).is_some()
+    }
+
+    /// [`evict_unprotected`](Self::evict_unprotected), returning the evicted entry
+    fn take_unprotected(&mut self, spare: Option<NonNull<Node<K, V>>>) -> Option<(K, V)> {
+        let mut cursor = self.tail;
+        while let Some(node_ptr) = cursor {
+            let node = unsafe { node_ptr.as_ref() };
+            if Some(node_ptr) != spare
+                && self.access_counter - node.inserted >= self.min_residency as u64
+            {
+                let node = unsafe { self.detach(node_ptr) };
+                self.notify_evicted(&node.key, &node.value, EvictionReason::Capacity);
+                return Some((node.key, node.value));
+            }
+            cursor = node.prev;
+        }
+        None
+    }
+
+    /// Inserts at the least recently used end, evicting anything but `spare`
+    ///
+    /// A new key is dropped if room can only be made by evicting `spare`.
+    fn place_cold(&mut self, key: K, value: V, spare: Option<NonNull<Node<K, V>>>) {
+        let value = match &self.insert_transform {
+            Some(transform) => match transform(&key, value) {
+                Some(value) => value,
+                None => return,
+            },
+            None => value,
+        };
+        if matches!(&self.byte_budget, Some(budget) if !budget.fits(&key, &value)) {
+            self.remove(&key);
+            return;
+        }
+        self.prefetch_buffer.remove(&key);
+
+        if let Some(&node_ptr) = self.map.get(&key) {
+            unsafe {
+                let node = &mut *node_ptr.as_ptr();
+                if let Some(budget) = self.byte_budget.as_mut() {
+                    budget.sub(&node.key, &node.value);
+                    budget.add(&node.key, &value);
+                }
+                node.value = value;
+            }
+            self.evict_over_budget();
+            return;
+        }
+
+        while self.len >= self.capacity {
+            if !self.evict_unprotected(spare) {
+                if spare.is_some() {
+                    return;
+                }
+                break;
+            }
+        }
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.add(&key, &value);
+        }
+        let seq = self.next_seq();
+        let node_ptr = unsafe {
+            NonNull::new_unchecked(Box::into_raw(Box::new(Node::new(key.clone(), value, seq))))
+        };
+        self.map.insert(key, node_ptr);
+        unsafe { self.add_to_back(node_ptr) };
+        self.len += 1;
+        self.metrics.insertions += 1;
+        // The new entry is the tail, so it goes first if it overran the budget
+        self.evict_over_budget();
+    }
+
+    /// Unlinks the least recently used item (tail) and returns its entry
+    fn pop_lru(&mut self) -> Option<(K, V)> {
         if let Some(tail_ptr) = self.tail {
             unsafe {
                 let tail_node = Box::from_raw(tail_ptr.as_ptr());
-                let key = tail_node.key.clone();
 
                 // Remove from hash map
-                self.map.remove(&key);
+                self.map.remove(&tail_node.key);
 
                 // Update tail pointer
                 self.tail = tail_node.prev;
@@ -288,7 +885,10 @@ where
                 }
 
                 self.len -= 1;
-                Some(key)
+                if let Some(budget) = self.byte_budget.as_mut() {
+                    budget.sub(&tail_node.key, &tail_node.value);
+                }
+                Some((tail_node.key, tail_node.value))
             }
         } else {
             None
@@ -324,6 +924,24 @@ impl LruCache<usize, String> {
     }
 }
 
+impl LruCache<u32, String> {
+    /// Creates a new u32 LRU cache with specified prefetch strategy
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LRU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl LruCache<u64, String> {
+    /// Creates a new u64 LRU cache with specified prefetch strategy
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "LRU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 impl<K, V> CachePolicy<K, V> for LruCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -335,6 +953,10 @@ where
     /// This operation moves the accessed item to the front of the LRU order
     /// and triggers prefetch predictions for future accesses.
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check if it's in the prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             // Move from prefetch buffer to main cache
@@ -346,8 +968,14 @@ where
         }
 
         if let Some(&node_ptr) = self.map.get(key) {
+            let seq = self.next_seq();
             unsafe {
                 // Move to front (mark as recently used)
+                (*node_ptr.as_ptr()).seq = seq;
+                if self.count_frequency {
+                    (*node_ptr.as_ptr()).hits += 1;
+                }
+                self.record_access(node_ptr);
                 self.move_to_front(node_ptr);
 
                 // Perform prefetch predictions
@@ -360,30 +988,66 @@ where
         }
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Inserts a key-value pair into the cache
     ///
     /// If the key already exists, updates the value and moves it to front.
     /// If the cache is at capacity, evicts the least recently used item first.
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        let value = match &self.insert_transform {
+            Some(transform) => match transform(&key, value) {
+                Some(value) => value,
+                None => return,
+            },
+            None => value,
+        };
+
+        if matches!(&self.byte_budget, Some(budget) if !budget.fits(&key, &value)) {
+            self.remove(&key);
+            return;
+        }
+
         // Remove from prefetch buffer if it exists there
         self.prefetch_buffer.remove(&key);
 
         // Check if key already exists
         if let Some(existing_ptr) = self.map.get_mut(&key) {
             let existing_ptr_value = *existing_ptr; // copy NonNull
+            let seq = self.next_seq();
             unsafe {
-                (*existing_ptr_value.as_ptr()).value = value;
+                let node = &mut *existing_ptr_value.as_ptr();
+                if let Some(budget) = self.byte_budget.as_mut() {
+                    budget.sub(&node.key, &node.value);
+                    budget.add(&node.key, &value);
+                }
+                node.value = value;
+                node.seq = seq;
+                self.record_access(existing_ptr_value);
                 self.move_to_front(existing_ptr_value);
             }
+            self.evict_over_budget();
             return;
         }
 
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.add(&key, &value);
+        }
+
         // Create new node
-        let new_node = Box::new(Node::new(key.clone(), value));
+        let seq = self.next_seq();
+        let new_node = Box::new(Node::new(key.clone(), value, seq));
         let node_ptr = unsafe { NonNull::new_unchecked(Box::into_raw(new_node)) };
 
         // Add to hash map
         self.map.insert(key, node_ptr);
+        self.record_access(node_ptr);
 
         // Add to front of list
         unsafe {
@@ -391,17 +1055,18 @@ where
         }
 
         self.len += 1;
+        self.metrics.insertions += 1;
 
         // Check if we need to evict
-        if self.len > self.capacity {
-            self.evict_lru();
-        }
+        while self.len > self.capacity && self.evict_unprotected(None) {}
+        self.evict_over_budget();
     }
 
     /// Removes an entry from the cache
     ///
     /// Returns the removed value if it existed, `None` otherwise.
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         // Check prefetch buffer first
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
@@ -415,6 +1080,9 @@ where
                 // Deallocate and extract value
                 let node = Box::from_raw(node_ptr.as_ptr());
                 self.len -= 1;
+                if let Some(budget) = self.byte_budget.as_mut() {
+                    budget.sub(&node.key, &node.value);
+                }
 
                 Some(node.value)
             }
@@ -439,12 +1107,92 @@ where
         self.tail = None;
         self.len = 0;
         self.prefetch_buffer.clear();
+        if let Some(budget) = self.byte_budget.as_mut() {
+            budget.clear();
+        }
     }
 
     /// Returns the maximum capacity of the cache
     fn capacity(&self) -> usize {
         self.capacity
     }
+
+    /// Changes the capacity, evicting least recently used items if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "LRU cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.len > self.capacity {
+            if let Some((key, value)) = self.pop_lru() {
+                self.notify_evicted(&key, &value, EvictionReason::Capacity);
+            }
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.take_unprotected(None)
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let node_ptr = *self.map.get(key)?;
+        Some(unsafe { &mut (*node_ptr.as_ptr()).value })
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let node_ptr = self.map.get(key)?;
+        Some(unsafe { &node_ptr.as_ref().value })
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in self.entries() {
+            f(key, value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    ///
+    /// In byte-capacity mode the entries are reweighed afterwards and the
+    /// least recently used ones evicted if the rewrite overran the budget.
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, node) in &self.map {
+            unsafe { f(key, &mut (*node.as_ptr()).value) }
+        }
+        if let Some(mut budget) = self.byte_budget.take() {
+            budget.reweigh(self.entries());
+            self.byte_budget = Some(budget);
+            self.evict_over_budget();
+        }
+    }
+
+    /// Evicts least recently used entries until the weighed total fits
+    fn set_byte_capacity(&mut self, bytes: usize, weigher: Weigher<K, V>) {
+        let mut budget = ByteBudget::new(bytes, weigher);
+        budget.reweigh(self.entries());
+        self.byte_budget = Some(budget);
+        self.evict_over_budget();
+    }
 }
 
 impl<K, V> BenchmarkablePolicy<K, V> for LruCache<K, V>
@@ -467,6 +1215,45 @@ where
         self.clear();
         self.reset_prefetch_stats();
     }
+
+    /// Also restarts access sequence numbers, which survive `clear`
+    fn reset_all(&mut self) {
+        self.reset_for_benchmark();
+        self.reset_metrics();
+        self.access_counter = 0;
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K, V> serde::Serialize for LruCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Serialize,
+    V: Clone + serde::Serialize,
+{
+    /// Writes the capacity and the entries, least recently used first
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        entries.reverse();
+        super::Snapshot { capacity: self.capacity, entries }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K, V> serde::Deserialize<'de> for LruCache<K, V>
+where
+    K: Hash + Eq + Clone + serde::Deserialize<'de>,
+    V: Clone + serde::Deserialize<'de>,
+{
+    /// Replays the entries oldest first, so recency order comes back intact
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = super::Snapshot::<(K, V)>::deserialize(deserializer)?;
+        snapshot.validate()?;
+        let mut cache = Self::new(snapshot.capacity);
+        for (key, value) in snapshot.entries {
+            cache.insert(key, value);
+        }
+        Ok(cache)
+    }
 }
 
 /// Safe wrapper that ensures proper cleanup
@@ -494,3 +1281,415 @@ where
     V: Clone + Sync,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Predicts the next key and then the accessed key again
+    struct PingPongPrefetch;
+
+    impl PrefetchStrategy<i32> for PingPongPrefetch {
+        fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
+            vec![accessed_key + 1, *accessed_key]
+        }
+        fn update_access_pattern(&mut self, _key: &i32) {}
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn test_prefetch_target_main_cache_skips_promotion() {
+        let mut cache = LruCache::with_custom_prefetch(4, Box::new(PingPongPrefetch));
+        cache.set_prefetch_loader(Box::new(|key| Some(key * 10)));
+        for key in 1..=3 {
+            cache.insert(key, key * 10);
+        }
+        // Staged in the buffer, promoted on first access
+        cache.get(&3);
+        assert!(!cache.contains_key(&4));
+        assert_eq!(cache.access(&4), (Access::PrefetchHit, Some(&40)));
+        assert_eq!(cache.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut cache = LruCache::with_custom_prefetch(4, Box::new(PingPongPrefetch));
+        cache.set_prefetch_loader(Box::new(|key| Some(key * 10)));
+        cache.set_prefetch_target(PrefetchTarget::MainCache);
+        for key in 1..=3 {
+            cache.insert(key, key * 10);
+        }
+        cache.get(&3);
+        assert!(cache.contains_key(&4));
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.access(&4), (Access::MainHit, Some(&40)));
+        assert_eq!(cache.prefetch_stats().cache_hits_from_prefetch, 0);
+
+        // 5 was predicted from 4 and evicted 1; it is now the coldest entry
+        assert!(cache.contains_key(&5) && !cache.contains_key(&1));
+        cache.insert(9, 90);
+        assert!(!cache.contains_key(&5));
+        assert!([2, 3, 4, 9].iter().all(|key| cache.contains_key(key)));
+
+        // A single slot is never given up to its own prediction
+        let mut single = LruCache::with_custom_prefetch(1, Box::new(PingPongPrefetch));
+        single.set_prefetch_loader(Box::new(|key| Some(key * 10)));
+        single.set_prefetch_target(PrefetchTarget::MainCache);
+        single.insert(1, 10);
+        assert_eq!(single.get(&1), Some(&10));
+        assert!(!single.contains_key(&2));
+    }
+
+    #[test]
+    fn test_lru_verify_and_repair_rebuilds_corrupted_list() {
+        let mut cache = LruCache::new(5);
+        for key in 1..=5 {
+            cache.insert(key, key);
+        }
+        cache.get(&2);
+        let clean = cache.verify_and_repair();
+        assert!(!clean.repaired);
+
+        cache.corrupt_list_for_test();
+        assert!(cache.check_invariants().is_err());
+        let report = cache.verify_and_repair();
+        assert!(report.repaired);
+        assert_eq!((report.resident, report.recorded_len), (5, 4));
+        assert!(cache.check_invariants().is_ok());
+        assert_eq!(cache.len(), 5);
+
+        // Recency order survives: 1 and then 3 are the coldest
+        cache.insert(6, 6);
+        assert!(!cache.contains_key(&1));
+        cache.insert(7, 7);
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn test_lru_resize_drops_least_recently_used() {
+        let mut cache = LruCache::new(8);
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+        cache.get(&5);
+        cache.get(&1);
+        cache.get(&6);
+
+        cache.resize(3);
+        let kept: Vec<i32> = (0..8).filter(|k| cache.contains_key(k)).collect();
+        assert_eq!(kept, vec![1, 5, 6]);
+        // Recency survives the shrink: 5 is now the oldest
+        cache.insert(9, 9);
+        assert!(!cache.contains_key(&5));
+
+        cache.resize(6);
+        for i in 20..23 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.len(), 6);
+        assert!(cache.contains_key(&1) && cache.contains_key(&6) && cache.contains_key(&9));
+    }
+
+    #[test]
+    fn test_lru_last_access_seq_follows_access_order() {
+        let mut cache = LruCache::new(4);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        cache.get(&1);
+        cache.insert(2, 20);
+
+        let seqs: Vec<u64> = [3, 1, 2].iter().map(|k| cache.last_access_seq(k).unwrap()).collect();
+        assert!(seqs.windows(2).all(|w| w[0] < w[1]), "{:?}", seqs);
+        assert_eq!(cache.last_access_seq(&4), None);
+        // Misses and contains_key leave stamps alone
+        cache.get(&4);
+        assert!(cache.contains_key(&3));
+        assert_eq!(cache.last_access_seq(&3), Some(seqs[0]));
+    }
+
+    #[test]
+    fn test_lru_frequency_counting_keeps_lru_eviction() {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, 1);
+        assert_eq!(cache.frequency_of(&1), None);
+        cache.enable_frequency_counting();
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+
+        for _ in 0..5 {
+            cache.get(&1);
+        }
+        cache.get(&2);
+        cache.get(&3);
+        cache.get(&3);
+        assert_eq!(cache.frequency_of(&1), Some(5));
+        assert_eq!(cache.frequency_of(&2), Some(1));
+        assert_eq!(cache.frequency_of(&3), Some(2));
+
+        // Key 1 is the most frequent but least recent, so LRU still evicts it
+        cache.insert(4, 4);
+        assert!(!cache.contains_key(&1));
+        assert_eq!(cache.frequency_of(&1), None);
+        assert_eq!(cache.frequency_of(&4), Some(0));
+    }
+
+    #[test]
+    fn test_lru_split_off_cold_keeps_most_recent() {
+        let mut cache = LruCache::new(8);
+        for i in 0..8 {
+            cache.insert(i, i * 10);
+        }
+        // Refresh the two oldest keys so they count as hot
+        cache.get(&0);
+        cache.get(&1);
+
+        let cold = cache.split_off_cold(4);
+        assert_eq!(cold, vec![(2, 20), (3, 30), (4, 40), (5, 50)]);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.capacity(), 8);
+        for key in [6, 7, 0, 1] {
+            assert!(cache.contains_key(&key));
+        }
+
+        // Nothing to split once the cache already fits
+        assert!(cache.split_off_cold(4).is_empty());
+        assert_eq!(cache.split_off_cold(0).len(), 4);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lru_insert_transform_rejects_and_rewrites() {
+        let mut cache: LruCache<i32, String> = LruCache::new(2);
+        cache.set_insert_transform(Box::new(|_, value: String| {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.trim().to_string())
+            }
+        }));
+
+        cache.insert(1, "  one ".to_string());
+        cache.insert(2, "two".to_string());
+        assert_eq!(cache.get(&1).map(String::as_str), Some("one"));
+
+        // A rejected insert neither evicts nor overwrites
+        cache.insert(3, String::new());
+        cache.insert(2, String::new());
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.get(&2).map(String::as_str), Some("two"));
+        assert!(cache.contains_key(&1));
+    }
+
+    #[test]
+    fn test_prediction_observer_sees_each_prediction() {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = LruCache::with_custom_prefetch(4, Box::new(PingPongPrefetch));
+        let tap = Arc::clone(&seen);
+        cache.set_prediction_observer(Box::new(move |key: &i32, predicted: &[i32]| {
+            tap.lock().unwrap().push((*key, predicted.to_vec()));
+        }));
+
+        cache.insert(1, 10);
+        cache.insert(2, 20);
+        cache.get(&1);
+        cache.get(&2);
+        // Misses don't run prefetch, so the observer is not called
+        cache.get(&3);
+
+        assert_eq!(*seen.lock().unwrap(), vec![(1, vec![2, 1]), (2, vec![3, 2])]);
+        assert_eq!(cache.prefetch_stats().predictions_made, 4);
+        assert_eq!(cache.get(&1), Some(&10));
+    }
+
+    #[test]
+    fn test_lru_access_rate_tracks_intervals() {
+        use crate::clock::MockClock;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let clock = Arc::new(MockClock::new());
+        let mut cache = LruCache::new(8);
+        cache.enable_access_rate(clock.clone(), 5);
+
+        // Key 1 every 250ms, key 2 in a burst of four 50ms gaps after a pause
+        cache.insert(1, 10);
+        cache.insert(3, 30);
+        for _ in 0..8 {
+            clock.advance(Duration::from_millis(250));
+            cache.get(&1);
+        }
+        cache.insert(2, 20);
+        for _ in 0..4 {
+            clock.advance(Duration::from_millis(50));
+            cache.get(&2);
+        }
+
+        assert!((cache.access_rate(&1).unwrap() - 4.0).abs() < 1e-9);
+        assert!((cache.access_rate(&2).unwrap() - 20.0).abs() < 1e-9);
+        // Only the insert of key 3 is recorded, and misses record nothing
+        assert_eq!(cache.access_rate(&3), None);
+        cache.get(&4);
+        assert_eq!(cache.access_rate(&4), None);
+
+        // The window keeps only the last five accesses: a slow tail lowers the rate
+        clock.advance(Duration::from_secs(2));
+        cache.get(&2);
+        assert!((cache.access_rate(&2).unwrap() - 4.0 / 2.15).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lru_invalidate_where_notifies_listener() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Versioned {
+            version: u32,
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = LruCache::new(3);
+        let tap = Arc::clone(&seen);
+        cache.set_eviction_listener(Box::new(move |key: &i32, value: &Versioned, reason| {
+            tap.lock().unwrap().push((*key, value.version, reason));
+        }));
+
+        for key in 0..4 {
+            cache.insert(key, Versioned { version: key as u32 });
+        }
+        // Key 0 made room for key 3
+        assert_eq!(*seen.lock().unwrap(), vec![(0, 0, EvictionReason::Capacity)]);
+        seen.lock().unwrap().clear();
+
+        assert_eq!(cache.invalidate_where(|_, value| value.version < 3), 2);
+        let mut invalidated = seen.lock().unwrap().clone();
+        invalidated.sort_by_key(|&(key, _, _)| key);
+        assert_eq!(
+            invalidated,
+            vec![(1, 1, EvictionReason::Invalidated), (2, 2, EvictionReason::Invalidated)]
+        );
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key(&3));
+        assert!(cache.check_invariants().is_ok());
+
+        // Nothing matches: nothing removed, nothing reported
+        assert_eq!(cache.invalidate_where(|_, value| value.version > 10), 0);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_lru_invalidate_where_counts_only_resident_entries() {
+        let mut cache = LruCache::with_custom_prefetch(4, Box::new(PingPongPrefetch));
+        cache.set_prefetch_loader(Box::new(|key| Some(key * 10)));
+        cache.insert(1, 10);
+        cache.insert(3, 30);
+        // 2 is staged in the prefetch buffer, 1 and 3 are resident
+        cache.get(&1);
+
+        assert_eq!(cache.invalidate_where(|key, _| *key <= 2), 1);
+        assert_eq!(cache.len(), 1);
+        // The staged value was dropped as well, so a read misses
+        assert_eq!(cache.remove(&2), None);
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_lru_merge_from_resolves_conflicts() {
+        let mut mine = LruCache::new(4);
+        for (key, value) in [(1, 10), (2, 5), (3, 30)] {
+            mine.insert(key, value);
+        }
+        let mut theirs = LruCache::new(8);
+        for (key, value) in [(2, 20), (3, 3), (4, 40)] {
+            theirs.insert(key, value);
+        }
+
+        let mut conflicts = Vec::new();
+        mine.merge_from(theirs, |key, a, b| {
+            conflicts.push(*key);
+            *a.max(b)
+        });
+
+        conflicts.sort();
+        assert_eq!(conflicts, vec![2, 3]);
+        assert_eq!(mine.len(), 4);
+        assert_eq!(mine.get(&2), Some(&20));
+        assert_eq!(mine.get(&3), Some(&30));
+        assert_eq!(mine.get(&4), Some(&40));
+        assert_eq!(mine.get(&1), Some(&10));
+
+        // Merging past capacity evicts this cache's coldest entries first
+        let mut more = LruCache::new(8);
+        for key in 5..8 {
+            more.insert(key, key * 10);
+        }
+        mine.merge_from(more, |_, a, _| *a);
+        assert_eq!(mine.len(), 4);
+        assert!(mine.contains_key(&1));
+        assert!((5..8).all(|key| mine.contains_key(&key)));
+        assert!(mine.check_invariants().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lru_serde_round_trip_preserves_eviction_order() {
+        let mut original = LruCache::new(4);
+        for i in 0..4 {
+            original.insert(i, format!("v{}", i));
+        }
+        original.get(&0);
+        original.get(&2);
+
+        let json = serde_json::to_string(&original).unwrap();
+        let mut restored: LruCache<i32, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.capacity(), 4);
+        let order: Vec<i32> = restored.iter().map(|(k, _)| *k).collect();
+        assert_eq!(order, vec![2, 0, 3, 1]);
+        assert!(restored.check_invariants().is_ok());
+
+        // The next eviction hits the same key in both
+        original.insert(9, "v9".to_string());
+        restored.insert(9, "v9".to_string());
+        let kept = |cache: &LruCache<i32, String>| (0..4).filter(|k| cache.contains_key(k)).collect::<Vec<_>>();
+        assert_eq!(kept(&restored), kept(&original));
+        assert!(!restored.contains_key(&1));
+
+        assert!(serde_json::from_str::<LruCache<i32, String>>(r#"{"capacity":0,"entries":[]}"#).is_err());
+        assert!(serde_json::from_str::<LruCache<i32, String>>(r#"{"capacity":1,"entries":[[1,"a"],[2,"b"]]}"#).is_err());
+    }
+
+    #[test]
+    fn test_lru_metrics_count_insertions_and_evictions() {
+        let mut cache = LruCache::new(100);
+        for key in 0..300 {
+            cache.insert(key, key);
+        }
+        assert_eq!(cache.metrics(), CacheMetrics { insertions: 300, evictions: 200 });
+
+        // Updates, removals and clearing are neither
+        cache.insert(299, 0);
+        cache.remove(&298);
+        cache.clear();
+        assert_eq!(cache.metrics(), CacheMetrics { insertions: 300, evictions: 200 });
+    }
+
+    #[test]
+    fn test_lru_get_mut_updates_in_place_and_refreshes_recency() {
+        let mut cache = LruCache::new(3);
+        for key in 1..=3 {
+            cache.insert(key, vec![key]);
+        }
+
+        cache.get_mut(&1).unwrap().push(10);
+        assert_eq!(cache.get(&1), Some(&vec![1, 10]));
+        assert_eq!(cache.get_mut(&4), None);
+
+        // 1 was just used, so 2 is the least recently used
+        cache.get_mut(&3).unwrap().clear();
+        cache.insert(4, vec![4]);
+        assert!(!cache.contains_key(&2));
+        assert_eq!(cache.peek(&1), Some(&vec![1, 10]));
+        assert_eq!(cache.peek(&3), Some(&vec![]));
+    }
+}
@@ -1,9 +1,25 @@
 //! Cache policy implementations containing eviction strategies implementing CachePolicy.
 
+use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
-use crate::CachePolicy;
+use std::ptr::NonNull;
+use crate::{CachePolicy, Weigher};
+
+/// Runs a policy's `check_invariants` at a method boundary
+///
+/// Only active in debug builds with the `check-invariants` feature, so the
+/// O(n) walk costs nothing otherwise.
+macro_rules! debug_check_invariants {
+    ($cache:expr) => {
+        #[cfg(all(feature = "check-invariants", debug_assertions))]
+        if let Err(msg) = $cache.check_invariants() {
+            debug_assert!(false, "cache invariant violated: {}", msg);
+        }
+    };
+}
 
 pub mod lru;
+pub mod dense_lru;
 pub mod mru;
 pub mod fifo;
 pub mod lfu;
@@ -13,17 +29,224 @@ pub mod clock;
 pub mod two_q;
 pub mod slru;
 pub mod car;
+pub mod sieve;
+pub mod lirs;
+pub mod s3_fifo;
+pub mod unbounded;
+pub mod any;
 
-pub use lru::LruCache;
+pub use lru::{LruCache, PrefetchLoader, PrefetchTarget, RepairReport};
+pub use dense_lru::DenseLruCache;
 pub use mru::MruCache;
 pub use fifo::FifoCache;
 pub use lfu::LfuCache;
+#[cfg(feature = "aging")]
+pub use lfu::AgingHandle;
 pub use random::RandomCache;
 pub use arc::ArcCache;
 pub use clock::ClockCache;
 pub use two_q::TwoQCache;
 pub use slru::SlruCache;
 pub use car::CarCache;
+pub use sieve::SieveCache;
+pub use lirs::LirsCache;
+pub use s3_fifo::S3FifoCache;
+pub use unbounded::UnboundedCache;
+pub use any::AnyCache;
+
+/// Walks a doubly-linked list from head to tail, checking that prev/next
+/// links agree, the tail is the last node and the length is `expected_len`
+///
+/// Returns the nodes in list order. `links` yields a node's `(prev, next)`.
+///
+/// # Safety
+/// Every pointer reachable from `head` must point at a live node.
+pub(crate) unsafe fn walk_linked_list<N>(
+    name: &str,
+    head: Option<NonNull<N>>,
+    tail: Option<NonNull<N>>,
+    expected_len: usize,
+    links: impl Fn(&N) -> (Option<NonNull<N>>, Option<NonNull<N>>),
+) -> Result<Vec<NonNull<N>>, String> {
+    let mut nodes = Vec::with_capacity(expected_len);
+    let mut prev: Option<NonNull<N>> = None;
+    let mut current = head;
+
+    while let Some(node) = current {
+        // Bail out on cycles instead of looping forever
+        if nodes.len() == expected_len {
+            return Err(format!("{} is longer than its recorded size {}", name, expected_len));
+        }
+        let (node_prev, node_next) = links(unsafe { node.as_ref() });
+        if node_prev != prev {
+            return Err(format!("{} has a broken prev link at position {}", name, nodes.len()));
+        }
+        nodes.push(node);
+        prev = Some(node);
+        current = node_next;
+    }
+
+    if nodes.len() != expected_len {
+        return Err(format!("{} holds {} nodes but records size {}", name, nodes.len(), expected_len));
+    }
+    if tail != prev {
+        return Err(format!("{} tail does not point at its last node", name));
+    }
+    Ok(nodes)
+}
+
+/// Running weight of the resident entries of a policy in byte-capacity mode
+///
+/// The owning policy reports every entry it stores or drops and evicts
+/// while [`is_over`](Self::is_over) holds.
+pub(crate) struct ByteBudget<K, V> {
+    limit: usize,
+    total: usize,
+    weigher: Weigher<K, V>,
+}
+
+impl<K, V> ByteBudget<K, V> {
+    pub(crate) fn new(limit: usize, weigher: Weigher<K, V>) -> Self {
+        Self { limit, total: 0, weigher }
+    }
+
+    pub(crate) fn total(&self) -> usize {
+        self.total
+    }
+
+    pub(crate) fn is_over(&self) -> bool {
+        self.total > self.limit
+    }
+
+    /// Whether the entry alone fits in the budget
+    pub(crate) fn fits(&self, key: &K, value: &V) -> bool {
+        (self.weigher)(key, value) <= self.limit
+    }
+
+    /// Accounts for an entry that became resident
+    pub(crate) fn add(&mut self, key: &K, value: &V) {
+        self.total += (self.weigher)(key, value);
+    }
+
+    /// Accounts for an entry that left the cache
+    pub(crate) fn sub(&mut self, key: &K, value: &V) {
+        self.total = self.total.saturating_sub((self.weigher)(key, value));
+    }
+
+    /// Forgets every entry
+    pub(crate) fn clear(&mut self) {
+        self.total = 0;
+    }
+
+    /// Recomputes the total from scratch, e.g. after values changed in place
+    pub(crate) fn reweigh<'a>(&mut self, entries: impl Iterator<Item = (&'a K, &'a V)>)
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.total = entries.map(|(key, value)| (self.weigher)(key, value)).sum();
+    }
+
+    /// Checks the running total against a fresh weighing and the limit
+    pub(crate) fn check<'a>(&self, entries: impl Iterator<Item = (&'a K, &'a V)>) -> Result<(), String>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        let actual: usize = entries.map(|(key, value)| (self.weigher)(key, value)).sum();
+        if actual != self.total {
+            return Err(format!("byte total {} but entries weigh {}", self.total, actual));
+        }
+        if self.is_over() {
+            return Err(format!("byte total {} exceeds budget {}", self.total, self.limit));
+        }
+        Ok(())
+    }
+}
+
+/// Number of recent predictions a policy remembers while waiting for them to be requested
+const PREDICTION_WINDOW: usize = 64;
+
+/// Recently predicted keys that have not been requested yet
+///
+/// Backs the `prefetch_hits` and `prefetch_misses` counters of the policy-level
+/// prefetch statistics: a prediction is a hit when a later `get` asks for it
+/// and a miss when it falls out of the window of recent predictions unused.
+pub(crate) struct PendingPredictions<K> {
+    /// Recorded predictions, oldest first, tagged with their recording sequence
+    order: VecDeque<(K, u64)>,
+    /// Unrequested keys and the sequence of their live slot in `order`
+    pending: HashMap<K, u64>,
+    next_seq: u64,
+}
+
+impl<K: Hash + Eq + Clone> PendingPredictions<K> {
+    pub(crate) fn new() -> Self {
+        Self { order: VecDeque::new(), pending: HashMap::new(), next_seq: 0 }
+    }
+
+    /// Remembers a prediction and returns how many older ones aged out unused
+    pub(crate) fn record(&mut self, key: K) -> u64 {
+        if self.pending.contains_key(&key) {
+            return 0;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.pending.insert(key.clone(), seq);
+        self.order.push_back((key, seq));
+
+        let mut expired = 0;
+        while self.order.len() > PREDICTION_WINDOW {
+            let Some((oldest, seq)) = self.order.pop_front() else { break };
+            // A slot whose key was requested, or predicted again since, is stale
+            if self.pending.get(&oldest) == Some(&seq) {
+                self.pending.remove(&oldest);
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    /// Consumes a pending prediction for the key, returning whether there was one
+    pub(crate) fn take(&mut self, key: &K) -> bool {
+        self.pending.remove(key).is_some()
+    }
+
+    /// Forgets every pending prediction
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.pending.clear();
+    }
+}
+
+/// Serialized form of a policy: its capacity and resident entries in eviction order
+///
+/// Prefetch state, hooks and statistics are left out; a loaded cache starts
+/// them fresh, as [`new`](LruCache::new) would.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct Snapshot<E> {
+    pub(crate) capacity: usize,
+    pub(crate) entries: Vec<E>,
+}
+
+#[cfg(feature = "serde")]
+impl<E> Snapshot<E> {
+    /// Rejects snapshots no cache could have written
+    pub(crate) fn validate<Err: serde::de::Error>(&self) -> Result<(), Err> {
+        if self.capacity == 0 {
+            return Err(Err::custom("cache capacity must be greater than 0"));
+        }
+        if self.entries.len() > self.capacity {
+            return Err(Err::custom(format!(
+                "{} entries exceed capacity {}",
+                self.entries.len(),
+                self.capacity
+            )));
+        }
+        Ok(())
+    }
+}
 
 /// Supported cache policies
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -38,6 +261,9 @@ pub enum PolicyType {
     TwoQ,
     Slru,
     Car,
+    Sieve,
+    Lirs,
+    S3Fifo,
 }
 
 impl PolicyType {
@@ -53,6 +279,9 @@ impl PolicyType {
             PolicyType::TwoQ => "2Q",
             PolicyType::Slru => "SLRU",
             PolicyType::Car => "CAR",
+            PolicyType::Sieve => "SIEVE",
+            PolicyType::Lirs => "LIRS",
+            PolicyType::S3Fifo => "S3-FIFO",
         }
     }
 
@@ -68,6 +297,9 @@ impl PolicyType {
             PolicyType::TwoQ => "Two-queue combining FIFO & LRU for scan resistance",
             PolicyType::Slru => "Segmented LRU with probationary and protected segments",
             PolicyType::Car => "Clock with adaptive replacement like ARC",
+            PolicyType::Sieve => "FIFO queue with visited bits swept by a hand that never reorders hits",
+            PolicyType::Lirs => "Keeps blocks with short reuse distance, evicting from a small HIR queue",
+            PolicyType::S3Fifo => "Small, main and ghost FIFO queues that filter one-hit wonders",
         }
     }
 
@@ -83,6 +315,9 @@ impl PolicyType {
             PolicyType::TwoQ,
             PolicyType::Slru,
             PolicyType::Car,
+            PolicyType::Sieve,
+            PolicyType::Lirs,
+            PolicyType::S3Fifo,
         ]
     }
 
@@ -92,6 +327,8 @@ impl PolicyType {
             PolicyType::TwoQ,
             PolicyType::Slru,
             PolicyType::Car,
+            PolicyType::Lirs,
+            PolicyType::S3Fifo,
         ]
     }
 
@@ -101,6 +338,7 @@ impl PolicyType {
             PolicyType::Fifo,
             PolicyType::Clock,
             PolicyType::Random,
+            PolicyType::Sieve,
         ]
     }
 
@@ -109,7 +347,15 @@ impl PolicyType {
     }
 
     pub fn is_scan_resistant(&self) -> bool {
-        matches!(self, PolicyType::Arc | PolicyType::TwoQ | PolicyType::Slru | PolicyType::Car)
+        matches!(
+            self,
+            PolicyType::Arc
+                | PolicyType::TwoQ
+                | PolicyType::Slru
+                | PolicyType::Car
+                | PolicyType::Lirs
+                | PolicyType::S3Fifo
+        )
     }
 }
 
@@ -133,6 +379,9 @@ where
         PolicyType::TwoQ => Box::new(TwoQCache::<K, V>::new(capacity)),
         PolicyType::Slru => Box::new(SlruCache::<K, V>::new(capacity)),
         PolicyType::Car => Box::new(CarCache::<K, V>::new(capacity)),
+        PolicyType::Sieve => Box::new(SieveCache::<K, V>::new(capacity)),
+        PolicyType::Lirs => Box::new(LirsCache::<K, V>::new(capacity)),
+        PolicyType::S3Fifo => Box::new(S3FifoCache::<K, V>::new(capacity)),
     }
 }
 
@@ -152,6 +401,30 @@ where
         self.clear();
     }
 
+    /// Restores a pristine state in one call: contents, prefetch stats and
+    /// strategy, metrics, and every internal counter
+    ///
+    /// Capacity and other configuration stay. Policies keeping counters
+    /// beyond what `reset_for_benchmark` and `reset_metrics` clear override
+    /// this.
+    fn reset_all(&mut self) {
+        self.reset_for_benchmark();
+        self.reset_metrics();
+    }
+
+    /// Resets the cache, sizes it to `capacity` and inserts `prefill` in order
+    ///
+    /// Benchmarks call this in their setup step so every measured run starts
+    /// from the same warm contents instead of timing construction and
+    /// cold misses.
+    fn prepare_populated(&mut self, capacity: usize, prefill: &[(K, V)]) {
+        self.reset_for_benchmark();
+        self.resize(capacity);
+        for (key, value) in prefill {
+            self.insert(key.clone(), value.clone());
+        }
+    }
+
     fn benchmark_operations(&mut self, operations: &[(K, Option<V>)]) {
         for (key, maybe_value) in operations {
             if let Some(value) = maybe_value {
@@ -244,6 +517,30 @@ where
                 temporal_locality: true,
                 spatial_locality: false,
             },
+            PolicyType::Sieve => PolicyCharacteristics {
+                avg_get_complexity: "O(1)",
+                avg_insert_complexity: "O(1)*",
+                memory_overhead: "Low",
+                cache_friendly: true,
+                temporal_locality: true,
+                spatial_locality: false,
+            },
+            PolicyType::Lirs => PolicyCharacteristics {
+                avg_get_complexity: "O(1)",
+                avg_insert_complexity: "O(1)*",
+                memory_overhead: "High",
+                cache_friendly: true,
+                temporal_locality: true,
+                spatial_locality: false,
+            },
+            PolicyType::S3Fifo => PolicyCharacteristics {
+                avg_get_complexity: "O(1)",
+                avg_insert_complexity: "O(1)*",
+                memory_overhead: "Medium",
+                cache_friendly: true,
+                temporal_locality: true,
+                spatial_locality: false,
+            },
         }
     }
 }
@@ -272,3 +569,682 @@ impl Default for PolicyCharacteristics {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Drives random get/insert/remove/resize operations, checking
+    /// invariants after every single one
+    fn run_random_ops<C>(cache: &mut C, check: impl Fn(&C) -> Result<(), String>, seed: u64)
+    where
+        C: CachePolicy<u32, u32>,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for step in 0..5000 {
+            let key = rng.gen_range(0..64);
+            match rng.gen_range(0..100) {
+                0..=44 => {
+                    cache.get(&key);
+                }
+                45..=89 => cache.insert(key, step),
+                90..=97 => {
+                    cache.remove(&key);
+                }
+                _ => cache.resize(rng.gen_range(1..48)),
+            }
+            if let Err(msg) = check(cache) {
+                panic!("invariant broken at step {} (seed {}): {}", step, seed, msg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arc_invariants_random_ops() {
+        for seed in 0..4 {
+            run_random_ops(&mut ArcCache::new(16), ArcCache::check_invariants, seed);
+        }
+    }
+
+    #[test]
+    fn test_car_invariants_random_ops() {
+        for seed in 0..4 {
+            run_random_ops(&mut CarCache::new(16), CarCache::check_invariants, seed);
+        }
+    }
+
+    #[test]
+    fn test_slru_invariants_random_ops() {
+        for seed in 0..4 {
+            run_random_ops(&mut SlruCache::new(16), SlruCache::check_invariants, seed);
+        }
+    }
+
+    /// Drives random inserts, weight-changing updates, gets and removals on
+    /// a cache in byte mode, checking the running byte total every step
+    fn run_byte_ops<C>(
+        cache: &mut C,
+        total_bytes: impl Fn(&C) -> Option<usize>,
+        check: impl Fn(&C) -> Result<(), String>,
+    ) where
+        C: CachePolicy<u32, String>,
+    {
+        cache.set_byte_capacity(64, Box::new(|_, value: &String| value.len()));
+        let mut rng = StdRng::seed_from_u64(11);
+        for step in 0..3000 {
+            let key = rng.gen_range(0..32);
+            match rng.gen_range(0..10) {
+                0..=5 => cache.insert(key, "x".repeat(rng.gen_range(0..24))),
+                6..=7 => {
+                    cache.get(&key);
+                }
+                _ => {
+                    cache.remove(&key);
+                }
+            }
+            // Read the running total before the visit reweighs entries
+            let running = total_bytes(cache);
+            let mut weighed = 0;
+            cache.for_each_value_mut(&mut |_, value| weighed += value.len());
+            assert_eq!(running, Some(weighed), "byte total drifted at step {}", step);
+            assert!(weighed <= 64, "budget exceeded at step {}", step);
+            check(cache).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_byte_capacity_holds_across_random_ops() {
+        run_byte_ops(&mut LruCache::new(1000), LruCache::total_bytes, LruCache::check_invariants);
+        run_byte_ops(&mut LfuCache::new(1000), LfuCache::total_bytes, LfuCache::check_invariants);
+        run_byte_ops(&mut FifoCache::new(1000), FifoCache::total_bytes, FifoCache::check_invariants);
+    }
+
+    #[test]
+    fn test_byte_capacity_update_reweighs_entry() {
+        let mut cache = LruCache::new(100);
+        cache.insert(1, "aaaa".to_string());
+        cache.insert(2, "bbbb".to_string());
+        cache.set_byte_capacity(10, Box::new(|_, value: &String| value.len()));
+        assert_eq!(cache.total_bytes(), Some(8));
+
+        // Growing key 1 overruns the budget and pushes out the older key 2
+        cache.insert(1, "aaaaaaaa".to_string());
+        assert_eq!(cache.total_bytes(), Some(8));
+        assert_eq!(cache.len(), 1);
+        assert!(!cache.contains_key(&2));
+
+        // An entry heavier than the whole budget is not kept, nor does it
+        // push anything out
+        cache.insert(3, "c".repeat(11));
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.total_bytes(), Some(8));
+        assert_eq!(cache.remove(&1).as_deref(), Some("aaaaaaaa"));
+        assert_eq!(cache.total_bytes(), Some(0));
+
+        // Policies without byte support stay in entry-count mode
+        let mut arc = ArcCache::new(2);
+        arc.set_byte_capacity(1, Box::new(|_, value: &String| value.len()));
+        arc.insert(1, "long value".to_string());
+        arc.insert(2, "long value".to_string());
+        assert_eq!(arc.len(), 2);
+    }
+
+    /// Inserts at one under, exactly and one over a 10-byte budget
+    fn check_byte_boundary<C: CachePolicy<u32, String>>(mut cache: C, total_bytes: impl Fn(&C) -> Option<usize>) {
+        cache.set_byte_capacity(10, Box::new(|_, value: &String| value.len()));
+        cache.insert(1, "a".repeat(3));
+        cache.insert(2, "b".repeat(3));
+
+        cache.insert(3, "c".repeat(9));
+        assert!(cache.contains_key(&3));
+        assert_eq!(cache.len(), 1);
+
+        cache.insert(4, "d".to_string());
+        cache.insert(5, "e".repeat(10));
+        assert!(cache.contains_key(&5));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(total_bytes(&cache), Some(10));
+
+        cache.insert(6, "f".repeat(11));
+        assert!(!cache.contains_key(&6));
+        assert!(cache.contains_key(&5));
+        assert_eq!(total_bytes(&cache), Some(10));
+    }
+
+    #[test]
+    fn test_byte_capacity_boundary_at_budget_size() {
+        check_byte_boundary(LruCache::new(100), LruCache::total_bytes);
+        check_byte_boundary(LfuCache::new(100), LfuCache::total_bytes);
+        check_byte_boundary(FifoCache::new(100), FifoCache::total_bytes);
+    }
+
+    #[test]
+    fn test_min_residency_spares_fresh_entries() {
+        fn check<C: CachePolicy<i32, i32>>(mut cache: C) {
+            cache.insert(1, 1);
+            cache.insert(2, 2);
+            // 1 is still protected, so the cache goes over capacity
+            assert_eq!(cache.len(), 2);
+
+            cache.insert(3, 3);
+            assert!(cache.contains_key(&2));
+            assert!(!cache.contains_key(&1));
+            assert_eq!(cache.len(), 2);
+        }
+
+        let mut lru = LruCache::new(1);
+        lru.set_min_residency(2);
+        check(lru);
+        let mut fifo = FifoCache::new(1);
+        fifo.set_min_residency(2);
+        check(fifo);
+
+        // Without it, the fresh entry is the next victim
+        let mut lru = LruCache::new(1);
+        lru.insert(1, 1);
+        lru.insert(2, 2);
+        assert!(!lru.contains_key(&1));
+    }
+
+    #[test]
+    fn test_evict_one_picks_the_victim_insert_would() {
+        for &policy in PolicyType::all() {
+            // Random eviction can't be replayed
+            if policy == PolicyType::Random {
+                continue;
+            }
+            let mut evicting = AnyCache::new(policy, 8);
+            let mut inserting = AnyCache::new(policy, 8);
+            for key in 0..24 {
+                evicting.insert(key, key);
+                inserting.insert(key, key);
+                if key % 3 == 0 {
+                    evicting.get(&(key / 2));
+                    inserting.get(&(key / 2));
+                }
+            }
+
+            for key in 100..110 {
+                let before: Vec<i32> = (0..110).filter(|k| evicting.contains_key(k)).collect();
+                if evicting.len() >= evicting.capacity() {
+                    let (victim, value) = evicting.evict_one().unwrap();
+                    assert_eq!(victim, value);
+                    assert!(before.contains(&victim) && !evicting.contains_key(&victim), "{:?}", policy);
+                }
+                evicting.insert(key, key);
+                inserting.insert(key, key);
+                let after: Vec<i32> = (0..110).filter(|k| evicting.contains_key(k)).collect();
+                let reference: Vec<i32> = (0..110).filter(|k| inserting.contains_key(k)).collect();
+                assert_eq!(after, reference, "{:?} after inserting {}", policy, key);
+                assert_eq!(evicting.metrics(), inserting.metrics(), "{:?}", policy);
+            }
+
+            while evicting.evict_one().is_some() {}
+            assert!(evicting.is_empty(), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_get_mut_updates_values_and_usage_like_get() {
+        for &policy in PolicyType::all() {
+            let mut mutated = AnyCache::new(policy, 8);
+            let mut read = AnyCache::new(policy, 8);
+            for key in 0..8 {
+                mutated.insert(key, key);
+                read.insert(key, key);
+            }
+
+            // 2Q and SLRU keep as few as one of the cold inserts
+            let resident: Vec<i32> = (0..8).filter(|k| mutated.contains_key(k)).collect();
+            let (first, last) = (resident[0], resident[resident.len() - 1]);
+            let touches = [first, last, first, first, last];
+            for key in touches {
+                *mutated.get_mut(&key).unwrap() += 100;
+                read.get(&key);
+            }
+            for key in [first, last] {
+                let bumps = touches.iter().filter(|&&k| k == key).count() as i32;
+                assert_eq!(mutated.get(&key), Some(&(key + 100 * bumps)), "{:?}", policy);
+                read.get(&key);
+            }
+            assert_eq!(mutated.get_mut(&100), None, "{:?}", policy);
+
+            // Random eviction can't be replayed, the rest must evict identically
+            if policy == PolicyType::Random {
+                continue;
+            }
+            for key in 10..20 {
+                mutated.insert(key, key);
+                read.insert(key, key);
+                let after: Vec<i32> = (0..20).filter(|k| mutated.contains_key(k)).collect();
+                let reference: Vec<i32> = (0..20).filter(|k| read.contains_key(k)).collect();
+                assert_eq!(after, reference, "{:?} after inserting {}", policy, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_metrics_account_for_every_entry() {
+        for &policy in PolicyType::all() {
+            let mut cache = AnyCache::new(policy, 100);
+            for key in 0..300 {
+                cache.insert(key, key);
+            }
+            // 2Q holds one-time keys in A1in alone, so it may keep fewer
+            let metrics = cache.metrics();
+            assert_eq!(metrics.insertions, 300, "{}", policy.name());
+            assert_eq!(metrics.evictions, 300 - cache.len() as u64, "{}", policy.name());
+
+            // Every entry counted in leaves through an eviction or a removal
+            let mut rng = StdRng::seed_from_u64(5);
+            let mut removed = 0;
+            for _ in 0..5_000 {
+                let key = rng.gen_range(0..400);
+                match rng.gen_range(0..10) {
+                    0 => removed += cache.remove(&key).is_some() as u64,
+                    1..=4 => {
+                        cache.get(&key);
+                    }
+                    _ => cache.insert(key, key),
+                }
+            }
+            cache.resize(40);
+            let metrics = cache.metrics();
+            assert_eq!(
+                metrics.insertions - metrics.evictions - removed,
+                cache.len() as u64,
+                "{}",
+                policy.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_any_cache_dispatches_to_every_policy() {
+        for &policy in PolicyType::all() {
+            let mut cache = AnyCache::new(policy, 64);
+            assert_eq!(cache.policy_type(), policy);
+            assert_eq!(cache.capacity(), 64);
+            assert!(cache.is_empty());
+
+            for key in 0..4 {
+                cache.insert(key, key * 10);
+            }
+            assert_eq!(cache.len(), 4, "{}", policy.name());
+            assert_eq!(cache.get(&2), Some(&20), "{}", policy.name());
+            assert!(cache.contains_key(&2));
+            assert_eq!(cache.remove(&2), Some(20));
+            assert!(!cache.contains_key(&2));
+
+            cache.for_each_value_mut(&mut |_, v| *v += 1);
+            assert_eq!(cache.get(&3), Some(&31), "{}", policy.name());
+            assert_eq!(cache.insert_if_absent_with(7, || 70), (true, Some(&70)));
+            assert_eq!(cache.insert_if_absent_with(7, || 0), (false, Some(&70)));
+
+            cache.resize(2);
+            assert!(cache.len() <= 2, "{}", policy.name());
+            cache.clear();
+            assert!(cache.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_prepare_populated_starts_warm() {
+        let prefill: Vec<(u32, u32)> = (0..10).map(|i| (i, i * 2)).collect();
+        let mut cache = LruCache::new(4);
+        cache.insert(100, 0);
+
+        cache.prepare_populated(8, &prefill);
+        assert_eq!(cache.capacity(), 8);
+        assert_eq!(cache.len(), 8);
+        assert!(!cache.contains_key(&100));
+        // The last `capacity` prefill entries are resident, so reads hit
+        for key in 2..10 {
+            assert_eq!(cache.get(&key), Some(&(key * 2)));
+        }
+    }
+
+    #[test]
+    fn test_reset_all_leaves_pristine_state() {
+        let mut arc = ArcCache::with_prefetch_i32(8, crate::prefetch::PrefetchType::Sequential);
+        arc.set_p(3);
+        arc.freeze_adaptation(true);
+        for key in (0..6).chain(0..6) {
+            if arc.get(&key).is_none() {
+                arc.insert(key, key.to_string());
+            }
+        }
+        assert!(arc.prefetch_stats().predictions_made > 0);
+
+        arc.reset_all();
+        assert_eq!(arc.len(), 0);
+        assert_eq!(arc.p(), 0);
+        assert!(!arc.is_adaptation_frozen());
+        let stats = arc.prefetch_stats();
+        assert_eq!(
+            (stats.predictions_made, stats.prefetch_hits, stats.prefetch_misses, stats.cache_hits_from_prefetch),
+            (0, 0, 0, 0)
+        );
+        assert_eq!(arc.estimated_hit_ratio_at(16), None);
+        assert_eq!(arc.metrics(), Default::default());
+
+        let mut lru = LruCache::new(4);
+        lru.insert(1, 1);
+        lru.get(&1);
+        lru.reset_all();
+        assert!(lru.is_empty());
+        assert_eq!(lru.metrics(), Default::default());
+        lru.insert(2, 2);
+        assert_eq!(lru.last_access_seq(&2), Some(1));
+
+        let mut fifo = FifoCache::new(2);
+        for key in 0..3 {
+            fifo.insert(key, key);
+        }
+        fifo.reset_all();
+        assert_eq!(fifo.metrics(), Default::default());
+    }
+
+    #[test]
+    fn test_touch_refreshes_recency_without_prefetch() {
+        use crate::prefetch::SequentialPrefetch;
+
+        let mut lru = LruCache::with_custom_prefetch(4, Box::new(SequentialPrefetch::<i32>::new()));
+        for key in 0..4 {
+            lru.insert(key, key * 10);
+        }
+        assert_eq!(lru.recency_rank(&0), Some(3));
+        assert!(lru.touch(&0));
+        assert_eq!(lru.recency_rank(&0), Some(0));
+        assert!(!lru.touch(&9));
+        assert_eq!(lru.prefetch_stats().predictions_made, 0);
+        // The touched key survives the next eviction; the coldest goes instead
+        lru.insert(4, 40);
+        assert!(!lru.contains_key(&1));
+        assert_eq!(lru.get(&0), Some(&0));
+
+        let mut slru = SlruCache::with_custom_prefetch(20, Box::new(SequentialPrefetch::<i32>::new()));
+        for key in 0..3 {
+            slru.insert(key, key * 10);
+        }
+        assert_eq!(slru.recency_rank(&0), Some(2));
+        assert!(slru.touch(&0));
+        assert_eq!(slru.recency_rank(&0), Some(0));
+        assert_eq!(slru.prefetch_stats().predictions_made, 0);
+        assert!(slru.check_invariants().is_ok());
+
+        let mut arc = ArcCache::with_custom_prefetch(4, Box::new(SequentialPrefetch::<i32>::new()));
+        arc.insert(0, 0);
+        arc.insert(1, 10);
+        assert!(arc.touch(&0));
+        let lists = arc.debug_lists();
+        assert_eq!((lists.t1, lists.t2), (vec![1], vec![0]));
+        assert!(arc.touch(&1));
+        assert!(arc.touch(&0));
+        assert_eq!(arc.debug_lists().t2, vec![0, 1]);
+        assert_eq!(arc.prefetch_stats().predictions_made, 0);
+        assert_eq!(arc.get(&0), Some(&0));
+    }
+
+    #[test]
+    fn test_contains_many_reflects_membership_without_side_effects() {
+        let keys: Vec<i32> = (0..40).collect();
+        for &policy in PolicyType::all() {
+            let mut probed = AnyCache::new(policy, 16);
+            let mut untouched = AnyCache::new(policy, 16);
+            for key in (0..40).step_by(3) {
+                probed.insert(key, key);
+                untouched.insert(key, key);
+            }
+
+            let expected: Vec<bool> = keys.iter().map(|key| probed.contains_key(key)).collect();
+            assert!(expected.iter().any(|&present| present));
+            assert!(expected.iter().any(|&present| !present));
+            assert_eq!(probed.contains_many(&keys), expected, "{:?}", policy);
+            assert!(probed.contains_many(&[]).is_empty());
+
+            // Random eviction can't be replayed, the rest must evict identically
+            if policy == PolicyType::Random {
+                continue;
+            }
+            for key in 100..120 {
+                probed.insert(key, key);
+                untouched.insert(key, key);
+            }
+            let after: Vec<i32> = (0..120).filter(|key| probed.contains_key(key)).collect();
+            let reference: Vec<i32> = (0..120).filter(|key| untouched.contains_key(key)).collect();
+            assert_eq!(after, reference, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_contains_key_leaves_eviction_order_untouched() {
+        for &policy in PolicyType::all() {
+            // Random eviction can't be replayed
+            if policy == PolicyType::Random {
+                continue;
+            }
+            let mut probed = AnyCache::new(policy, 8);
+            let mut untouched = AnyCache::new(policy, 8);
+            for key in 0..16 {
+                probed.insert(key, key);
+                untouched.insert(key, key);
+                if key % 3 == 0 {
+                    probed.get(&(key / 2));
+                    untouched.get(&(key / 2));
+                }
+            }
+
+            for key in 100..110 {
+                // Probe every key, resident, evicted and never seen, twice
+                for probe in (0..32).chain(100..110).chain(0..32) {
+                    probed.contains_key(&probe);
+                }
+                probed.insert(key, key);
+                untouched.insert(key, key);
+                let after: Vec<i32> = (0..110).filter(|k| probed.contains_key(k)).collect();
+                let reference: Vec<i32> = (0..110).filter(|k| untouched.contains_key(k)).collect();
+                assert_eq!(after, reference, "{:?} after inserting {}", policy, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefetch_loader_fills_buffer_for_predicted_keys() {
+        use crate::prefetch::SequentialPrefetch;
+
+        fn check(cache: &mut impl CachePolicy<i32, i32>, name: &str) {
+            for key in 0..8 {
+                cache.insert(key, key * 10);
+            }
+            // A steady stride lets the strategy predict the keys past 7
+            for key in 0..8 {
+                assert_eq!(cache.get(&key), Some(&(key * 10)), "{}", name);
+            }
+            assert!(!cache.contains_key(&8), "{}", name);
+            assert_eq!(cache.get(&8), Some(&80), "{} should serve 8 from the prefetch buffer", name);
+        }
+        fn loader() -> PrefetchLoader<i32, i32> {
+            Box::new(|key: &i32| Some(key * 10))
+        }
+        fn sequential() -> Box<dyn crate::PrefetchStrategy<i32>> {
+            Box::new(SequentialPrefetch::<i32>::new())
+        }
+
+        let mut arc = ArcCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut arc, "ARC");
+        assert_eq!(arc.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut car = CarCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut car, "CAR");
+        assert_eq!(car.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut slru = SlruCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut slru, "SLRU");
+        assert_eq!(slru.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut mru = MruCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut mru, "MRU");
+        assert_eq!(mru.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut sieve = SieveCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut sieve, "SIEVE");
+        assert_eq!(sieve.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut lirs = LirsCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut lirs, "LIRS");
+        assert_eq!(lirs.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        let mut s3fifo = S3FifoCache::with_custom_prefetch(64, sequential()).with_loader(loader());
+        check(&mut s3fifo, "S3-FIFO");
+        assert_eq!(s3fifo.prefetch_stats().cache_hits_from_prefetch, 1);
+
+        // Without a loader predictions are counted but nothing is buffered
+        let mut bare = ArcCache::with_custom_prefetch(64, sequential());
+        for key in 0..8 {
+            bare.insert(key, key);
+            bare.get(&key);
+        }
+        assert!(bare.prefetch_stats().predictions_made > 0);
+        assert_eq!(bare.get(&8), None);
+    }
+
+    #[test]
+    fn test_peek_leaves_eviction_order_untouched() {
+        for &policy in PolicyType::all() {
+            // Random eviction can't be replayed
+            if policy == PolicyType::Random {
+                continue;
+            }
+            let mut peeked = AnyCache::new(policy, 20);
+            let mut untouched = AnyCache::new(policy, 20);
+            for key in 0..20 {
+                peeked.insert(key, key * 10);
+                untouched.insert(key, key * 10);
+            }
+            untouched.get(&19);
+            peeked.get(&19);
+
+            // Peek every resident key, the next victim included
+            let resident: Vec<i32> = (0..20).filter(|k| peeked.contains_key(k)).collect();
+            assert!(resident.len() > 1, "{:?}", policy);
+            for key in &resident {
+                assert_eq!(peeked.peek(key), Some(&(key * 10)), "{:?}", policy);
+                assert_eq!(peeked.peek(key), Some(&(key * 10)), "{:?}", policy);
+            }
+            assert_eq!(peeked.peek(&100), None);
+
+            for key in 100..104 {
+                peeked.insert(key, key);
+                untouched.insert(key, key);
+                let evicted: Vec<i32> = (0..20).filter(|k| !peeked.contains_key(k)).collect();
+                let reference: Vec<i32> = (0..20).filter(|k| !untouched.contains_key(k)).collect();
+                assert_eq!(evicted, reference, "{:?} after inserting {}", policy, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_yields_each_resident_entry_once() {
+        use std::collections::HashSet;
+
+        // Enough churn that the adaptive policies hold ghost keys
+        macro_rules! check {
+            ($cache:expr) => {{
+                let mut cache = $cache;
+                for key in 0..60 {
+                    cache.insert(key, key * 2);
+                    if key % 3 == 0 {
+                        cache.get(&(key / 2));
+                    }
+                }
+                let entries: Vec<(i32, i32)> = cache.iter().map(|(k, v)| (*k, *v)).collect();
+                let name = stringify!($cache);
+                assert_eq!(entries.len(), cache.len(), "{}", name);
+                assert_eq!(entries.iter().map(|(k, _)| k).collect::<HashSet<_>>().len(), entries.len(), "{}", name);
+                assert!(entries.iter().all(|(k, v)| cache.contains_key(k) && *v == k * 2), "{}", name);
+            }};
+        }
+        check!(LruCache::new(16));
+        check!(MruCache::new(16));
+        check!(FifoCache::new(16));
+        check!(LfuCache::new(16));
+        check!(SlruCache::new(16));
+        check!(ArcCache::new(16));
+        check!(CarCache::new(16));
+        check!(ClockCache::new(16));
+        check!(TwoQCache::new(16));
+        check!(RandomCache::new(16));
+        check!(SieveCache::new(16));
+        check!(LirsCache::new(16));
+        check!(S3FifoCache::new(16));
+
+        let mut lru = LruCache::new(4);
+        let mut mru = MruCache::new(4);
+        let mut fifo = FifoCache::new(4);
+        for key in 0..4 {
+            lru.insert(key, key);
+            mru.insert(key, key);
+            fifo.insert(key, key);
+        }
+        lru.get(&1);
+        mru.get(&1);
+        fifo.get(&1);
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &i32)>| iter.map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys(&mut lru.iter()), vec![1, 3, 2, 0]);
+        assert_eq!(keys(&mut mru.iter()), vec![0, 2, 3, 1]);
+        assert_eq!(keys(&mut fifo.iter()), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_prefetch_stats_count_hits_for_requested_predictions() {
+        use crate::prefetch::PrefetchType;
+
+        // Walks resident keys in order so each access was predicted by the last
+        fn scan(cache: &mut impl CachePolicy<i32, String>) {
+            for key in 0..32 {
+                cache.insert(key, key.to_string());
+            }
+            for key in 0..32 {
+                cache.get(&key);
+            }
+        }
+
+        let mut lru = LruCache::with_prefetch_i32(64, PrefetchType::Sequential);
+        scan(&mut lru);
+        let stats = lru.prefetch_stats();
+        assert!(stats.prefetch_hits > 0);
+        assert!(stats.prefetch_hits <= stats.predictions_made);
+        assert!(stats.hit_rate() > 0.0);
+
+        let mut arc = ArcCache::with_prefetch_i32(64, PrefetchType::Sequential);
+        scan(&mut arc);
+        assert!(arc.prefetch_stats().prefetch_hits > 0);
+
+        let mut fifo = FifoCache::with_prefetch_i32(64, PrefetchType::Sequential);
+        scan(&mut fifo);
+        assert!(fifo.prefetch_stats().prefetch_hits > 0);
+
+        let mut sieve = SieveCache::with_prefetch_i32(64, PrefetchType::Sequential);
+        scan(&mut sieve);
+        assert!(sieve.prefetch_stats().prefetch_hits > 0);
+
+        // Predictions nobody asks for age out as misses once the window fills
+        let mut lru = LruCache::with_prefetch_i32(64, PrefetchType::Sequential);
+        for run in 0..100 {
+            // Short runs keep the stride steady but abandon each run's lookahead
+            for key in run * 1000..run * 1000 + 4 {
+                lru.insert(key, key.to_string());
+                lru.get(&key);
+            }
+        }
+        let stats = lru.prefetch_stats();
+        assert!(stats.prefetch_misses > 0);
+        assert!(stats.prefetch_misses < stats.predictions_made);
+
+        lru.reset_prefetch_stats();
+        assert_eq!((lru.prefetch_stats().prefetch_hits, lru.prefetch_stats().prefetch_misses), (0, 0));
+    }
+}
@@ -2,9 +2,10 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::ptr::NonNull;
 use std::marker::PhantomData;
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType, walk_linked_list};
 
 /// A Most Recently Used (MRU) cache implementation with integrated prefetch strategies
 ///
@@ -37,6 +38,16 @@ where
     prefetch_buffer_size: usize,
     /// Statistics for prefetch effectiveness
     prefetch_stats: PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
     _marker: PhantomData<Box<Node<K, V>>>,
 }
 
@@ -133,6 +144,11 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            prefetch_loader: None,
             _marker: PhantomData,
         }
     }
@@ -142,6 +158,37 @@ where
         Self::new(100)
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries, least recently used first
+    ///
+    /// MRU evicts the most recent entry, so this is the reverse of eviction
+    /// order. Prefetched entries not yet read are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        std::iter::successors(self.tail, |node| unsafe { node.as_ref().prev }).map(|node| {
+            let node = unsafe { &*node.as_ptr() };
+            (&node.key, &node.value)
+        })
+    }
+
     /// Returns current prefetch statistics
     pub fn prefetch_stats(&self) -> &PrefetchStats {
         &self.prefetch_stats
@@ -150,9 +197,31 @@ where
     /// Resets prefetch statistics
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: map and list agree in size and
+    /// contents, links are intact and the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() != self.len {
+            return Err(format!("map holds {} keys but len is {}", self.map.len(), self.len));
+        }
+        if self.len > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.len, self.capacity));
+        }
+        let nodes = unsafe {
+            walk_linked_list("MRU list", self.head, self.tail, self.len, |n| (n.prev, n.next))?
+        };
+        for node in nodes {
+            let key = unsafe { &node.as_ref().key };
+            if self.map.get(key) != Some(&node) {
+                return Err("map entry does not point at its list node".to_string());
+            }
+        }
+        Ok(())
+    }
+
     /// Sets the prefetch buffer size
     pub fn set_prefetch_buffer_size(&mut self, size: usize) {
         self.prefetch_buffer_size = size.max(1);
@@ -172,28 +241,29 @@ where
 
     /// Performs prefetch predictions and populates the prefetch buffer
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         // Update prefetch strategy with the accessed key
         self.prefetch_strategy.update_access_pattern(accessed_key);
 
         // Get predictions from the strategy
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             // Only prefetch if the key is not already in main cache or prefetch buffer
             if !self.map.contains_key(&predicted_key) &&
                !self.prefetch_buffer.contains_key(&predicted_key) {
-
-                // Here you would typically load the value from your data source
-                // For now, we'll simulate with a placeholder
-                // In a real implementation, this would be:
-                // if let Some(value) = self.load_from_source(&predicted_key) {
-                //     self.prefetch_buffer.insert(predicted_key, value);
-                // }
-
-                // For demonstration, we'll skip actual prefetch loading
-                // but track the prediction
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
             }
         }
 
@@ -268,9 +338,9 @@ where
 
     /// Removes and deallocates the most recently used item (head)
     ///
-    /// Returns the key of the evicted item, or None if the cache is empty.
+    /// Returns the evicted entry, or None if the cache is empty.
     /// This is the key difference from LRU - we evict from head instead of tail.
-    fn evict_mru(&mut self) -> Option<K> {
+    fn evict_mru(&mut self) -> Option<(K, V)> {
         if let Some(head_ptr) = self.head {
             unsafe {
                 let head_node = Box::from_raw(head_ptr.as_ptr());
@@ -290,7 +360,7 @@ where
                 }
 
                 self.len -= 1;
-                Some(key)
+                Some((key, head_node.value))
             }
         } else {
             None
@@ -326,6 +396,24 @@ impl MruCache<usize, String> {
     }
 }
 
+impl MruCache<u32, String> {
+    /// Creates a new u32 MRU cache with specified prefetch strategy
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "MRU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl MruCache<u64, String> {
+    /// Creates a new u64 MRU cache with specified prefetch strategy
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "MRU cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 impl<K, V> CachePolicy<K, V> for MruCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -337,6 +425,10 @@ where
     /// This operation moves the accessed item to the front of the MRU order
     /// and triggers prefetch predictions for future accesses.
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check if it's in the prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             // Move from prefetch buffer to main cache
@@ -362,11 +454,19 @@ where
         }
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Inserts a key-value pair into the cache
     ///
     /// If the key already exists, updates the value and moves it to front.
     /// If the cache is at capacity, evicts the most recently used item first.
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         // Remove from prefetch buffer if it exists there
         self.prefetch_buffer.remove(&key);
 
@@ -381,8 +481,8 @@ where
         }
 
         // Check if we need to evict before inserting
-        if self.len >= self.capacity {
-            self.evict_mru();
+        if self.len >= self.capacity && self.evict_mru().is_some() {
+            self.metrics.evictions += 1;
         }
 
         // Create new node
@@ -398,12 +498,14 @@ where
         }
 
         self.len += 1;
+        self.metrics.insertions += 1;
     }
 
     /// Removes an entry from the cache
     ///
     /// Returns the removed value if it existed, `None` otherwise.
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         // Check prefetch buffer first
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
@@ -447,6 +549,68 @@ where
     fn capacity(&self) -> usize {
         self.capacity
     }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Changes the capacity, evicting most recently used items if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "MRU cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.len > self.capacity {
+            self.evict_mru();
+            self.metrics.evictions += 1;
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        let evicted = self.evict_mru()?;
+        self.metrics.evictions += 1;
+        Some(evicted)
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let node_ptr = *self.map.get(key)?;
+        Some(unsafe { &mut (*node_ptr.as_ptr()).value })
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let node_ptr = self.map.get(key)?;
+        Some(unsafe { &node_ptr.as_ref().value })
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, node) in &self.map {
+            unsafe { f(key, &node.as_ref().value) }
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, node) in &self.map {
+            unsafe { f(key, &mut (*node.as_ptr()).value) }
+        }
+    }
 }
 
 impl<K, V> BenchmarkablePolicy<K, V> for MruCache<K, V>
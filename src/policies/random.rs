@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::hash::Hash;
 use rand::{thread_rng, Rng};
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
 use super::{BenchmarkablePolicy, PolicyType};
 
@@ -35,6 +35,14 @@ where
 
     /// Statistics tracking prefetch predictions and effectiveness.
     prefetch_stats: PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
 }
 
 /// Stores statistical data about prefetch operation efficiency.
@@ -103,6 +111,10 @@ where
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
         }
     }
 
@@ -117,6 +129,19 @@ where
         self.trim_prefetch_buffer();
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Iterate resident entries in no particular order
+    ///
+    /// Victims are drawn at random, so there is no eviction order to follow.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+
     /// Get a reference to the current prefetch statistics.
     pub fn prefetch_stats(&self) -> &PrefetchStats {
         &self.prefetch_stats
@@ -125,9 +150,18 @@ where
     /// Reset all prefetch statistics and the prefetch strategy state.
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.map.len(), self.capacity));
+        }
+        Ok(())
+    }
+
     /// Remove old entries from the prefetch buffer while exceeding buffer size.
     fn trim_prefetch_buffer(&mut self) {
         while self.prefetch_buffer.len() > self.prefetch_buffer_size {
@@ -144,11 +178,18 @@ where
     /// This calls the prefetch strategy to get predicted next keys and records predictions.
     /// Actual loading of predicted values from a data source is not performed here.
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             // Only prefetch keys that are not already cached or prefetched.
             if !self.map.contains_key(&predicted_key)
@@ -162,16 +203,17 @@ where
     }
 
     /// Evict a random entry from the main cache to free space.
-    fn evict_random(&mut self) {
+    fn evict_random(&mut self) -> Option<(K, V)> {
         if self.map.is_empty() {
-            return;
+            return None;
         }
         let mut rng = thread_rng();
-        let keys: Vec<K> = self.map.keys().cloned().collect();
+        let mut keys: Vec<K> = self.map.keys().cloned().collect();
 
-        if let Some(random_key) = keys.get(rng.gen_range(0..keys.len())) {
-            self.map.remove(random_key);
-        }
+        let random_key = keys.swap_remove(rng.gen_range(0..keys.len()));
+        let value = self.map.remove(&random_key)?;
+        self.metrics.evictions += 1;
+        Some((random_key, value))
     }
 }
 
@@ -203,6 +245,24 @@ impl RandomCache<usize, String> {
     }
 }
 
+impl RandomCache<u32, String> {
+    /// Creates a new u32 Random cache with specified prefetch strategy
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "Random cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl RandomCache<u64, String> {
+    /// Creates a new u64 Random cache with specified prefetch strategy
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "Random cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 impl<K, V> CachePolicy<K, V> for RandomCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -213,6 +273,10 @@ where
     /// - If the key is found in the prefetch buffer, move it into the main cache and return it.
     /// - Otherwise, check the main cache; if present, perform prefetching before returning.
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -231,21 +295,32 @@ where
         }
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Insert or update a key-value pair.
     ///
     /// Evicts a random entry if the cache is full and the key is new.
     /// Removes any existing entry for the key from the prefetch buffer.
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         self.prefetch_buffer.remove(&key);
 
         if !self.map.contains_key(&key) && self.map.len() == self.capacity {
             self.evict_random();
         }
-        self.map.insert(key, value);
+        if self.map.insert(key, value).is_none() {
+            self.metrics.insertions += 1;
+        }
     }
 
     /// Remove a key and return its value if it exists in the cache or prefetch buffer.
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
         }
@@ -267,6 +342,63 @@ where
     fn capacity(&self) -> usize {
         self.capacity
     }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Change the capacity, evicting random entries if shrinking.
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "Random cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.map.len() > self.capacity {
+            self.evict_random();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.evict_random()
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        self.map.get_mut(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, value) in &self.map {
+            f(key, value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, value) in self.map.iter_mut() {
+            f(key, value);
+        }
+    }
 }
 
 impl<K, V> BenchmarkablePolicy<K, V> for RandomCache<K, V>
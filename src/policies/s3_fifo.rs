@@ -0,0 +1,612 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
+use crate::prefetch::{PrefetchType, NoPrefetch};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType};
+
+/// S3-FIFO cache implementation with prefetch strategies
+///
+/// Three FIFO queues replace LRU's recency list. New entries go to a small
+/// queue holding a tenth of the capacity; when one reaches its end it moves
+/// to the main queue if it was read since insertion and is otherwise
+/// evicted, leaving its key in a ghost queue. A key found in the ghost queue
+/// skips the small queue on its next insert. The main queue evicts like
+/// Clock, giving every entry one more lap per unit of its 2-bit frequency.
+/// Hits only bump the counter and never move entries, and one-hit wonders
+/// and scans are filtered out by the small queue before they reach main.
+pub struct S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Resident entries
+    map: HashMap<K, S3Entry<V>>,
+
+    /// Probation queue for new entries, oldest at the front
+    small: VecDeque<K>,
+
+    /// Queue for entries that proved reuse, oldest at the front
+    main: VecDeque<K>,
+
+    /// Recently evicted keys from the small queue, tagged with the sequence
+    /// number of their insertion, oldest at the front
+    ghost: VecDeque<(K, u64)>,
+
+    /// Keys currently in the ghost queue and the sequence of their live slot
+    ghost_seqs: HashMap<K, u64>,
+
+    /// Next ghost sequence number
+    ghost_seq: u64,
+
+    /// Target length of the small queue
+    small_capacity: usize,
+
+    /// Maximum cache capacity
+    capacity: usize,
+
+    /// Prefetch strategy implementation (pluggable)
+    prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+
+    /// Prefetched but not yet used items
+    prefetch_buffer: HashMap<K, V>,
+
+    /// Limit for prefetch buffer size
+    prefetch_buffer_size: usize,
+
+    /// Prefetch statistics
+    prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+}
+
+/// A resident value and its saturating access counter
+struct S3Entry<V> {
+    value: V,
+    /// Hits since insertion or the last promotion, capped at [`MAX_FREQ`]
+    freq: u8,
+    in_main: bool,
+}
+
+/// Ceiling of the 2-bit access counter
+const MAX_FREQ: u8 = 3;
+
+impl<K, V> S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Creates a new S3-FIFO cache using the default "no prefetch" strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_custom_prefetch(capacity, Box::new(NoPrefetch))
+    }
+
+    /// Creates an S3-FIFO cache with a custom prefetch strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn with_custom_prefetch(
+        capacity: usize,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+    ) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+
+        Self {
+            map: HashMap::new(),
+            small: VecDeque::new(),
+            main: VecDeque::new(),
+            ghost: VecDeque::new(),
+            ghost_seqs: HashMap::new(),
+            ghost_seq: 0,
+            small_capacity: Self::small_capacity_for(capacity),
+            capacity,
+            prefetch_strategy,
+            prefetch_buffer: HashMap::new(),
+            prefetch_buffer_size: (capacity / 4).max(1),
+            prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            prefetch_loader: None,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Small queue share of `capacity`: a tenth, at least one entry
+    fn small_capacity_for(capacity: usize) -> usize {
+        (capacity / 10).max(1)
+    }
+
+    /// Number of keys the ghost queue remembers, the main queue's share
+    fn ghost_capacity(&self) -> usize {
+        self.capacity - self.small_capacity
+    }
+
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries, the small queue newest first followed by
+    /// the main queue newest first
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.small
+            .iter()
+            .rev()
+            .chain(self.main.iter().rev())
+            .filter_map(|key| self.map.get_key_value(key))
+            .map(|(key, entry)| (key, &entry.value))
+    }
+
+    /// Whether `key` is resident in the main queue
+    pub fn is_in_main(&self, key: &K) -> bool {
+        self.map.get(key).is_some_and(|entry| entry.in_main)
+    }
+
+    /// Whether `key` was recently evicted from the small queue and would
+    /// go straight to the main queue on insert
+    pub fn is_ghost(&self, key: &K) -> bool {
+        self.ghost_seqs.contains_key(key)
+    }
+
+    pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
+        &self.prefetch_stats
+    }
+
+    pub fn reset_prefetch_stats(&mut self) {
+        self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
+        self.prefetch_strategy.reset();
+    }
+
+    /// Verifies internal consistency: each resident key sits in exactly the
+    /// queue its entry names, counters stay within two bits, and ghosts are
+    /// neither resident nor beyond the ghost capacity
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.map.len(), self.capacity));
+        }
+        if self.small.len() + self.main.len() != self.map.len() {
+            return Err(format!(
+                "queues hold {} + {} keys but map holds {}",
+                self.small.len(),
+                self.main.len(),
+                self.map.len()
+            ));
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (queue, in_main) in [(&self.small, false), (&self.main, true)] {
+            for key in queue {
+                if !seen.insert(key) {
+                    return Err("key queued twice".to_string());
+                }
+                match self.map.get(key) {
+                    Some(entry) if entry.in_main == in_main => {}
+                    Some(_) => return Err("key queued in the wrong queue".to_string()),
+                    None => return Err("queue references a missing key".to_string()),
+                }
+            }
+        }
+        if self.map.values().any(|entry| entry.freq > MAX_FREQ) {
+            return Err(format!("access counter exceeds {}", MAX_FREQ));
+        }
+        if self.ghost.len() > self.ghost_capacity() {
+            return Err(format!("{} ghost slots exceed capacity {}", self.ghost.len(), self.ghost_capacity()));
+        }
+        let live = self.ghost.iter().filter(|(key, seq)| self.ghost_seqs.get(key) == Some(seq)).count();
+        if live != self.ghost_seqs.len() {
+            return Err(format!("{} live ghost slots but {} ghost keys", live, self.ghost_seqs.len()));
+        }
+        if self.ghost_seqs.keys().any(|key| self.map.contains_key(key)) {
+            return Err("resident key is also a ghost".to_string());
+        }
+        Ok(())
+    }
+
+    /// Update prefetch strategy with new access patterns
+    fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
+        self.prefetch_strategy.update_access_pattern(accessed_key);
+        let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
+
+        for predicted_key in predictions {
+            self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
+            if !self.map.contains_key(&predicted_key)
+                && !self.prefetch_buffer.contains_key(&predicted_key)
+            {
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
+            }
+        }
+        self.trim_prefetch_buffer();
+    }
+
+    /// Ensure the prefetch buffer does not exceed the configured size
+    fn trim_prefetch_buffer(&mut self) {
+        while self.prefetch_buffer.len() > self.prefetch_buffer_size {
+            if let Some(key) = self.prefetch_buffer.keys().next().cloned() {
+                self.prefetch_buffer.remove(&key);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Remembers a key evicted from the small queue, forgetting the oldest
+    /// ghosts beyond the ghost capacity
+    fn push_ghost(&mut self, key: K) {
+        let seq = self.ghost_seq;
+        self.ghost_seq += 1;
+        self.ghost_seqs.insert(key.clone(), seq);
+        self.ghost.push_back((key, seq));
+        self.trim_ghosts();
+    }
+
+    fn trim_ghosts(&mut self) {
+        while self.ghost.len() > self.ghost_capacity() {
+            let Some((oldest, seq)) = self.ghost.pop_front() else {
+                break;
+            };
+            // A slot whose key came back since is stale
+            if self.ghost_seqs.get(&oldest) == Some(&seq) {
+                self.ghost_seqs.remove(&oldest);
+            }
+        }
+    }
+
+    /// Evicts one entry, from the small queue while it is at its target
+    /// length and from the main queue otherwise
+    ///
+    /// Small queue entries read since insertion move to main instead, and
+    /// main entries with a nonzero counter go round again one count lower.
+    fn evict(&mut self) -> Option<(K, V)> {
+        loop {
+            if self.small.len() >= self.small_capacity || self.main.is_empty() {
+                let key = self.small.pop_front()?;
+                let entry = self.map.get_mut(&key).expect("queued key is resident");
+                if entry.freq > 0 {
+                    entry.freq = 0;
+                    entry.in_main = true;
+                    self.main.push_back(key);
+                    continue;
+                }
+                let entry = self.map.remove(&key).expect("queued key is resident");
+                self.push_ghost(key.clone());
+                self.metrics.evictions += 1;
+                return Some((key, entry.value));
+            }
+
+            let key = self.main.pop_front()?;
+            let entry = self.map.get_mut(&key).expect("queued key is resident");
+            if entry.freq > 0 {
+                entry.freq -= 1;
+                self.main.push_back(key);
+                continue;
+            }
+            let entry = self.map.remove(&key).expect("queued key is resident");
+            self.metrics.evictions += 1;
+            return Some((key, entry.value));
+        }
+    }
+}
+
+impl<K, V> CachePolicy<K, V> for S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Gets a value and bumps its counter; the entry keeps its queue position
+    fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
+        // Check prefetch buffer first
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            self.prefetch_stats.cache_hits_from_prefetch += 1;
+            self.insert(key.clone(), value);
+            return self.get(key);
+        }
+
+        let entry = self.map.get_mut(key)?;
+        entry.freq = (entry.freq + 1).min(MAX_FREQ);
+        self.perform_prefetch(key);
+        self.map.get(key).map(|entry| &entry.value)
+    }
+
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
+    /// Inserts a new entry into the small queue, or straight into main when
+    /// its key is a ghost; updating a resident entry counts as a hit
+    fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        // Invalidate prefetch
+        self.prefetch_buffer.remove(&key);
+
+        if let Some(entry) = self.map.get_mut(&key) {
+            entry.value = value;
+            entry.freq = (entry.freq + 1).min(MAX_FREQ);
+            return;
+        }
+
+        if self.map.len() >= self.capacity {
+            self.evict();
+        }
+        let in_main = self.ghost_seqs.remove(&key).is_some();
+        if in_main {
+            self.main.push_back(key.clone());
+        } else {
+            self.small.push_back(key.clone());
+        }
+        self.map.insert(key, S3Entry { value, freq: 0, in_main });
+        self.metrics.insertions += 1;
+    }
+
+    /// Removes a key; O(n) in the length of its queue
+    fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            return Some(value);
+        }
+        let entry = self.map.remove(key)?;
+        let queue = if entry.in_main { &mut self.main } else { &mut self.small };
+        if let Some(pos) = queue.iter().position(|queued| queued == key) {
+            queue.remove(pos);
+        }
+        Some(entry.value)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn clear(&mut self) {
+        self.map.clear();
+        self.small.clear();
+        self.main.clear();
+        self.ghost.clear();
+        self.ghost_seqs.clear();
+        self.prefetch_buffer.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Changes the capacity and the small queue's share, evicting if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        self.small_capacity = Self::small_capacity_for(new_capacity);
+        while self.map.len() > self.capacity {
+            self.evict();
+        }
+        self.trim_ghosts();
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.evict()
+    }
+
+    /// Checks residency without touching counters or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        self.map.get_mut(key).map(|entry| &mut entry.value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|entry| &entry.value)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving counters untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, entry) in &self.map {
+            f(key, &entry.value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving counters untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, entry) in self.map.iter_mut() {
+            f(key, &mut entry.value);
+        }
+    }
+}
+
+impl<K, V> BenchmarkablePolicy<K, V> for S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn policy_type(&self) -> PolicyType {
+        PolicyType::S3Fifo
+    }
+
+    fn benchmark_name(&self) -> String {
+        format!("{}_cap_{}_prefetch", self.policy_type().name(), self.capacity())
+    }
+
+    fn reset_for_benchmark(&mut self) {
+        self.clear();
+        self.reset_prefetch_stats();
+    }
+}
+
+/// Specialized constructors for concrete key types
+impl S3FifoCache<i32, String> {
+    pub fn with_prefetch_i32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl S3FifoCache<i64, String> {
+    pub fn with_prefetch_i64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl S3FifoCache<usize, String> {
+    pub fn with_prefetch_usize(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_usize(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl S3FifoCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl S3FifoCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "S3-FIFO cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+
+/// Ensure thread-safety for parallel benchmarks
+unsafe impl<K, V> Send for S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone + Send,
+    V: Clone + Send,
+{
+}
+unsafe impl<K, V> Sync for S3FifoCache<K, V>
+where
+    K: Hash + Eq + Clone + Sync,
+    V: Clone + Sync,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_s3_fifo_filters_one_hit_wonders_through_ghost() {
+        // One small slot and nine main slots
+        let mut cache = S3FifoCache::new(10);
+        cache.insert(1, 1);
+        cache.get(&1);
+        for key in 2..=10 {
+            cache.insert(key, key);
+        }
+        assert!(!cache.is_in_main(&1));
+
+        // The small queue overflows: 1 was read and moves to main, while
+        // 2, never read again, is evicted and leaves a ghost
+        cache.insert(11, 11);
+        assert!(cache.is_in_main(&1));
+        assert!(!cache.contains_key(&2));
+        assert!(cache.is_ghost(&2));
+        assert!(cache.check_invariants().is_ok());
+
+        // Seen again while a ghost, 2 skips the small queue
+        cache.insert(2, 2);
+        assert!(cache.is_in_main(&2));
+        assert!(!cache.is_ghost(&2));
+
+        // A key never seen before still starts in the small queue
+        cache.insert(50, 50);
+        assert!(cache.contains_key(&50));
+        assert!(!cache.is_in_main(&50));
+        assert!(cache.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_s3_fifo_resists_scans() {
+        // A hot set read twice per round, interleaved with a one-off scan
+        let mut s3 = S3FifoCache::new(20);
+        let mut lru = LruCache::new(20);
+        let mut hits = (0, 0);
+        let mut next_scan = 1_000;
+        for _ in 0..50 {
+            for key in (0..15).chain(0..15) {
+                if s3.get(&key).is_some() {
+                    hits.0 += 1;
+                } else {
+                    s3.insert(key, key);
+                }
+                if lru.get(&key).is_some() {
+                    hits.1 += 1;
+                } else {
+                    lru.insert(key, key);
+                }
+            }
+            for _ in 0..30 {
+                s3.insert(next_scan, next_scan);
+                lru.insert(next_scan, next_scan);
+                next_scan += 1;
+            }
+        }
+        assert!(hits.0 > hits.1, "S3-FIFO {} vs LRU {} hits", hits.0, hits.1);
+        assert!(s3.check_invariants().is_ok());
+        assert!(PolicyType::S3Fifo.is_scan_resistant());
+    }
+}
@@ -0,0 +1,684 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
+use crate::prefetch::{PrefetchType, NoPrefetch};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType};
+
+/// SIEVE cache implementation with prefetch strategies
+///
+/// Entries sit in a FIFO queue and carry a "visited" bit set on every hit.
+/// To evict, a hand sweeps from the oldest end towards the newest, clearing
+/// visited bits as it passes and evicting the first unvisited entry. Unlike
+/// LRU, hits never move entries, and unlike Clock, the hand resumes where it
+/// stopped instead of sending survivors back to the head. New entries that
+/// are never reused are dropped quickly, which suits web-like workloads
+/// dominated by one-hit wonders.
+pub struct SieveCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Map from key to node index for O(1) lookup
+    map: HashMap<K, usize>,
+
+    /// Node storage; slots of removed entries are reused via `free`
+    nodes: Vec<Option<SieveNode<K, V>>>,
+
+    /// Indices of empty slots in `nodes`
+    free: Vec<usize>,
+
+    /// Newest entry
+    head: Option<usize>,
+
+    /// Oldest entry
+    tail: Option<usize>,
+
+    /// Next candidate for eviction; `None` restarts the sweep at the tail
+    hand: Option<usize>,
+
+    /// Maximum cache capacity
+    capacity: usize,
+
+    /// Prefetch strategy implementation (pluggable)
+    prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+
+    /// Prefetched but not yet used items
+    prefetch_buffer: HashMap<K, V>,
+
+    /// Limit for prefetch buffer size
+    prefetch_buffer_size: usize,
+
+    /// Prefetch statistics
+    prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+}
+
+/// Queue node; `prev` points towards the newer end, `next` towards the older
+struct SieveNode<K, V> {
+    key: K,
+    value: V,
+    /// Set on hit, cleared when the hand passes over the entry
+    visited: bool,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+impl<K, V> SieveCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Creates a new SIEVE cache using the default "no prefetch" strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_custom_prefetch(capacity, Box::new(NoPrefetch))
+    }
+
+    /// Creates a SIEVE cache with a custom prefetch strategy
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn with_custom_prefetch(
+        capacity: usize,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+    ) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+
+        Self {
+            map: HashMap::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            tail: None,
+            hand: None,
+            capacity,
+            prefetch_strategy,
+            prefetch_buffer: HashMap::new(),
+            prefetch_buffer_size: (capacity / 4).max(1),
+            prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            prefetch_loader: None,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries, newest first
+    ///
+    /// The hand skips visited entries, so the order only approximates
+    /// survival.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        std::iter::successors(self.head, |&index| self.node(index).next).map(|index| {
+            let node = self.node(index);
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
+        &self.prefetch_stats
+    }
+
+    pub fn reset_prefetch_stats(&mut self) {
+        self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
+        self.prefetch_strategy.reset();
+    }
+
+    /// Verifies internal consistency: the queue links agree in both
+    /// directions, map, queue and free slots account for every node, and
+    /// the hand points into the queue
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() > self.capacity {
+            return Err(format!("len {} exceeds capacity {}", self.map.len(), self.capacity));
+        }
+        let mut walked = 0;
+        let mut prev = None;
+        let mut current = self.head;
+        let mut hand_seen = self.hand.is_none();
+        while let Some(index) = current {
+            if walked == self.map.len() {
+                return Err(format!("queue is longer than the {} mapped keys", self.map.len()));
+            }
+            let node = match self.nodes.get(index) {
+                Some(Some(node)) => node,
+                _ => return Err(format!("queue links to empty slot {}", index)),
+            };
+            if node.prev != prev {
+                return Err(format!("queue has a broken prev link at slot {}", index));
+            }
+            if self.map.get(&node.key) != Some(&index) {
+                return Err(format!("map does not point at slot {}", index));
+            }
+            hand_seen |= self.hand == Some(index);
+            walked += 1;
+            prev = current;
+            current = node.next;
+        }
+        if walked != self.map.len() {
+            return Err(format!("queue holds {} nodes but map holds {}", walked, self.map.len()));
+        }
+        if self.tail != prev {
+            return Err("queue tail does not point at its last node".to_string());
+        }
+        if !hand_seen {
+            return Err("hand points outside the queue".to_string());
+        }
+        if walked + self.free.len() != self.nodes.len() {
+            return Err(format!(
+                "{} nodes and {} free slots don't cover {} slots",
+                walked,
+                self.free.len(),
+                self.nodes.len()
+            ));
+        }
+        if self.free.iter().any(|&index| self.nodes[index].is_some()) {
+            return Err("free list holds an occupied slot".to_string());
+        }
+        Ok(())
+    }
+
+    /// Update prefetch strategy with new access patterns
+    fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
+        self.prefetch_strategy.update_access_pattern(accessed_key);
+        let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
+
+        for predicted_key in predictions {
+            self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
+            if !self.map.contains_key(&predicted_key)
+                && !self.prefetch_buffer.contains_key(&predicted_key)
+            {
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
+            }
+        }
+        self.trim_prefetch_buffer();
+    }
+
+    /// Ensure the prefetch buffer does not exceed the configured size
+    fn trim_prefetch_buffer(&mut self) {
+        while self.prefetch_buffer.len() > self.prefetch_buffer_size {
+            if let Some(key) = self.prefetch_buffer.keys().next().cloned() {
+                self.prefetch_buffer.remove(&key);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn node(&self, index: usize) -> &SieveNode<K, V> {
+        self.nodes[index].as_ref().expect("linked slot is occupied")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut SieveNode<K, V> {
+        self.nodes[index].as_mut().expect("linked slot is occupied")
+    }
+
+    /// Stores a new node at the head of the queue and returns its slot
+    fn push_head(&mut self, key: K, value: V) -> usize {
+        let node = SieveNode {
+            key,
+            value,
+            visited: false,
+            prev: None,
+            next: self.head,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        match self.head {
+            Some(old_head) => self.node_mut(old_head).prev = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.head = Some(index);
+        index
+    }
+
+    /// Unlinks the node at `index`, frees its slot and returns it
+    ///
+    /// A hand resting on the node moves on to the next newer entry.
+    fn unlink(&mut self, index: usize) -> SieveNode<K, V> {
+        let node = self.nodes[index].take().expect("linked slot is occupied");
+        match node.prev {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        if self.hand == Some(index) {
+            self.hand = node.prev;
+        }
+        self.map.remove(&node.key);
+        self.free.push(index);
+        node
+    }
+
+    /// Sweeps the hand towards the head, clearing visited bits, and evicts
+    /// the first unvisited entry
+    fn evict(&mut self) -> Option<(K, V)> {
+        let mut current = self.hand.or(self.tail)?;
+        loop {
+            let node = self.node_mut(current);
+            if !node.visited {
+                break;
+            }
+            node.visited = false;
+            // Wrap around to the oldest entry after the newest
+            current = node.prev.or(self.tail)?;
+        }
+        // Parking the hand on the victim lets `unlink` step it past
+        self.hand = Some(current);
+        let node = self.unlink(current);
+        self.metrics.evictions += 1;
+        Some((node.key, node.value))
+    }
+
+    /// Empties the queue and slot storage without touching prefetch state
+    fn clear_links(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = None;
+        self.tail = None;
+        self.hand = None;
+    }
+}
+
+impl<K, V> CachePolicy<K, V> for SieveCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Gets a value and marks it visited; the entry keeps its queue position
+    fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
+        // Check prefetch buffer first
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            self.prefetch_stats.cache_hits_from_prefetch += 1;
+            self.insert(key.clone(), value);
+            return self.get(key);
+        }
+
+        let index = *self.map.get(key)?;
+        self.node_mut(index).visited = true;
+        self.perform_prefetch(key);
+        Some(&self.node(index).value)
+    }
+
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
+    /// Inserts a new entry at the head, or updates a resident one in place
+    ///
+    /// An update counts as a hit and sets the visited bit.
+    fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        // Invalidate prefetch
+        self.prefetch_buffer.remove(&key);
+
+        if let Some(&index) = self.map.get(&key) {
+            let node = self.node_mut(index);
+            node.value = value;
+            node.visited = true;
+            return;
+        }
+
+        if self.map.len() >= self.capacity {
+            self.evict();
+        }
+        let index = self.push_head(key.clone(), value);
+        self.map.insert(key, index);
+        self.metrics.insertions += 1;
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
+        if let Some(value) = self.prefetch_buffer.remove(key) {
+            return Some(value);
+        }
+        let index = *self.map.get(key)?;
+        Some(self.unlink(index).value)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear_links();
+        self.prefetch_buffer.clear();
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Changes the capacity, sweeping the hand to evict if shrinking
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "SIEVE cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        while self.map.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.evict()
+    }
+
+    /// Checks residency without touching visited bits or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let index = *self.map.get(key)?;
+        Some(&mut self.node_mut(index).value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let index = *self.map.get(key)?;
+        Some(&self.node(index).value)
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for node in self.nodes.iter().flatten() {
+            f(&node.key, &node.value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving visited bits untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for node in self.nodes.iter_mut().flatten() {
+            f(&node.key, &mut node.value);
+        }
+    }
+
+    /// Drops slots freed by removals by rebuilding the queue densely
+    fn compact(&mut self) {
+        if self.free.is_empty() {
+            return;
+        }
+        let mut order = Vec::with_capacity(self.map.len());
+        let mut current = self.tail;
+        while let Some(index) = current {
+            current = self.node(index).prev;
+            order.push(index);
+        }
+        let hand_key = self.hand.map(|index| self.node(index).key.clone());
+
+        let mut nodes = std::mem::take(&mut self.nodes);
+        self.clear_links();
+        // Re-push oldest first so the newest ends up at the head again
+        for index in order {
+            let node = nodes[index].take().expect("linked slot is occupied");
+            let visited = node.visited;
+            let key = node.key.clone();
+            let new_index = self.push_head(node.key, node.value);
+            self.node_mut(new_index).visited = visited;
+            self.map.insert(key, new_index);
+        }
+        self.hand = hand_key.and_then(|key| self.map.get(&key).copied());
+    }
+}
+
+impl<K, V> BenchmarkablePolicy<K, V> for SieveCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn policy_type(&self) -> PolicyType {
+        PolicyType::Sieve
+    }
+
+    fn benchmark_name(&self) -> String {
+        format!("{}_cap_{}_prefetch", self.policy_type().name(), self.capacity())
+    }
+
+    fn reset_for_benchmark(&mut self) {
+        self.clear();
+        self.reset_prefetch_stats();
+    }
+}
+
+/// Specialized constructors for concrete key types
+impl SieveCache<i32, String> {
+    pub fn with_prefetch_i32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl SieveCache<i64, String> {
+    pub fn with_prefetch_i64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_i64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl SieveCache<usize, String> {
+    pub fn with_prefetch_usize(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_usize(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl SieveCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+impl SieveCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SIEVE cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+
+/// Ensure thread-safety for parallel benchmarks
+unsafe impl<K, V> Send for SieveCache<K, V>
+where
+    K: Hash + Eq + Clone + Send,
+    V: Clone + Send,
+{
+}
+unsafe impl<K, V> Sync for SieveCache<K, V>
+where
+    K: Hash + Eq + Clone + Sync,
+    V: Clone + Sync,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::FifoCache;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Web-like trace: a Zipf-skewed popular set interleaved with a stream
+    /// of one-hit wonders that are never requested again
+    fn web_trace(len: usize, seed: u64) -> Vec<i32> {
+        let popular = 500;
+        let weights: Vec<f64> = (1..=popular).map(|rank| 1.0 / rank as f64).collect();
+        let total: f64 = weights.iter().sum();
+        let mut cumulative = Vec::with_capacity(popular);
+        let mut acc = 0.0;
+        for w in weights {
+            acc += w / total;
+            cumulative.push(acc);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut next_one_off = popular as i32;
+        (0..len)
+            .map(|_| {
+                if rng.gen_bool(0.3) {
+                    next_one_off += 1;
+                    next_one_off
+                } else {
+                    let u: f64 = rng.gen_range(0.0..1.0);
+                    cumulative.partition_point(|&c| c < u).min(popular - 1) as i32
+                }
+            })
+            .collect()
+    }
+
+    /// Read-through replay returning the hit ratio
+    fn hit_ratio<C: CachePolicy<i32, i32>>(mut cache: C, trace: &[i32]) -> f64 {
+        let mut hits = 0;
+        for &key in trace {
+            if cache.get(&key).is_some() {
+                hits += 1;
+            } else {
+                cache.insert(key, key);
+            }
+        }
+        hits as f64 / trace.len() as f64
+    }
+
+    #[test]
+    fn test_sieve_beats_fifo_on_web_trace() {
+        let trace = web_trace(50_000, 3);
+        let sieve = hit_ratio(SieveCache::new(100), &trace);
+        let fifo = hit_ratio(FifoCache::new(100), &trace);
+        assert!(sieve > fifo, "SIEVE {:.3} vs FIFO {:.3}", sieve, fifo);
+    }
+
+    #[test]
+    fn test_sieve_hand_spares_visited_entries() {
+        let mut cache = SieveCache::new(3);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        cache.get(&1);
+
+        // The hand clears 1's visited bit and evicts 2, the next oldest
+        cache.insert(4, 4);
+        assert!(cache.contains_key(&1));
+        assert!(!cache.contains_key(&2));
+
+        // The hand resumes at 3 rather than restarting at 1 in the tail,
+        // so the unvisited newest entry 4 goes next
+        cache.get(&3);
+        cache.insert(5, 5);
+        assert!(!cache.contains_key(&4));
+        assert!(cache.contains_key(&1));
+        assert!(cache.contains_key(&3));
+        assert!(cache.check_invariants().is_ok());
+
+        // Compacting after a removal keeps the queue and hand intact
+        cache.remove(&1);
+        cache.compact();
+        assert!(cache.check_invariants().is_ok());
+        cache.insert(6, 6);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_sieve_hit_only_sets_visited_bit() {
+        let mut cache = SieveCache::new(3);
+        for key in 1..=3 {
+            cache.insert(key, key);
+        }
+        let order = |cache: &SieveCache<i32, i32>| cache.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        let visited = |cache: &SieveCache<i32, i32>, key: i32| cache.node(cache.map[&key]).visited;
+        let before = order(&cache);
+
+        for key in [1, 3, 2, 1] {
+            cache.get(&key);
+        }
+        assert_eq!(order(&cache), before);
+        assert!((1..=3).all(|key| visited(&cache, key)));
+
+        // One full sweep clears every bit, then the hand wraps to the oldest
+        cache.insert(4, 4);
+        assert!(!cache.contains_key(&1));
+        assert!(!visited(&cache, 2) && !visited(&cache, 3));
+
+        // Having been passed over once, 2 is evicted on the next visit
+        cache.insert(5, 5);
+        assert!(!cache.contains_key(&2));
+        assert!(cache.contains_key(&3));
+        assert!(!PolicyType::Sieve.is_scan_resistant());
+    }
+}
@@ -3,9 +3,10 @@ use std::hash::Hash;
 use std::ptr::NonNull;
 use std::marker::PhantomData;
 
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::lru::PrefetchLoader;
+use super::{BenchmarkablePolicy, PolicyType, walk_linked_list};
 
 /// Segmented LRU (SLRU) cache implementation with prefetching support
 /// 
@@ -13,6 +14,9 @@ use super::{BenchmarkablePolicy, PolicyType};
 /// - Probationary segment holds newly inserted entries
 /// - Protected segment holds frequently accessed entries
 /// 
+/// A hit in probationary promotes the entry; when the protected segment is
+/// full, its least recently used entry drops back to probationary.
+/// 
 /// This design protects frequent items while evicting one-time accesses quickly.
 pub struct SlruCache<K, V>
 where
@@ -35,12 +39,24 @@ where
 
     /// Total capacity for the entire cache
     capacity: usize,
+    /// Share of the capacity given to the protected segment
+    protected_fraction: f64,
 
     /// Prefetch strategy and buffer
     prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
     prefetch_buffer: HashMap<K, V>,
     prefetch_buffer_size: usize,
     prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
+    /// Optional source of values for predicted keys
+    prefetch_loader: Option<PrefetchLoader<K, V>>,
 
     /// PhantomData for ownership tracking of Nodes
     _marker: PhantomData<Box<Node<K, V>>>,
@@ -62,6 +78,18 @@ enum Segment {
     Protected,
 }
 
+/// Protected share of the capacity used by [`SlruCache::new`]
+const DEFAULT_PROTECTED_FRACTION: f64 = 0.8;
+
+/// Split capacity into (protected, probationary) sizes
+///
+/// Probationary always keeps at least one slot since every new key lands
+/// there first; a capacity of 1 therefore has no protected segment.
+fn segment_capacities(capacity: usize, protected_fraction: f64) -> (usize, usize) {
+    let protected_capacity = (((capacity as f64) * protected_fraction).ceil() as usize).min(capacity - 1);
+    (protected_capacity, capacity - protected_capacity)
+}
+
 impl<K, V> Node<K, V> {
     /// Construct a new node belonging to a specific segment
     fn new(key: K, value: V, segment: Segment) -> Self {
@@ -89,11 +117,37 @@ where
     pub fn with_custom_prefetch(
         capacity: usize,
         prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
+    ) -> Self {
+        Self::with_split_and_prefetch(capacity, DEFAULT_PROTECTED_FRACTION, prefetch_strategy)
+    }
+
+    /// Create with no prefetch strategy, giving `protected_fraction` of the
+    /// capacity (rounded up) to the protected segment
+    ///
+    /// The probationary segment still keeps at least one slot, and the split
+    /// is kept across [`resize`](CachePolicy::resize).
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0 or the fraction is not in `(0, 1)`
+    pub fn with_split(capacity: usize, protected_fraction: f64) -> Self {
+        Self::with_split_and_prefetch(capacity, protected_fraction, Box::new(NoPrefetch))
+    }
+
+    /// Create with specified prefetch strategy and protected share, as in
+    /// [`with_split`](Self::with_split)
+    pub fn with_split_and_prefetch(
+        capacity: usize,
+        protected_fraction: f64,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>,
     ) -> Self {
         assert!(capacity > 0, "SLRU cache capacity must be > 0");
+        assert!(
+            protected_fraction > 0.0 && protected_fraction < 1.0,
+            "SLRU protected fraction must be in (0, 1), got {}",
+            protected_fraction
+        );
 
-        let protected_capacity = ((capacity as f64) * 0.8).ceil() as usize;
-        let probationary_capacity = capacity - protected_capacity;
+        let (protected_capacity, probationary_capacity) = segment_capacities(capacity, protected_fraction);
 
         SlruCache {
             probationary_map: HashMap::new(),
@@ -109,15 +163,66 @@ where
             protected_capacity,
 
             capacity,
+            protected_fraction,
             prefetch_strategy,
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
+            prefetch_loader: None,
 
             _marker: PhantomData,
         }
     }
 
+    /// Maximum number of entries in the protected segment
+    pub fn protected_capacity(&self) -> usize {
+        self.protected_capacity
+    }
+
+    /// Maximum number of entries in the probationary segment
+    pub fn probationary_capacity(&self) -> usize {
+        self.probationary_capacity
+    }
+
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Installs the loader prefetching uses to fetch predicted keys
+    ///
+    /// Loaded predictions wait in the prefetch buffer until read. Without a
+    /// loader predictions are only counted, never loaded.
+    pub fn set_prefetch_loader(&mut self, loader: PrefetchLoader<K, V>) {
+        self.prefetch_loader = Some(loader);
+    }
+
+    /// Builder form of [`set_prefetch_loader`](Self::set_prefetch_loader)
+    pub fn with_loader(mut self, loader: PrefetchLoader<K, V>) -> Self {
+        self.set_prefetch_loader(loader);
+        self
+    }
+
+    /// Iterates resident entries: the protected segment, then the
+    /// probationary one, each most recently used first
+    ///
+    /// Eviction takes from the probationary tail, so entries come roughly
+    /// in the reverse of eviction order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let segment = |head: Option<NonNull<Node<K, V>>>| {
+            std::iter::successors(head, |node| unsafe { node.as_ref().next }).map(|node| {
+                let node = unsafe { &*node.as_ptr() };
+                (&node.key, &node.value)
+            })
+        };
+        segment(self.protected_head).chain(segment(self.probationary_head))
+    }
+
     /// Access prefetch statistics
     pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
         &self.prefetch_stats
@@ -126,22 +231,115 @@ where
     /// Reset prefetch statistics and strategy
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Marks `key` as recently used without reading its value
+    ///
+    /// A probationary entry is promoted to the protected segment, a
+    /// protected one moves to its front, exactly as on a hit. Runs no
+    /// prefetch. Returns whether the key was resident.
+    pub fn touch(&mut self, key: &K) -> bool {
+        if self.probationary_map.contains_key(key) {
+            unsafe { self.promote_to_protected(key) };
+            return true;
+        }
+        if let Some(&node_ptr) = self.protected_map.get(key) {
+            unsafe { self.move_to_front(node_ptr, Segment::Protected) };
+            return true;
+        }
+        false
+    }
+
+    /// Position of `key` counting the protected segment first, then the
+    /// probationary one, each most recently used first
+    pub fn recency_rank(&self, key: &K) -> Option<usize> {
+        let mut rank = 0;
+        for head in [self.protected_head, self.probationary_head] {
+            let mut cursor = head;
+            while let Some(node_ptr) = cursor {
+                let node = unsafe { node_ptr.as_ref() };
+                if node.key == *key {
+                    return Some(rank);
+                }
+                rank += 1;
+                cursor = node.next;
+            }
+        }
+        None
+    }
+
+    /// Verifies internal consistency: each segment's map, list and size
+    /// agree, nodes carry the right segment tag, no key is in both segments
+    /// and segment capacities are respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for (segment, map, head, tail, size, capacity) in [
+            (
+                Segment::Probationary,
+                &self.probationary_map,
+                self.probationary_head,
+                self.probationary_tail,
+                self.probationary_size,
+                self.probationary_capacity,
+            ),
+            (
+                Segment::Protected,
+                &self.protected_map,
+                self.protected_head,
+                self.protected_tail,
+                self.protected_size,
+                self.protected_capacity,
+            ),
+        ] {
+            if map.len() != size {
+                return Err(format!("{:?} map holds {} keys but size is {}", segment, map.len(), size));
+            }
+            if size > capacity {
+                return Err(format!("{:?} size {} exceeds its capacity {}", segment, size, capacity));
+            }
+            let nodes = unsafe {
+                walk_linked_list(&format!("{:?}", segment), head, tail, size, |n| (n.prev, n.next))?
+            };
+            for node in nodes {
+                let node_ref = unsafe { node.as_ref() };
+                if node_ref.segment != segment {
+                    return Err(format!("node in {:?} is tagged {:?}", segment, node_ref.segment));
+                }
+                if map.get(&node_ref.key) != Some(&node) {
+                    return Err(format!("{:?} map entry does not point at its list node", segment));
+                }
+            }
+        }
+        if self.probationary_map.keys().any(|k| self.protected_map.contains_key(k)) {
+            return Err("key present in both segments".to_string());
+        }
+        Ok(())
+    }
+
     /// Perform prefetch update after key access
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
 
             if !self.probationary_map.contains_key(&predicted_key)
                 && !self.protected_map.contains_key(&predicted_key)
                 && !self.prefetch_buffer.contains_key(&predicted_key)
             {
-                // Prefetch prediction (not actually loading in this demo)
+                let Some(value) = self.prefetch_loader.as_ref().and_then(|load| load(&predicted_key)) else {
+                    continue;
+                };
+                self.prefetch_buffer.insert(predicted_key, value);
             }
         }
         self.trim_prefetch_buffer();
@@ -239,7 +437,7 @@ where
     }
 
     /// Evict least recently used node from probationary segment
-    fn evict_probationary_lru(&mut self) -> Option<K> {
+    fn evict_probationary_lru(&mut self) -> Option<(K, V)> {
         if let Some(tail_ptr) = self.probationary_tail {
             unsafe {
                 let tail_node = Box::from_raw(tail_ptr.as_ptr());
@@ -255,8 +453,9 @@ where
                 }
 
                 self.probationary_size -= 1;
+                self.metrics.evictions += 1;
 
-                Some(key)
+                Some((key, tail_node.value))
             }
         } else {
             None
@@ -264,7 +463,7 @@ where
     }
 
     /// Evict least recently used node from protected segment
-    fn evict_protected_lru(&mut self) -> Option<K> {
+    fn evict_protected_lru(&mut self) -> Option<(K, V)> {
         if let Some(tail_ptr) = self.protected_tail {
             unsafe {
                 let tail_node = Box::from_raw(tail_ptr.as_ptr());
@@ -280,24 +479,47 @@ where
                 }
 
                 self.protected_size -= 1;
+                self.metrics.evictions += 1;
 
-                Some(key)
+                Some((key, tail_node.value))
             }
         } else {
             None
         }
     }
 
+    /// Move the protected segment's LRU node to the front of probationary
+    unsafe fn demote_protected_lru(&mut self) {
+        if let Some(tail_ptr) = self.protected_tail {
+            let key = unsafe { tail_ptr.as_ref() }.key.clone();
+            self.protected_map.remove(&key);
+            unsafe { self.remove_from_list(tail_ptr) };
+            self.protected_size -= 1;
+
+            self.probationary_map.insert(key, tail_ptr);
+            unsafe { self.add_to_front(tail_ptr, Segment::Probationary) };
+            self.probationary_size += 1;
+        }
+    }
+
     /// Promote a node from probationary to protected segment
     unsafe fn promote_to_protected(&mut self, key: &K) -> bool {
-        if let Some(node_ptr) = self.probationary_map.remove(key) {
-            if self.protected_size >= self.protected_capacity {
-                self.evict_protected_lru();
+        // A single-slot cache has no protected segment; just refresh recency
+        if self.protected_capacity == 0 {
+            if let Some(&node_ptr) = self.probationary_map.get(key) {
+                unsafe { self.move_to_front(node_ptr, Segment::Probationary) };
             }
-
+            return false;
+        }
+        if let Some(node_ptr) = self.probationary_map.remove(key) {
             unsafe { self.remove_from_list(node_ptr) };
             self.probationary_size -= 1;
 
+            // The slot just freed in probationary takes the protected LRU
+            if self.protected_size >= self.protected_capacity {
+                unsafe { self.demote_protected_lru() };
+            }
+
             self.protected_map.insert(key.clone(), node_ptr);
             unsafe { self.add_to_front(node_ptr, Segment::Protected) };
             self.protected_size += 1;
@@ -316,6 +538,10 @@ where
 {
     /// Retrieve value associated with key, promoting if needed
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -347,8 +573,16 @@ where
         None
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     /// Insert or update cache entry
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         self.prefetch_buffer.remove(&key);
 
         // Update if exists in probationary segment
@@ -381,10 +615,12 @@ where
         self.probationary_map.insert(key, node_ptr);
         unsafe { self.add_to_front(node_ptr, Segment::Probationary) };
         self.probationary_size += 1;
+        self.metrics.insertions += 1;
     }
 
     /// Remove entry from cache if present
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
         }
@@ -420,6 +656,74 @@ where
         self.capacity
     }
 
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Change capacity, recomputing the segment split
+    ///
+    /// Each segment then evicts from its own LRU tail until it fits.
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "SLRU cache capacity must be > 0");
+        self.capacity = new_capacity;
+        let (protected_capacity, probationary_capacity) = segment_capacities(new_capacity, self.protected_fraction);
+        self.protected_capacity = protected_capacity;
+        self.probationary_capacity = probationary_capacity;
+
+        while self.protected_size > self.protected_capacity {
+            self.evict_protected_lru();
+        }
+        while self.probationary_size > self.probationary_capacity {
+            self.evict_probationary_lru();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        self.evict_probationary_lru().or_else(|| self.evict_protected_lru())
+    }
+
+    /// Checks residency without touching eviction order or prefetch state
+    fn contains_key(&self, key: &K) -> bool {
+        self.probationary_map.contains_key(key) || self.protected_map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let node_ptr = *self.probationary_map.get(key).or(self.protected_map.get(key))?;
+        Some(unsafe { &mut (*node_ptr.as_ptr()).value })
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let node_ptr = self.probationary_map.get(key).or(self.protected_map.get(key))?;
+        Some(unsafe { &node_ptr.as_ref().value })
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, node) in self.probationary_map.iter().chain(self.protected_map.iter()) {
+            unsafe { f(key, &node.as_ref().value) }
+        }
+    }
+
+    /// Visits every resident value in place, leaving eviction order untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, node) in self.probationary_map.iter().chain(self.protected_map.iter()) {
+            unsafe { f(key, &mut (*node.as_ptr()).value) }
+        }
+    }
+
     /// Clears all cache entries and frees memory
     fn clear(&mut self) {
         for (_, node_ptr) in self.probationary_map.drain() {
@@ -499,6 +803,22 @@ impl SlruCache<usize, String> {
     }
 }
 
+impl SlruCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SLRU cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+
+impl SlruCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "SLRU cache capacity must be greater than 0");
+        let strat = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, strat)
+    }
+}
+
 unsafe impl<K, V> Send for SlruCache<K, V>
 where
     K: Hash + Eq + Clone + Send,
@@ -513,3 +833,78 @@ where
 {
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slru_small_capacities_keep_inserted_keys() {
+        for capacity in 1..=3 {
+            let mut cache = SlruCache::new(capacity);
+            for key in 0..10 {
+                cache.insert(key, key * 2);
+                assert_eq!(cache.get(&key), Some(&(key * 2)), "capacity {}", capacity);
+                assert!(cache.len() <= capacity);
+                assert!(cache.check_invariants().is_ok());
+            }
+            assert_eq!(cache.len(), capacity);
+            assert!(cache.probationary_capacity() >= 1);
+            assert_eq!(cache.protected_capacity() + cache.probationary_capacity(), capacity);
+        }
+
+        // The default split leaves a single slot entirely probationary
+        let cache: SlruCache<u32, u32> = SlruCache::new(1);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (0, 1));
+    }
+
+    #[test]
+    fn test_slru_with_split_sizes_segments() {
+        let mut cache: SlruCache<u32, u32> = SlruCache::with_split(10, 0.5);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (5, 5));
+        cache.resize(4);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (2, 2));
+
+        let cache: SlruCache<u32, u32> = SlruCache::with_split(10, 0.9);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (9, 1));
+
+        // A large share still leaves one probationary slot
+        let cache: SlruCache<u32, u32> = SlruCache::with_split(2, 0.99);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (1, 1));
+
+        let cache: SlruCache<u32, u32> = SlruCache::new(10);
+        assert_eq!((cache.protected_capacity(), cache.probationary_capacity()), (8, 2));
+    }
+
+    #[test]
+    fn test_slru_promotion_demotes_protected_lru() {
+        for fraction in [0.5, 0.9] {
+            let mut cache = SlruCache::with_split(10, fraction);
+            let protected = cache.protected_capacity() as u32;
+            for key in 0..protected {
+                cache.insert(key, key);
+                cache.get(&key);
+            }
+            assert_eq!(cache.protected_map.len(), protected as usize);
+
+            // Promoting one more pushes key 0 back to probationary, not out
+            cache.insert(100, 100);
+            cache.get(&100);
+            assert!(cache.protected_map.contains_key(&100));
+            assert!(cache.probationary_map.contains_key(&0));
+            assert_eq!(cache.len(), protected as usize + 1);
+            assert!(cache.check_invariants().is_ok());
+
+            // From there a second hit earns it its place back
+            cache.get(&0);
+            assert!(cache.protected_map.contains_key(&0));
+            assert!(cache.probationary_map.contains_key(&1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "protected fraction")]
+    fn test_slru_with_split_rejects_full_fraction() {
+        let _: SlruCache<u32, u32> = SlruCache::with_split(8, 1.0);
+    }
+}
@@ -2,9 +2,9 @@ use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
 use std::ptr::NonNull;
 use std::marker::PhantomData;
-use crate::{CachePolicy, PrefetchStrategy};
+use crate::{Access, CacheMetrics, CachePolicy, PredictionObserver, PrefetchStrategy};
 use crate::prefetch::{PrefetchType, NoPrefetch};
-use super::{BenchmarkablePolicy, PolicyType};
+use super::{BenchmarkablePolicy, PolicyType, walk_linked_list};
 
 /// 2Q cache implementation with integrated prefetch strategies
 ///
@@ -35,6 +35,8 @@ where
     a1_capacity: usize,      // Kin (typically capacity/4)
     a1out_capacity: usize,   // Kout (typically capacity/2)
     am_capacity: usize,      // capacity - a1_capacity
+    kin_fraction: f64,       // A1in share of capacity
+    kout_fraction: f64,      // A1out size relative to capacity
     
     /// Current sizes
     a1_size: usize,
@@ -45,6 +47,14 @@ where
     prefetch_buffer: HashMap<K, V>,
     prefetch_buffer_size: usize,
     prefetch_stats: super::lru::PrefetchStats,
+    /// Predicted keys awaiting a request, for the hit and miss counters
+    predicted: super::PendingPredictions<K>,
+    /// Optional tap receiving each accessed key and its predictions
+    prediction_observer: Option<PredictionObserver<K>>,
+    /// Whether accesses run the prefetch strategy
+    prefetch_enabled: bool,
+    /// Insertions and capacity evictions so far
+    metrics: CacheMetrics,
     
     _marker: PhantomData<Box<Node<K, V>>>,
 }
@@ -68,6 +78,21 @@ impl<K, V> Node<K, V> {
     }
 }
 
+/// Default A1in share of capacity (Kin)
+const DEFAULT_KIN_FRACTION: f64 = 0.25;
+/// Default A1out size relative to capacity (Kout)
+const DEFAULT_KOUT_FRACTION: f64 = 0.5;
+
+/// Split capacity into (A1in, A1out, Am) sizes
+///
+/// A1in and Am both get at least one slot once capacity is 2 or more.
+fn queue_capacities(capacity: usize, kin_fraction: f64, kout_fraction: f64) -> (usize, usize, usize) {
+    let max_a1 = capacity.saturating_sub(1).max(1);
+    let a1_capacity = ((capacity as f64 * kin_fraction) as usize).clamp(1, max_a1);
+    let a1out_capacity = ((capacity as f64 * kout_fraction) as usize).max(1);
+    (a1_capacity, a1out_capacity, capacity - a1_capacity)
+}
+
 impl<K, V> TwoQCache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -83,12 +108,40 @@ where
         capacity: usize,
         prefetch_strategy: Box<dyn PrefetchStrategy<K>>
     ) -> Self {
-        assert!(capacity > 0, "2Q cache capacity must be greater than 0");
-
         // Standard 2Q parameters
-        let a1_capacity = (capacity / 4).max(1);
-        let a1out_capacity = (capacity / 2).max(1);
-        let am_capacity = capacity - a1_capacity;
+        Self::with_ratios(capacity, DEFAULT_KIN_FRACTION, DEFAULT_KOUT_FRACTION, prefetch_strategy)
+    }
+
+    /// Creates a 2Q cache with custom queue ratios
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of resident items (A1in + Am)
+    /// * `kin_fraction` - Share of capacity given to the A1in FIFO, in `(0, 1)`
+    /// * `kout_fraction` - A1out ghost queue size relative to capacity, `> 0`
+    /// * `prefetch_strategy` - Custom prefetch strategy implementation
+    ///
+    /// Each queue keeps at least one slot when capacity allows.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0 or a fraction is out of range
+    pub fn with_ratios(
+        capacity: usize,
+        kin_fraction: f64,
+        kout_fraction: f64,
+        prefetch_strategy: Box<dyn PrefetchStrategy<K>>
+    ) -> Self {
+        assert!(capacity > 0, "2Q cache capacity must be greater than 0");
+        assert!(
+            kin_fraction > 0.0 && kin_fraction < 1.0,
+            "2Q kin_fraction must be between 0 and 1"
+        );
+        assert!(
+            kout_fraction > 0.0 && kout_fraction.is_finite(),
+            "2Q kout_fraction must be positive"
+        );
+
+        let (a1_capacity, a1out_capacity, am_capacity) =
+            queue_capacities(capacity, kin_fraction, kout_fraction);
 
         Self {
             a1: VecDeque::new(),
@@ -101,31 +154,111 @@ where
             a1_capacity,
             a1out_capacity,
             am_capacity,
+            kin_fraction,
+            kout_fraction,
             a1_size: 0,
             am_size: 0,
             prefetch_strategy,
             prefetch_buffer: HashMap::new(),
             prefetch_buffer_size: (capacity / 4).max(1),
             prefetch_stats: super::lru::PrefetchStats::default(),
+            predicted: super::PendingPredictions::new(),
+            prediction_observer: None,
+            prefetch_enabled: true,
+            metrics: CacheMetrics::default(),
             _marker: PhantomData,
         }
     }
 
+    /// Registers a callback receiving each accessed key with the keys
+    /// predicted for it; predictions themselves are unaffected
+    pub fn set_prediction_observer(&mut self, observer: PredictionObserver<K>) {
+        self.prediction_observer = Some(observer);
+    }
+
+    /// Iterates resident entries: Am most recently used first, then A1in
+    /// newest first
+    ///
+    /// Keys in the A1out ghost queue have no values and are not included.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let am = std::iter::successors(self.am_head, |node| unsafe { node.as_ref().next });
+        let a1 = self.a1.iter().rev().filter_map(|key| self.a1_map.get(key).copied());
+        am.chain(a1).map(|node| {
+            let node = unsafe { &*node.as_ptr() };
+            (&node.key, &node.value)
+        })
+    }
+
     pub fn prefetch_stats(&self) -> &super::lru::PrefetchStats {
         &self.prefetch_stats
     }
 
     pub fn reset_prefetch_stats(&mut self) {
         self.prefetch_stats = super::lru::PrefetchStats::default();
+        self.predicted.clear();
         self.prefetch_strategy.reset();
     }
 
+    /// Verifies internal consistency: A1 queue, map and size agree, the Am
+    /// list is intact, A1out is bounded and the capacity is respected
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.a1.len() != self.a1_map.len() || self.a1_map.len() != self.a1_size {
+            return Err(format!(
+                "A1 queue {} / map {} / size {} disagree",
+                self.a1.len(), self.a1_map.len(), self.a1_size
+            ));
+        }
+        if self.am_map.len() != self.am_size {
+            return Err(format!("Am map holds {} keys but size is {}", self.am_map.len(), self.am_size));
+        }
+        let nodes = unsafe {
+            walk_linked_list("Am", self.am_head, self.am_tail, self.am_size, |n| (n.prev, n.next))?
+        };
+        for node in nodes {
+            if self.am_map.get(unsafe { &node.as_ref().key }) != Some(&node) {
+                return Err("Am map entry does not point at its list node".to_string());
+            }
+        }
+        if self.a1.iter().any(|k| !self.a1_map.contains_key(k) || self.am_map.contains_key(k)) {
+            return Err("A1 queue key missing from A1 map or also in Am".to_string());
+        }
+        if self.a1out.len() > self.a1out_capacity {
+            return Err(format!("A1out holds {} keys, over {}", self.a1out.len(), self.a1out_capacity));
+        }
+        if self.a1_size + self.am_size > self.capacity {
+            return Err(format!("A1 + Am = {} exceeds capacity {}", self.a1_size + self.am_size, self.capacity));
+        }
+        Ok(())
+    }
+
+    /// Number of entries in the A1in FIFO
+    pub fn a1in_len(&self) -> usize {
+        self.a1_size
+    }
+
+    /// Number of keys remembered in the A1out ghost queue
+    pub fn a1out_len(&self) -> usize {
+        self.a1out.len()
+    }
+
+    /// Number of entries in the Am LRU list
+    pub fn am_len(&self) -> usize {
+        self.am_size
+    }
+
     fn perform_prefetch(&mut self, accessed_key: &K) {
+        if !self.prefetch_enabled {
+            return;
+        }
         self.prefetch_strategy.update_access_pattern(accessed_key);
         let predictions = self.prefetch_strategy.predict_next(accessed_key);
+        if let Some(observer) = self.prediction_observer.as_mut() {
+            observer(accessed_key, &predictions);
+        }
 
         for predicted_key in predictions {
             self.prefetch_stats.predictions_made += 1;
+            self.prefetch_stats.prefetch_misses += self.predicted.record(predicted_key.clone());
             if !self.a1_map.contains_key(&predicted_key) && 
                !self.am_map.contains_key(&predicted_key) &&
                !self.prefetch_buffer.contains_key(&predicted_key) {
@@ -192,7 +325,7 @@ where
     }
 
     /// Evicts LRU item from Am
-    fn evict_am_lru(&mut self) -> Option<K> {
+    fn evict_am_lru(&mut self) -> Option<(K, V)> {
         if let Some(tail_ptr) = self.am_tail {
             unsafe {
                 let tail_node = Box::from_raw(tail_ptr.as_ptr());
@@ -208,31 +341,35 @@ where
                 }
 
                 self.am_size -= 1;
-                Some(key)
+                self.metrics.evictions += 1;
+                Some((key, tail_node.value))
             }
         } else {
             None
         }
     }
 
+    /// Evicts the oldest A1 entry, remembering its key in A1out
+    fn evict_a1_oldest(&mut self) -> Option<(K, V)> {
+        let evicted_key = self.a1.pop_front()?;
+        let node_ptr = self.a1_map.remove(&evicted_key)?;
+        let node = unsafe { Box::from_raw(node_ptr.as_ptr()) };
+        self.a1_size -= 1;
+        self.metrics.evictions += 1;
+
+        // Add to A1out
+        self.a1out.push_back(evicted_key.clone());
+        if self.a1out.len() > self.a1out_capacity {
+            self.a1out.pop_front();
+        }
+        Some((evicted_key, node.value))
+    }
+
     /// Reclaim space by evicting from A1 and possibly Am
     fn reclaim(&mut self) {
         // First try to evict from A1
         if self.a1_size >= self.a1_capacity {
-            if let Some(evicted_key) = self.a1.pop_front() {
-                if let Some(node_ptr) = self.a1_map.remove(&evicted_key) {
-                    unsafe {
-                        let _node = Box::from_raw(node_ptr.as_ptr());
-                    }
-                    self.a1_size -= 1;
-
-                    // Add to A1out
-                    self.a1out.push_back(evicted_key);
-                    if self.a1out.len() > self.a1out_capacity {
-                        self.a1out.pop_front();
-                    }
-                }
-            }
+            self.evict_a1_oldest();
         }
 
         // If Am is over capacity, evict from Am
@@ -248,6 +385,10 @@ where
     V: Clone,
 {
     fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        if self.predicted.take(key) {
+            self.prefetch_stats.prefetch_hits += 1;
+        }
         // Check prefetch buffer first
         if let Some(_) = self.prefetch_buffer.get(key) {
             if let Some(value) = self.prefetch_buffer.remove(key) {
@@ -278,7 +419,15 @@ where
         None
     }
 
+    /// Reports [`Access::PrefetchHit`] when the key is served from the prefetch buffer
+    fn access(&mut self, key: &K) -> (Access, Option<&V>) {
+        let prefetched = self.prefetch_buffer.contains_key(key);
+        let value = self.get(key);
+        (Access::of(value.is_some(), prefetched), value)
+    }
+
     fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
         // Remove from prefetch buffer if exists
         self.prefetch_buffer.remove(&key);
 
@@ -316,6 +465,7 @@ where
             self.am_map.insert(key, node_ptr);
             unsafe { self.add_am_to_front(node_ptr); }
             self.am_size += 1;
+            self.metrics.insertions += 1;
             return;
         }
 
@@ -329,9 +479,11 @@ where
         self.a1_map.insert(key.clone(), node_ptr);
         self.a1.push_back(key);
         self.a1_size += 1;
+        self.metrics.insertions += 1;
     }
 
     fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
         // Check prefetch buffer first
         if let Some(value) = self.prefetch_buffer.remove(key) {
             return Some(value);
@@ -378,6 +530,81 @@ where
         self.capacity
     }
 
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    fn resize(&mut self, new_capacity: usize) {
+        assert!(new_capacity > 0, "2Q cache capacity must be greater than 0");
+        self.capacity = new_capacity;
+        let (a1_capacity, a1out_capacity, am_capacity) =
+            queue_capacities(new_capacity, self.kin_fraction, self.kout_fraction);
+        self.a1_capacity = a1_capacity;
+        self.a1out_capacity = a1out_capacity;
+        self.am_capacity = am_capacity;
+
+        // Prefer dropping first-time (A1) pages, as reclaim does
+        while self.a1_size + self.am_size > self.capacity {
+            if self.a1_size > self.a1_capacity || self.am_size == 0 {
+                self.evict_a1_oldest();
+            } else {
+                self.evict_am_lru();
+            }
+        }
+        while self.a1out.len() > self.a1out_capacity {
+            self.a1out.pop_front();
+        }
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        // Prefer dropping first-time (A1) pages, as resize does
+        if self.a1_size > self.a1_capacity || self.am_size == 0 {
+            self.evict_a1_oldest()
+        } else {
+            self.evict_am_lru()
+        }
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.a1_map.contains_key(key) || self.am_map.contains_key(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        let node_ptr = *self.a1_map.get(key).or(self.am_map.get(key))?;
+        Some(unsafe { &mut (*node_ptr.as_ptr()).value })
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        let node_ptr = self.a1_map.get(key).or(self.am_map.get(key))?;
+        Some(unsafe { &node_ptr.as_ref().value })
+    }
+
+    fn set_prefetch_enabled(&mut self, enabled: bool) {
+        self.prefetch_enabled = enabled;
+    }
+
+    fn prefetch_enabled(&self) -> bool {
+        self.prefetch_enabled
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, node) in self.a1_map.iter().chain(self.am_map.iter()) {
+            unsafe { f(key, &node.as_ref().value) }
+        }
+    }
+
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, node) in self.a1_map.iter().chain(self.am_map.iter()) {
+            unsafe { f(key, &mut (*node.as_ptr()).value) }
+        }
+    }
+
     fn clear(&mut self) {
         // Deallocate all A1 nodes
         for (_, node_ptr) in self.a1_map.drain() {
@@ -457,6 +684,22 @@ impl TwoQCache<usize, String> {
     }
 }
 
+impl TwoQCache<u32, String> {
+    pub fn with_prefetch_u32(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "2Q cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u32(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
+impl TwoQCache<u64, String> {
+    pub fn with_prefetch_u64(capacity: usize, prefetch_type: PrefetchType) -> Self {
+        assert!(capacity > 0, "2Q cache capacity must be greater than 0");
+        let prefetch_strategy = crate::prefetch::create_prefetch_strategy_u64(prefetch_type);
+        Self::with_custom_prefetch(capacity, prefetch_strategy)
+    }
+}
+
 unsafe impl<K, V> Send for TwoQCache<K, V>
 where
     K: Hash + Eq + Clone + Send,
@@ -470,3 +713,64 @@ where
     V: Clone + Sync,
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_q_ratio_sizes() {
+        let cache: TwoQCache<i32, i32> = TwoQCache::with_ratios(20, 0.5, 1.0, Box::new(NoPrefetch));
+        assert_eq!(cache.a1_capacity, 10);
+        assert_eq!(cache.am_capacity, 10);
+        assert_eq!(cache.a1out_capacity, 20);
+
+        // Tiny ratios still leave one slot per queue
+        let cache: TwoQCache<i32, i32> = TwoQCache::with_ratios(2, 0.01, 0.01, Box::new(NoPrefetch));
+        assert_eq!((cache.a1_capacity, cache.a1out_capacity, cache.am_capacity), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_two_q_queue_lengths() {
+        let mut cache = TwoQCache::new(8);
+        for i in 0..6 {
+            cache.insert(i, i);
+        }
+        // Re-reference a key that fell into A1out
+        cache.insert(0, 0);
+        assert_eq!(cache.am_len(), 1);
+        assert_eq!(cache.a1in_len() + cache.am_len(), cache.len());
+        assert!(cache.a1out_len() > 0);
+    }
+
+    /// Establishes a hot set, runs a one-touch scan, then counts hits on
+    /// the hot set and on the tail of the scan
+    fn scan_then_reuse_hits(kin_fraction: f64) -> (usize, usize) {
+        let mut cache = TwoQCache::with_ratios(20, kin_fraction, 0.5, Box::new(NoPrefetch));
+        let hot: Vec<i32> = (0..12).collect();
+        for _ in 0..3 {
+            for &key in &hot {
+                if cache.get(&key).is_none() {
+                    cache.insert(key, key);
+                }
+            }
+        }
+        for key in 1000..1100 {
+            cache.insert(key, key);
+        }
+        let hot_hits = hot.iter().filter(|&&key| cache.get(&key).is_some()).count();
+        let scan_tail_hits = (1090..1100).filter(|key| cache.get(key).is_some()).count();
+        (hot_hits, scan_tail_hits)
+    }
+
+    #[test]
+    fn test_two_q_ratio_scan_resistance() {
+        let (small_hot, small_tail) = scan_then_reuse_hits(0.2);
+        let (large_hot, large_tail) = scan_then_reuse_hits(0.5);
+
+        // A smaller A1in leaves more room in Am, so more of the hot set survives the scan
+        assert!(small_hot > large_hot, "{} vs {}", small_hot, large_hot);
+        // A larger A1in keeps more of the scan itself around for immediate reuse
+        assert!(large_tail > small_tail, "{} vs {}", large_tail, small_tail);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::{CacheMetrics, CachePolicy};
+
+/// A cache that never evicts on its own, meant for warmup phases
+///
+/// Entries accumulate without bound while recency is tracked, so the
+/// "load everything, then bound" pattern is a bulk load followed by
+/// [`freeze_into`](UnboundedCache::freeze_into). `capacity` reports `usize::MAX`.
+pub struct UnboundedCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Maps key to (value, stamp of its last access)
+    map: HashMap<K, (V, u64)>,
+    /// Keys ordered by last access, oldest first
+    order: BTreeMap<u64, K>,
+    /// Next access stamp to hand out
+    next_stamp: u64,
+    /// Insertions so far; evictions only count `evict_one` calls
+    metrics: CacheMetrics,
+}
+
+impl<K, V> UnboundedCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Creates an empty unbounded cache
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+            order: BTreeMap::new(),
+            next_stamp: 0,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Converts into a bounded policy keeping the `capacity` most recent entries
+    ///
+    /// `build` receives `capacity` and returns the empty target policy. The
+    /// surviving entries are inserted oldest first, so the target sees them
+    /// in the same recency order they had here.
+    ///
+    /// # Panics
+    /// Panics if capacity is 0
+    pub fn freeze_into<C, F>(mut self, capacity: usize, build: F) -> C
+    where
+        C: CachePolicy<K, V>,
+        F: FnOnce(usize) -> C,
+    {
+        assert!(capacity > 0, "frozen cache capacity must be greater than 0");
+        let mut bounded = build(capacity);
+        let skip = self.order.len().saturating_sub(capacity);
+        for key in std::mem::take(&mut self.order).into_values().skip(skip) {
+            if let Some((value, _)) = self.map.remove(&key) {
+                bounded.insert(key, value);
+            }
+        }
+        bounded
+    }
+
+    /// Verifies internal consistency: the recency index and the map agree
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() != self.order.len() {
+            return Err(format!(
+                "map holds {} keys but recency index holds {}",
+                self.map.len(),
+                self.order.len()
+            ));
+        }
+        for (stamp, key) in &self.order {
+            match self.map.get(key) {
+                Some((_, s)) if s == stamp => {}
+                _ => return Err(format!("recency stamp {} does not match its map entry", stamp)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves `key` to the most recent position
+    fn touch(&mut self, key: &K) {
+        if let Some((_, stamp)) = self.map.get_mut(key) {
+            self.order.remove(stamp);
+            *stamp = self.next_stamp;
+            self.order.insert(self.next_stamp, key.clone());
+            self.next_stamp += 1;
+        }
+    }
+}
+
+impl<K, V> Default for UnboundedCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> CachePolicy<K, V> for UnboundedCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Gets a value and marks it most recently used
+    fn get(&mut self, key: &K) -> Option<&V> {
+        debug_check_invariants!(self);
+        self.touch(key);
+        self.map.get(key).map(|(value, _)| value)
+    }
+
+    /// Inserts or updates a key-value pair; never evicts
+    fn insert(&mut self, key: K, value: V) {
+        debug_check_invariants!(self);
+        if let Some((v, _)) = self.map.get_mut(&key) {
+            *v = value;
+            self.touch(&key);
+            return;
+        }
+        self.map.insert(key.clone(), (value, self.next_stamp));
+        self.order.insert(self.next_stamp, key);
+        self.next_stamp += 1;
+        self.metrics.insertions += 1;
+    }
+
+    /// Removes a key, returning its value if present
+    fn remove(&mut self, key: &K) -> Option<V> {
+        debug_check_invariants!(self);
+        let (value, stamp) = self.map.remove(key)?;
+        self.order.remove(&stamp);
+        Some(value)
+    }
+
+    /// Returns the number of entries stored
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Removes all entries from the cache
+    fn clear(&mut self) {
+        self.map.clear();
+        self.order.clear();
+    }
+
+    /// Always `usize::MAX`; the cache never evicts on its own
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    fn reset_metrics(&mut self) {
+        self.metrics = CacheMetrics::default();
+    }
+
+    /// Ignored: an unbounded cache has no capacity to change, use
+    /// `freeze_into` to bound it
+    fn resize(&mut self, _new_capacity: usize) {}
+
+    /// Checks residency without touching recency order
+    fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    fn evict_one(&mut self) -> Option<(K, V)> {
+        let (_, key) = self.order.pop_first()?;
+        let (value, _) = self.map.remove(&key)?;
+        self.metrics.evictions += 1;
+        Some((key, value))
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get(key)?;
+        self.map.get_mut(key).map(|(value, _)| value)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|(value, _)| value)
+    }
+
+    /// Visits every resident entry, leaving eviction order untouched
+    fn for_each_entry(&self, f: &mut dyn FnMut(&K, &V)) {
+        for (key, (value, _)) in &self.map {
+            f(key, value);
+        }
+    }
+
+    /// Visits every resident value in place, leaving recency order untouched
+    fn for_each_value_mut(&mut self, f: &mut dyn FnMut(&K, &mut V)) {
+        for (key, (value, _)) in self.map.iter_mut() {
+            f(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_unbounded_freeze_into_lru() {
+        let mut cache = UnboundedCache::new();
+        for i in 0..1000 {
+            cache.insert(i, i * 2);
+        }
+        assert_eq!(cache.len(), 1000);
+        assert_eq!(cache.capacity(), usize::MAX);
+        // Touching an old key makes it one of the most recent
+        cache.get(&3);
+
+        let mut lru = cache.freeze_into(10, LruCache::new);
+        assert_eq!(lru.len(), 10);
+        assert_eq!(lru.capacity(), 10);
+        assert!(lru.contains_key(&3));
+        assert!(!lru.contains_key(&990));
+
+        // Recency carried over: key 991 is the oldest survivor
+        lru.insert(1000, 0);
+        assert!(!lru.contains_key(&991));
+        assert!(lru.contains_key(&3));
+        for i in 992..1000 {
+            assert_eq!(lru.get(&i), Some(&(i * 2)));
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::PrefetchStrategy;
-use super::{BenchmarkablePrefetch, PrefetchType};
+use super::{BenchmarkablePrefetch, Confidence, NumericKey, PrefetchType};
 use std::collections::{HashMap, VecDeque};
 
+/// Number of recent prediction outcomes kept per strategy
+const RECENT_OUTCOMES: usize = 50;
+
 /// Adaptive prefetch strategy
 ///
 /// This strategy dynamically combines multiple prefetch approaches
@@ -34,6 +37,13 @@ where
     min_confidence: f64,
     /// Current classification of detected access pattern
     current_pattern_type: PatternType,
+    /// Number of consecutive evaluations a new classification must persist
+    /// before `current_pattern_type` switches to it
+    classification_hysteresis: usize,
+    /// Candidate classification waiting to satisfy the hysteresis band
+    pending_pattern_type: PatternType,
+    /// Number of consecutive evaluations the pending classification has held
+    pending_pattern_count: usize,
     /// Internal state machine for sequential detection
     sequential_state: SequentialState<K>,
     /// Internal state machine for stride detection
@@ -42,6 +52,8 @@ where
     history_state: HistoryState<K>,
     /// Number of processed accesses so far
     total_accesses: usize,
+    /// Each strategy's predictions for the last access, scored against the next one
+    pending_predictions: HashMap<StrategyType, Vec<K>>,
 }
 
 /// Set of distinct strategies available
@@ -53,6 +65,16 @@ pub enum StrategyType {
     Hybrid,
 }
 
+impl StrategyType {
+    /// Every strategy, in the order ties between equal weights are broken
+    pub const ALL: [StrategyType; 4] = [
+        StrategyType::Sequential,
+        StrategyType::Stride,
+        StrategyType::HistoryBased,
+        StrategyType::Hybrid,
+    ];
+}
+
 /// Heuristic classification of detected access patterns
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PatternType {
@@ -119,24 +141,15 @@ where
         min_confidence: f64,
     ) -> Self {
         let mut strategy_weights = HashMap::new();
-        strategy_weights.insert(StrategyType::Sequential, 0.25);
-        strategy_weights.insert(StrategyType::Stride, 0.25);
-        strategy_weights.insert(StrategyType::HistoryBased, 0.25);
-        strategy_weights.insert(StrategyType::Hybrid, 0.25);
-
         let mut strategy_performance = HashMap::new();
-        for &strategy_type in &[
-            StrategyType::Sequential,
-            StrategyType::Stride,
-            StrategyType::HistoryBased,
-            StrategyType::Hybrid,
-        ] {
+        for strategy_type in StrategyType::ALL {
+            strategy_weights.insert(strategy_type, 0.25);
             strategy_performance.insert(
                 strategy_type,
                 PerformanceMetrics {
                     total_predictions: 0,
                     successful_predictions: 0,
-                    recent_successes: VecDeque::with_capacity(50),
+                    recent_successes: VecDeque::with_capacity(RECENT_OUTCOMES),
                     confidence: 0.5,
                 },
             );
@@ -152,6 +165,9 @@ where
             learning_rate,
             min_confidence,
             current_pattern_type: PatternType::Unknown,
+            classification_hysteresis: 1,
+            pending_pattern_type: PatternType::Unknown,
+            pending_pattern_count: 0,
             sequential_state: SequentialState {
                 last_key: None,
                 stride: None,
@@ -168,6 +184,7 @@ where
                 pattern_length: 3,
             },
             total_accesses: 0,
+            pending_predictions: HashMap::new(),
         }
     }
 
@@ -176,16 +193,73 @@ where
         &self.strategy_weights
     }
 
+    /// Returns the strategy with the highest current weight
+    ///
+    /// Equal weights are broken in [`StrategyType::ALL`] order.
+    pub fn dominant_strategy(&self) -> StrategyType {
+        let mut dominant = StrategyType::ALL[0];
+        for strategy_type in StrategyType::ALL {
+            if self.weight_of(strategy_type) > self.weight_of(dominant) {
+                dominant = strategy_type;
+            }
+        }
+        dominant
+    }
+
+    /// Returns the weight of [`dominant_strategy`](Self::dominant_strategy)
+    pub fn strategy_confidence(&self) -> f64 {
+        self.weight_of(self.dominant_strategy())
+    }
+
+    fn weight_of(&self, strategy_type: StrategyType) -> f64 {
+        self.strategy_weights.get(&strategy_type).copied().unwrap_or(0.0)
+    }
+
     /// Gets the most recent classification of the access pattern
     pub fn current_pattern_type(&self) -> PatternType {
         self.current_pattern_type
     }
 
+    /// Sets how many consecutive evaluations a new classification must
+    /// persist before the reported pattern type changes
+    ///
+    /// A value of 1 (the default) switches immediately; higher values
+    /// smooth out flip-flopping on noisy traces. Values below 1 are clamped.
+    pub fn set_classification_hysteresis(&mut self, k: usize) {
+        self.classification_hysteresis = k.max(1);
+    }
+
+    /// Returns the configured classification hysteresis
+    pub fn classification_hysteresis(&self) -> usize {
+        self.classification_hysteresis
+    }
+
     /// Returns immutable view of per-strategy performance metrics
     pub fn performance_metrics(&self) -> &HashMap<StrategyType, PerformanceMetrics> {
         &self.strategy_performance
     }
 
+    /// Forgets learned strategy weights and metrics, keeping pattern state
+    ///
+    /// Weights return to uniform and performance metrics are cleared, but the
+    /// access history and the sequential/stride/history detectors stay warm.
+    /// Useful at a phase boundary where the ensemble should re-learn which
+    /// strategy to trust.
+    pub fn reset_weights(&mut self) {
+        for metrics in self.strategy_performance.values_mut() {
+            *metrics = PerformanceMetrics {
+                total_predictions: 0,
+                successful_predictions: 0,
+                recent_successes: VecDeque::with_capacity(RECENT_OUTCOMES),
+                confidence: 0.5,
+            };
+        }
+        for weight in self.strategy_weights.values_mut() {
+            *weight = 0.25;
+        }
+        self.pending_predictions.clear();
+    }
+
     /// Updates internal classification of the access pattern
     fn classify_pattern(&mut self) -> PatternType {
         if self.access_history.len() < 4 {
@@ -206,6 +280,45 @@ where
         PatternType::Mixed
     }
 
+    /// Feeds a fresh classification through the hysteresis band
+    ///
+    /// The reported pattern type only changes once the same candidate has
+    /// been observed for `classification_hysteresis` consecutive evaluations.
+    fn apply_classification(&mut self, candidate: PatternType) {
+        if candidate == self.current_pattern_type {
+            self.pending_pattern_count = 0;
+            return;
+        }
+        if candidate == self.pending_pattern_type {
+            self.pending_pattern_count += 1;
+        } else {
+            self.pending_pattern_type = candidate;
+            self.pending_pattern_count = 1;
+        }
+        if self.pending_pattern_count >= self.classification_hysteresis {
+            self.current_pattern_type = candidate;
+            self.pending_pattern_count = 0;
+        }
+    }
+
+    /// Scores the predictions each strategy made for the previous access
+    /// against the key actually accessed next
+    fn record_outcomes(&mut self, key: &K) {
+        for (strategy_type, preds) in self.pending_predictions.drain() {
+            if let Some(metrics) = self.strategy_performance.get_mut(&strategy_type) {
+                let hit = preds.contains(key);
+                metrics.total_predictions += 1;
+                if hit {
+                    metrics.successful_predictions += 1;
+                }
+                if metrics.recent_successes.len() == RECENT_OUTCOMES {
+                    metrics.recent_successes.pop_front();
+                }
+                metrics.recent_successes.push_back(hit);
+            }
+        }
+    }
+
     /// Updates ensemble weights based on the most recent performance statistics
     fn update_strategy_weights(&mut self) {
         let total_weight: f64 = self
@@ -312,26 +425,6 @@ where
     }
 }
 
-/// Keys that can be mapped to/from `i64` for stride calculations
-trait NumericKey: Clone + std::hash::Hash + Eq {
-    fn to_i64(&self) -> i64;
-    fn add_i64(&self, val: i64) -> Self;
-}
-
-// Implementations for common numeric types
-impl NumericKey for i32 {
-    fn to_i64(&self) -> i64 { *self as i64 }
-    fn add_i64(&self, val: i64) -> Self { (*self as i64 + val) as i32 }
-}
-impl NumericKey for i64 {
-    fn to_i64(&self) -> i64 { *self }
-    fn add_i64(&self, val: i64) -> Self { *self + val }
-}
-impl NumericKey for usize {
-    fn to_i64(&self) -> i64 { *self as i64 }
-    fn add_i64(&self, val: i64) -> Self { (*self as i64 + val).max(0) as usize }
-}
-
 /// Core prefetch strategy implementation
 impl<K> PrefetchStrategy<K> for AdaptivePrefetch<K>
 where
@@ -369,6 +462,7 @@ where
 
         // Combine predictions weighted by strategy confidence
         let mut weighted_predictions: HashMap<K, f64> = HashMap::new();
+        self.pending_predictions = strategy_predictions.clone();
         for (strategy_type, preds) in strategy_predictions {
             if let Some(&weight) = self.strategy_weights.get(&strategy_type) {
                 for (idx, pred) in preds.iter().enumerate() {
@@ -378,9 +472,10 @@ where
             }
         }
 
-        // Rank predictions by accumulated weight
+        // Rank predictions by accumulated weight, lower keys first on ties;
+        // NaN weights from a degenerate learning rate rank as zero
         let mut sorted: Vec<_> = weighted_predictions.into_iter().collect();
-        sorted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        sorted.sort_by_key(|(key, weight)| (std::cmp::Reverse(Confidence::from_f64(*weight)), key.to_i64()));
         for (key, _) in sorted.into_iter().take(self.max_predictions) {
             predictions.push(key);
         }
@@ -388,6 +483,8 @@ where
     }
 
     fn update_access_pattern(&mut self, key: &K) {
+        self.record_outcomes(key);
+
         // Maintain history buffer
         if self.access_history.len() >= self.max_history {
             self.access_history.pop_front();
@@ -449,8 +546,9 @@ where
             *self.history_state.pattern_frequencies.entry(pattern).or_insert(0) += 1;
         }
 
-        // Refresh pattern classification
-        self.current_pattern_type = self.classify_pattern();
+        // Refresh pattern classification (subject to hysteresis)
+        let candidate = self.classify_pattern();
+        self.apply_classification(candidate);
         if self.total_accesses % 20 == 0 {
             self.update_strategy_weights();
         }
@@ -461,6 +559,8 @@ where
         self.access_history.clear();
         self.total_accesses = 0;
         self.current_pattern_type = PatternType::Unknown;
+        self.pending_pattern_type = PatternType::Unknown;
+        self.pending_pattern_count = 0;
 
         self.sequential_state = SequentialState {
             last_key: None,
@@ -478,17 +578,7 @@ where
             pattern_length: 3,
         };
 
-        for metrics in self.strategy_performance.values_mut() {
-            *metrics = PerformanceMetrics {
-                total_predictions: 0,
-                successful_predictions: 0,
-                recent_successes: VecDeque::with_capacity(50),
-                confidence: 0.5,
-            };
-        }
-        for weight in self.strategy_weights.values_mut() {
-            *weight = 0.25;
-        }
+        self.reset_weights();
     }
 }
 
@@ -502,4 +592,129 @@ impl BenchmarkablePrefetch<i64> for AdaptivePrefetch<i64> {
 impl BenchmarkablePrefetch<usize> for AdaptivePrefetch<usize> {
     fn prefetch_type(&self) -> PrefetchType { PrefetchType::Adaptive }
 }
+impl BenchmarkablePrefetch<u32> for AdaptivePrefetch<u32> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Adaptive }
+}
+impl BenchmarkablePrefetch<u64> for AdaptivePrefetch<u64> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Adaptive }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts how often the reported pattern type changes over a trace
+    fn count_transitions(strategy: &mut AdaptivePrefetch<i32>, trace: &[i32]) -> usize {
+        let mut transitions = 0;
+        let mut last = strategy.current_pattern_type();
+        for key in trace {
+            strategy.update_access_pattern(key);
+            let now = strategy.current_pattern_type();
+            if now != last {
+                transitions += 1;
+                last = now;
+            }
+        }
+        transitions
+    }
+
+    /// Builds a trace whose sequential confidence hovers around the
+    /// classification threshold, so the raw classifier flips every access
+    fn noisy_trace() -> Vec<i32> {
+        let mut strides = vec![1; 12];
+        strides.extend([2, 1, 2, 1, 2, 1]);
+        for _ in 0..10 {
+            strides.extend([1, 1, 2, 2]);
+        }
+        let mut trace = vec![0];
+        for stride in strides {
+            let last = *trace.last().unwrap();
+            trace.push(last + stride);
+        }
+        trace
+    }
 
+    #[test]
+    fn test_adaptive_hysteresis_suppresses_oscillation() {
+        let trace = noisy_trace();
+
+        let mut immediate = AdaptivePrefetch::<i32>::new();
+        let raw_transitions = count_transitions(&mut immediate, &trace);
+
+        let mut smoothed = AdaptivePrefetch::<i32>::new();
+        smoothed.set_classification_hysteresis(3);
+        let smoothed_transitions = count_transitions(&mut smoothed, &trace);
+
+        // Without hysteresis the classification flips on almost every access
+        assert!(raw_transitions > 20);
+        assert!(smoothed_transitions <= 3);
+
+        // A sustained shift to a clean sequential run still gets through
+        let last = *trace.last().unwrap();
+        for key in last + 1..last + 20 {
+            smoothed.update_access_pattern(&key);
+        }
+        assert_eq!(smoothed.current_pattern_type(), PatternType::Sequential);
+    }
+
+    #[test]
+    fn test_adaptive_hysteresis_clamped_and_reset() {
+        let mut strategy = AdaptivePrefetch::<i32>::new();
+        strategy.set_classification_hysteresis(0);
+        assert_eq!(strategy.classification_hysteresis(), 1);
+
+        strategy.set_classification_hysteresis(5);
+        for key in 0..10 {
+            strategy.update_access_pattern(&key);
+        }
+        strategy.reset();
+        assert_eq!(strategy.current_pattern_type(), PatternType::Unknown);
+        assert_eq!(strategy.classification_hysteresis(), 5);
+    }
+
+    #[test]
+    fn test_adaptive_reset_weights_keeps_history() {
+        let mut strategy = AdaptivePrefetch::<i32>::new();
+        for key in 0..100 {
+            strategy.predict_next(&key);
+            strategy.update_access_pattern(&key);
+        }
+        // Skew the learned state directly
+        strategy.strategy_weights.insert(StrategyType::Sequential, 0.7);
+        strategy.strategy_weights.insert(StrategyType::Stride, 0.1);
+        if let Some(metrics) = strategy.strategy_performance.get_mut(&StrategyType::Sequential) {
+            metrics.total_predictions = 10;
+            metrics.successful_predictions = 8;
+        }
+
+        let history_len = strategy.access_history.len();
+        let pattern = strategy.current_pattern_type();
+        strategy.reset_weights();
+
+        assert!(strategy.strategy_weights().values().all(|&w| (w - 0.25).abs() < 1e-9));
+        assert!(strategy.performance_metrics().values().all(|m| m.total_predictions == 0));
+        assert_eq!(strategy.access_history.len(), history_len);
+        assert_eq!(strategy.current_pattern_type(), pattern);
+        assert!(strategy.sequential_state.last_key.is_some());
+    }
+
+    #[test]
+    fn test_adaptive_dominant_strategy_follows_sequential_run() {
+        let mut strategy = AdaptivePrefetch::<i32>::new();
+        assert!((strategy.strategy_confidence() - 0.25).abs() < 1e-9);
+
+        // Same order as the caches: record the access, then predict
+        for key in 0..200 {
+            strategy.update_access_pattern(&key);
+            strategy.predict_next(&key);
+        }
+
+        assert_eq!(strategy.dominant_strategy(), StrategyType::Sequential);
+        let confidence = strategy.strategy_confidence();
+        assert!(confidence > 0.25);
+        assert!(confidence > strategy.strategy_weights()[&StrategyType::Hybrid]);
+        let metrics = &strategy.performance_metrics()[&StrategyType::Sequential];
+        assert!(metrics.successful_predictions > 150);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::PrefetchStrategy;
-use super::{BenchmarkablePrefetch, PrefetchType};
+use super::{BenchmarkablePrefetch, Confidence, PrefetchType};
 use std::collections::{HashMap, VecDeque};
 
 /// History-based prefetch strategy.
@@ -33,9 +33,19 @@ where
 #[derive(Debug, Clone)]
 struct CorrelationEntry {
     frequency: usize,
-    confidence: f64,
+    confidence: Confidence,
     last_seen: usize,
-    success_rate: f64,
+    success_rate: Confidence,
+}
+
+impl CorrelationEntry {
+    /// Ranking score: confidence * (1 + frequency / 10) * (1 + success rate),
+    /// in fixed point so it is totally ordered
+    fn score(&self) -> u128 {
+        self.confidence.raw() as u128
+            * (10 + self.frequency as u128)
+            * (Confidence::SCALE as u128 + self.success_rate.raw() as u128)
+    }
 }
 
 impl<K> HistoryBasedPrefetch<K>
@@ -85,9 +95,9 @@ where
         self.correlation_table.retain(|_, correlations| {
             correlations.retain(|_, entry| {
                 entry.frequency >= self.min_frequency
-                    || entry.confidence > 0.3
+                    || entry.confidence > Confidence::from_f64(0.3)
                     || (now - entry.last_seen) < self.history_window_size * 2
-                    || entry.success_rate > 0.5
+                    || entry.success_rate > Confidence::from_f64(0.5)
             });
             !correlations.is_empty()
         });
@@ -101,7 +111,7 @@ where
             self.correlation_table
                 .values()
                 .flat_map(|m| m.values())
-                .map(|e| e.confidence)
+                .map(|e| e.confidence.to_f64())
                 .sum::<f64>()
                 / total_correlations as f64
         } else {
@@ -116,6 +126,29 @@ where
     }
 }
 
+impl<K> HistoryBasedPrefetch<K>
+where
+    K: Copy + std::hash::Hash + Eq + Ord,
+{
+    /// Correlated next keys of the current pattern, best score first
+    ///
+    /// Equal scores are ordered by key so the ranking is deterministic.
+    fn ranked_predictions(&self) -> Vec<K> {
+        let Some(correlations) = self
+            .current_pattern()
+            .and_then(|pattern| self.correlation_table.get(&pattern))
+        else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<_> = correlations
+            .iter()
+            .filter(|(_, e)| e.frequency >= self.min_frequency && e.confidence > Confidence::from_f64(0.2))
+            .collect();
+        candidates.sort_by_key(|(key, e)| (std::cmp::Reverse(e.score()), **key));
+        candidates.into_iter().take(self.max_predictions).map(|(k, _)| *k).collect()
+    }
+}
+
 /// Diagnostic stats returned by `stats()`
 #[derive(Debug, Clone)]
 pub struct HistoryStats {
@@ -128,24 +161,7 @@ pub struct HistoryStats {
 // Implementation for i32 keys
 impl PrefetchStrategy<i32> for HistoryBasedPrefetch<i32> {
     fn predict_next(&mut self, _accessed_key: &i32) -> Vec<i32> {
-        if let Some(pattern) = self.current_pattern() {
-            if let Some(correlations) = self.correlation_table.get(&pattern) {
-                let mut candidates: Vec<_> = correlations
-                    .iter()
-                    .filter(|(_, e)| e.frequency >= self.min_frequency && e.confidence > 0.2)
-                    .collect();
-                candidates.sort_by(|a, b| {
-                    let score_a = a.1.confidence * (1.0 + a.1.frequency as f64 / 10.0) * (1.0 + a.1.success_rate);
-                    let score_b = b.1.confidence * (1.0 + b.1.frequency as f64 / 10.0) * (1.0 + b.1.success_rate);
-                    score_b.partial_cmp(&score_a).unwrap()
-                });
-                candidates.into_iter().take(self.max_predictions).map(|(k, _)| *k).collect()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
+        self.ranked_predictions()
     }
 
     fn update_access_pattern(&mut self, key: &i32) {
@@ -170,13 +186,13 @@ impl PrefetchStrategy<i32> for HistoryBasedPrefetch<i32> {
             let correlations = self.correlation_table.entry(pattern).or_default();
             let entry = correlations.entry(next_key).or_insert(CorrelationEntry {
                 frequency: 0,
-                confidence: 0.3,
+                confidence: Confidence::from_f64(0.3),
                 last_seen: self.total_observations,
-                success_rate: 0.0,
+                success_rate: Confidence::ZERO,
             });
 
             entry.frequency += 1;
-            entry.confidence = (entry.confidence + self.learning_rate * 0.5).min(1.0);
+            entry.confidence = entry.confidence.saturating_add(Confidence::from_f64(self.learning_rate * 0.5));
             entry.last_seen = self.total_observations;
         }
 
@@ -195,24 +211,7 @@ impl PrefetchStrategy<i32> for HistoryBasedPrefetch<i32> {
 // Implementation for i64 keys
 impl PrefetchStrategy<i64> for HistoryBasedPrefetch<i64> {
     fn predict_next(&mut self, _accessed_key: &i64) -> Vec<i64> {
-        if let Some(pattern) = self.current_pattern() {
-            if let Some(correlations) = self.correlation_table.get(&pattern) {
-                let mut candidates: Vec<_> = correlations
-                    .iter()
-                    .filter(|(_, e)| e.frequency >= self.min_frequency && e.confidence > 0.2)
-                    .collect();
-                candidates.sort_by(|a, b| {
-                    let score_a = a.1.confidence * (1.0 + a.1.frequency as f64 / 10.0) * (1.0 + a.1.success_rate);
-                    let score_b = b.1.confidence * (1.0 + b.1.frequency as f64 / 10.0) * (1.0 + b.1.success_rate);
-                    score_b.partial_cmp(&score_a).unwrap()
-                });
-                candidates.into_iter().take(self.max_predictions).map(|(k, _)| *k).collect()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
+        self.ranked_predictions()
     }
 
     fn update_access_pattern(&mut self, key: &i64) {
@@ -237,13 +236,13 @@ impl PrefetchStrategy<i64> for HistoryBasedPrefetch<i64> {
             let correlations = self.correlation_table.entry(pattern).or_default();
             let entry = correlations.entry(next_key).or_insert(CorrelationEntry {
                 frequency: 0,
-                confidence: 0.3,
+                confidence: Confidence::from_f64(0.3),
                 last_seen: self.total_observations,
-                success_rate: 0.0,
+                success_rate: Confidence::ZERO,
             });
 
             entry.frequency += 1;
-            entry.confidence = (entry.confidence + self.learning_rate * 0.5).min(1.0);
+            entry.confidence = entry.confidence.saturating_add(Confidence::from_f64(self.learning_rate * 0.5));
             entry.last_seen = self.total_observations;
         }
 
@@ -259,73 +258,62 @@ impl PrefetchStrategy<i64> for HistoryBasedPrefetch<i64> {
     }
 }
 
-// Implementation for usize keys
-impl PrefetchStrategy<usize> for HistoryBasedPrefetch<usize> {
-    fn predict_next(&mut self, _accessed_key: &usize) -> Vec<usize> {
-        if let Some(pattern) = self.current_pattern() {
-            if let Some(correlations) = self.correlation_table.get(&pattern) {
-                let mut candidates: Vec<_> = correlations
-                    .iter()
-                    .filter(|(_, e)| e.frequency >= self.min_frequency && e.confidence > 0.2)
-                    .collect();
-                candidates.sort_by(|a, b| {
-                    let score_a = a.1.confidence * (1.0 + a.1.frequency as f64 / 10.0) * (1.0 + a.1.success_rate);
-                    let score_b = b.1.confidence * (1.0 + b.1.frequency as f64 / 10.0) * (1.0 + b.1.success_rate);
-                    score_b.partial_cmp(&score_a).unwrap()
-                });
-                candidates.into_iter().take(self.max_predictions).map(|(k, _)| *k).collect()
-            } else {
-                Vec::new()
+// Implementation for unsigned keys (usize, u32, u64)
+macro_rules! impl_unsigned_key {
+    ($($key:ty),*) => {$(
+        impl PrefetchStrategy<$key> for HistoryBasedPrefetch<$key> {
+            fn predict_next(&mut self, _accessed_key: &$key) -> Vec<$key> {
+                self.ranked_predictions()
             }
-        } else {
-            Vec::new()
-        }
-    }
-
-    fn update_access_pattern(&mut self, key: &usize) {
-        if self.access_history.len() >= self.history_window_size {
-            self.access_history.pop_front();
-        }
-        self.access_history.push_back(*key);
-        self.total_observations += 1;
 
-        if self.access_history.len() >= self.pattern_length + 1 {
-            let pattern: Vec<usize> = self.access_history
-                .range(..self.access_history.len() - 1)
-                .cloned()
-                .rev()
-                .take(self.pattern_length)
-                .collect::<Vec<_>>()
-                .into_iter()
-                .rev()
-                .collect();
-            let next_key = *key;
-
-            let correlations = self.correlation_table.entry(pattern).or_default();
-            let entry = correlations.entry(next_key).or_insert(CorrelationEntry {
-                frequency: 0,
-                confidence: 0.3,
-                last_seen: self.total_observations,
-                success_rate: 0.0,
-            });
-
-            entry.frequency += 1;
-            entry.confidence = (entry.confidence + self.learning_rate * 0.5).min(1.0);
-            entry.last_seen = self.total_observations;
-        }
+            fn update_access_pattern(&mut self, key: &$key) {
+                if self.access_history.len() >= self.history_window_size {
+                    self.access_history.pop_front();
+                }
+                self.access_history.push_back(*key);
+                self.total_observations += 1;
+
+                if self.access_history.len() > self.pattern_length {
+                    let pattern: Vec<$key> = self.access_history
+                        .range(..self.access_history.len() - 1)
+                        .cloned()
+                        .rev()
+                        .take(self.pattern_length)
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .rev()
+                        .collect();
+                    let next_key = *key;
+
+                    let correlations = self.correlation_table.entry(pattern).or_default();
+                    let entry = correlations.entry(next_key).or_insert(CorrelationEntry {
+                        frequency: 0,
+                        confidence: Confidence::from_f64(0.3),
+                        last_seen: self.total_observations,
+                        success_rate: Confidence::ZERO,
+                    });
+
+                    entry.frequency += 1;
+                    entry.confidence = entry.confidence.saturating_add(Confidence::from_f64(self.learning_rate * 0.5));
+                    entry.last_seen = self.total_observations;
+                }
+
+                if self.total_observations % (self.history_window_size * 5) == 0 {
+                    self.cleanup_correlations();
+                }
+            }
 
-        if self.total_observations % (self.history_window_size * 5) == 0 {
-            self.cleanup_correlations();
+            fn reset(&mut self) {
+                self.access_history.clear();
+                self.correlation_table.clear();
+                self.total_observations = 0;
+            }
         }
-    }
-
-    fn reset(&mut self) {
-        self.access_history.clear();
-        self.correlation_table.clear();
-        self.total_observations = 0;
-    }
+    )*};
 }
 
+impl_unsigned_key!(usize, u32, u64);
+
 impl BenchmarkablePrefetch<i32> for HistoryBasedPrefetch<i32> {
     fn prefetch_type(&self) -> PrefetchType {
         PrefetchType::HistoryBased
@@ -341,6 +329,16 @@ impl BenchmarkablePrefetch<usize> for HistoryBasedPrefetch<usize> {
         PrefetchType::HistoryBased
     }
 }
+impl BenchmarkablePrefetch<u32> for HistoryBasedPrefetch<u32> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::HistoryBased
+    }
+}
+impl BenchmarkablePrefetch<u64> for HistoryBasedPrefetch<u64> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::HistoryBased
+    }
+}
 
 #[cfg(test)]
 mod tests {
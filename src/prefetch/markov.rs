@@ -1,6 +1,6 @@
 use crate::PrefetchStrategy;
-use super::{BenchmarkablePrefetch, PrefetchType};
-use std::collections::HashMap;
+use super::{BenchmarkablePrefetch, Confidence, NumericKey, PrefetchType};
+use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
 
 /// Markov Chain prefetch strategy
@@ -15,15 +15,29 @@ use std::hash::Hash;
 /// - Adapts to changing access patterns over time
 /// - Can handle multiple possible transitions from each state
 /// - Works well for pointer chasing, tree traversals, and irregular patterns
+///
+/// By default the next key is predicted from the current key alone. With
+/// [`with_order`](Self::with_order) the chain conditions on the last
+/// `order` keys instead, telling apart paths that pass through the same key.
 #[derive(Debug, Clone)]
 pub struct MarkovPrefetch<K> 
 where
     K: Clone + Hash + Eq,
 {
     /// Transition matrix: state -> (next_state -> probability)
-    transitions: HashMap<K, HashMap<K, f64>>,
-    /// Current state in the Markov chain
-    current_state: Option<K>,
+    transitions: HashMap<K, StateTransitions<K>>,
+    /// Transitions out of contexts of 2 to `order` keys, oldest first
+    ///
+    /// Kept apart so the default first-order chain never builds a context key.
+    context_transitions: HashMap<Vec<K>, StateTransitions<K>>,
+    /// Decay rounds applied so far; states catch up to it when touched
+    decay_epoch: u64,
+    /// Epoch of the last sweep bringing every state up to date
+    swept_at: u64,
+    /// Last `order` accessed keys, oldest first; the current state is the last
+    context: VecDeque<K>,
+    /// Number of preceding keys a transition is conditioned on
+    order: usize,
     /// History of recent accesses for context
     access_history: Vec<K>,
     /// Maximum history length to maintain
@@ -40,6 +54,61 @@ where
     decay_factor: f64,
 }
 
+/// Outgoing transitions of one state, decayed lazily
+///
+/// Every transition decays all probabilities by `decay_factor`. Rather
+/// than touching every state each time, a state records the epoch its
+/// probabilities were last decayed to and applies the missing rounds at
+/// once, as `decay_factor^rounds`, when it is next read or updated.
+#[derive(Debug, Clone)]
+struct StateTransitions<K> {
+    /// Probabilities as of `decayed_at`
+    probs: HashMap<K, f64>,
+    /// Decay epoch `probs` reflects
+    decayed_at: u64,
+}
+
+impl<K> StateTransitions<K>
+where
+    K: Clone + Hash + Eq,
+{
+    /// Factor turning stored probabilities into ones current at `epoch`
+    fn pending_decay(&self, epoch: u64, decay_factor: f64) -> f64 {
+        let rounds = (epoch - self.decayed_at).min(i32::MAX as u64) as i32;
+        decay_factor.powi(rounds)
+    }
+
+    /// Applies pending decay, dropping transitions that fell below `floor`
+    ///
+    /// Decay only shrinks probabilities, so a transition under the floor
+    /// now was under it at some round in between too, and eager decay
+    /// would have dropped it then.
+    fn catch_up(&mut self, epoch: u64, decay_factor: f64, floor: f64) {
+        if self.decayed_at == epoch {
+            return;
+        }
+        let factor = self.pending_decay(epoch, decay_factor);
+        for prob in self.probs.values_mut() {
+            *prob *= factor;
+        }
+        self.probs.retain(|_, prob| *prob >= floor);
+        self.decayed_at = epoch;
+    }
+
+    /// Moves probability towards `to`, keeping the state normalized
+    fn strengthen(&mut self, to: &K, learning_rate: f64) {
+        let current_prob = self.probs.get(to).copied().unwrap_or(0.0);
+        self.probs.insert(to.clone(), current_prob + learning_rate * (1.0 - current_prob));
+
+        let total: f64 = self.probs.values().sum();
+        if total > 0.0 {
+            for prob in self.probs.values_mut() {
+                *prob /= total;
+            }
+        }
+    }
+}
+
 impl<K> MarkovPrefetch<K>
 where
     K: Clone + Hash + Eq,
@@ -66,7 +135,11 @@ where
     ) -> Self {
         Self {
             transitions: HashMap::new(),
-            current_state: None,
+            context_transitions: HashMap::new(),
+            decay_epoch: 0,
+            swept_at: 0,
+            context: VecDeque::new(),
+            order: 1,
             access_history: Vec::with_capacity(max_history),
             max_history,
             min_probability,
@@ -77,15 +150,44 @@ where
         }
     }
 
+    /// Sets how many preceding keys each transition is conditioned on
+    ///
+    /// An order-2 chain learns `(A, B) -> C` rather than `B -> C`, so it can
+    /// tell `A -> B -> C` from `X -> B -> D`. Shorter contexts are learned
+    /// alongside and used while the full one is unseen. Defaults to 1;
+    /// values below 1 are clamped.
+    pub fn with_order(mut self, order: usize) -> Self {
+        self.order = order.max(1);
+        self
+    }
+
+    /// Number of preceding keys each transition is conditioned on
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
     /// Returns the current state of the Markov chain
     pub fn current_state(&self) -> Option<&K> {
-        self.current_state.as_ref()
+        self.context.back()
     }
 
-    /// Returns the transition probabilities from the current state
-    pub fn current_transitions(&self) -> Option<&HashMap<K, f64>> {
-        self.current_state.as_ref()
-            .and_then(|state| self.transitions.get(state))
+    /// Returns the first-order transition probabilities from the current state
+    pub fn current_transitions(&self) -> Option<HashMap<K, f64>> {
+        self.context_state(1)
+            .map(|transitions| self.decayed_transitions(transitions).collect())
+    }
+
+    /// Markov predictions from the longest seen context with their
+    /// probabilities, most likely first
+    ///
+    /// Unlike `predict_next` this adds no context or fallback predictions.
+    pub fn predict_next_scored(&self) -> Vec<(K, f64)> {
+        (1..=self.context.len())
+            .rev()
+            .filter_map(|len| self.context_state(len))
+            .map(|transitions| self.get_sorted_predictions(transitions))
+            .find(|predictions| !predictions.is_empty())
+            .unwrap_or_default()
     }
 
     /// Returns the total number of observed transitions
@@ -93,69 +195,95 @@ where
         self.total_transitions
     }
 
-    /// Updates transition probabilities with decay
-    fn update_transition(&mut self, from: &K, to: &K) {
-        // Apply decay to all existing transitions
+    /// Transitions weaker than this are dropped once decayed
+    fn prune_floor(&self) -> f64 {
+        self.min_probability / 10.0
+    }
+
+    /// Records a move to `to`, once for every context length up to `order`
+    fn observe_transition(&mut self, to: &K) {
+        // Decay every existing transition, lazily
         if self.total_transitions > 0 {
-            self.apply_decay();
+            self.decay_epoch += 1;
+            self.sweep_if_due();
+        }
+
+        let (epoch, decay_factor, floor) = (self.decay_epoch, self.decay_factor, self.prune_floor());
+        let fresh = || StateTransitions {
+            probs: HashMap::new(),
+            decayed_at: epoch,
+        };
+
+        if let Some(from) = self.context.back() {
+            let from_transitions = self.transitions.entry(from.clone()).or_insert_with(fresh);
+            from_transitions.catch_up(epoch, decay_factor, floor);
+            from_transitions.strengthen(to, self.learning_rate);
+        }
+        for len in 2..=self.context.len() {
+            let from: Vec<K> = self.context.range(self.context.len() - len..).cloned().collect();
+            let from_transitions = self.context_transitions.entry(from).or_insert_with(fresh);
+            from_transitions.catch_up(epoch, decay_factor, floor);
+            from_transitions.strengthen(to, self.learning_rate);
         }
 
-        // Update the specific transition
-        let from_transitions = self.transitions.entry(from.clone()).or_insert_with(HashMap::new);
-        
-        let current_prob = from_transitions.get(to).unwrap_or(&0.0);
-        let new_prob = current_prob + self.learning_rate * (1.0 - current_prob);
-        
-        from_transitions.insert(to.clone(), new_prob);
-        
-        // Normalize probabilities for this state
-        self.normalize_state_probabilities(from);
-        
         self.total_transitions += 1;
     }
 
-    /// Applies decay to all transition probabilities
-    fn apply_decay(&mut self) {
-        for state_transitions in self.transitions.values_mut() {
-            for prob in state_transitions.values_mut() {
-                *prob *= self.decay_factor;
-            }
-            
-            // Remove transitions that have become too weak
-            state_transitions.retain(|_, prob| *prob >= self.min_probability / 10.0);
+    /// Outgoing transitions of the last `len` keys of the context, if seen
+    fn context_state(&self, len: usize) -> Option<&StateTransitions<K>> {
+        if len == 1 {
+            self.context.back().and_then(|state| self.transitions.get(state))
+        } else {
+            let from: Vec<K> = self.context.range(self.context.len() - len..).cloned().collect();
+            self.context_transitions.get(&from)
         }
     }
 
-    /// Normalizes probabilities for a given state to sum to 1.0
-    fn normalize_state_probabilities(&mut self, state: &K) {
-        if let Some(transitions) = self.transitions.get_mut(state) {
-            let total: f64 = transitions.values().sum();
-            
-            if total > 0.0 {
-                for prob in transitions.values_mut() {
-                    *prob /= total;
-                }
-            }
+    /// Brings every state up to date once per state-count epochs
+    ///
+    /// Keeps states that are never touched again from holding transitions
+    /// eager decay would have dropped, at amortized constant cost.
+    fn sweep_if_due(&mut self) {
+        let states = self.transitions.len() + self.context_transitions.len();
+        if self.decay_epoch - self.swept_at < states as u64 {
+            return;
         }
+        let (epoch, decay_factor, floor) = (self.decay_epoch, self.decay_factor, self.prune_floor());
+        for state_transitions in self.transitions.values_mut().chain(self.context_transitions.values_mut()) {
+            state_transitions.catch_up(epoch, decay_factor, floor);
+        }
+        self.swept_at = epoch;
+    }
+
+    /// Transitions of one state with pending decay applied
+    ///
+    /// Normalizing can leave a transition just under the floor until the
+    /// next decay round, so only states with rounds pending are filtered.
+    fn decayed_transitions<'a>(
+        &'a self,
+        transitions: &'a StateTransitions<K>,
+    ) -> impl Iterator<Item = (K, f64)> + 'a {
+        let factor = transitions.pending_decay(self.decay_epoch, self.decay_factor);
+        let floor = if transitions.decayed_at < self.decay_epoch { self.prune_floor() } else { 0.0 };
+        transitions
+            .probs
+            .iter()
+            .map(move |(key, prob)| (key.clone(), prob * factor))
+            .filter(move |(_, prob)| *prob >= floor)
     }
 
     /// Gets predictions sorted by probability
-    fn get_sorted_predictions(&self, state: &K) -> Vec<(K, f64)> {
-        if let Some(transitions) = self.transitions.get(state) {
-            let mut predictions: Vec<_> = transitions
-                .iter()
-                .filter(|(_, prob)| **prob >= self.min_probability)
-                .map(|(key, prob)| (key.clone(), *prob))
-                .collect();
-            
-            // Sort by probability (descending)
-            predictions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-            predictions.truncate(self.max_predictions);
-            
-            predictions
-        } else {
-            Vec::new()
-        }
+    fn get_sorted_predictions(&self, transitions: &StateTransitions<K>) -> Vec<(K, f64)> {
+        let mut predictions: Vec<_> = self
+            .decayed_transitions(transitions)
+            .filter(|(_, prob)| *prob >= self.min_probability)
+            .collect();
+
+        // Sort by probability (descending); fixed point keeps NaN from panicking
+        predictions.sort_by_key(|(_, prob)| std::cmp::Reverse(Confidence::from_f64(*prob)));
+        predictions.truncate(self.max_predictions);
+
+        predictions
     }
 
     /// Adds context-aware predictions using recent history
@@ -206,16 +334,15 @@ where
     }
 }
 
-// Implementation for i32 keys
-impl PrefetchStrategy<i32> for MarkovPrefetch<i32> {
-    fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
+impl<K> PrefetchStrategy<K> for MarkovPrefetch<K>
+where
+    K: NumericKey,
+{
+    fn predict_next(&mut self, accessed_key: &K) -> Vec<K> {
         let mut predictions = Vec::new();
 
-        // Get Markov chain predictions
-        if let Some(state) = &self.current_state {
-            let markov_predictions = self.get_sorted_predictions(state);
-            predictions.extend(markov_predictions.into_iter().map(|(key, _)| key));
-        }
+        // Get Markov chain predictions from the longest context seen before
+        predictions.extend(self.predict_next_scored().into_iter().map(|(key, _)| key));
 
         // If we don't have enough Markov predictions, use context
         if predictions.len() < self.max_predictions {
@@ -230,10 +357,14 @@ impl PrefetchStrategy<i32> for MarkovPrefetch<i32> {
             }
         }
 
-        // If still not enough, add some sequential predictions as fallback
+        // If still not enough, add some sequential predictions as fallback.
+        // Keys saturating at their type's bound repeat, so those are skipped
         if predictions.is_empty() && self.total_transitions < 10 {
             for i in 1..=self.max_predictions.min(3) {
-                predictions.push(accessed_key + i as i32);
+                let next_key = accessed_key.add_i64(i as i64);
+                if next_key != *accessed_key && !predictions.contains(&next_key) {
+                    predictions.push(next_key);
+                }
             }
         }
 
@@ -241,17 +372,20 @@ impl PrefetchStrategy<i32> for MarkovPrefetch<i32> {
         predictions
     }
 
-    fn update_access_pattern(&mut self, key: &i32) {
-        // Update transition if we have a previous state
-        if let Some(prev_state) = self.current_state.clone() {
-            self.update_transition(&prev_state, key);
+    fn update_access_pattern(&mut self, key: &K) {
+        // Update transitions if we have a previous state
+        if !self.context.is_empty() {
+            self.observe_transition(key);
         }
 
-        // Update current state
-        self.current_state = Some(*key);
+        // Update current context
+        self.context.push_back(key.clone());
+        if self.context.len() > self.order {
+            self.context.pop_front();
+        }
 
         // Update access history
-        self.access_history.push(*key);
+        self.access_history.push(key.clone());
         if self.access_history.len() > self.max_history {
             self.access_history.remove(0);
         }
@@ -259,133 +393,38 @@ impl PrefetchStrategy<i32> for MarkovPrefetch<i32> {
 
     fn reset(&mut self) {
         self.transitions.clear();
-        self.current_state = None;
+        self.context_transitions.clear();
+        self.context.clear();
         self.access_history.clear();
         self.total_transitions = 0;
     }
 }
 
-// Implementation for i64 keys
-impl PrefetchStrategy<i64> for MarkovPrefetch<i64> {
-    fn predict_next(&mut self, accessed_key: &i64) -> Vec<i64> {
-        let mut predictions = Vec::new();
-
-        if let Some(state) = &self.current_state {
-            let markov_predictions = self.get_sorted_predictions(state);
-            predictions.extend(markov_predictions.into_iter().map(|(key, _)| key));
-        }
-
-        if predictions.len() < self.max_predictions {
-            let context_predictions = self.get_context_predictions();
-            for pred in context_predictions {
-                if !predictions.contains(&pred) {
-                    predictions.push(pred);
-                    if predictions.len() >= self.max_predictions {
-                        break;
-                    }
-                }
-            }
-        }
-
-        if predictions.is_empty() && self.total_transitions < 10 {
-            for i in 1..=self.max_predictions.min(3) {
-                predictions.push(accessed_key + i as i64);
-            }
-        }
-
-        predictions.truncate(self.max_predictions);
-        predictions
-    }
-
-    fn update_access_pattern(&mut self, key: &i64) {
-        if let Some(prev_state) = self.current_state.clone() {
-            self.update_transition(&prev_state, key);
-        }
-
-        self.current_state = Some(*key);
-        self.access_history.push(*key);
-        
-        if self.access_history.len() > self.max_history {
-            self.access_history.remove(0);
-        }
-    }
-
-    fn reset(&mut self) {
-        self.transitions.clear();
-        self.current_state = None;
-        self.access_history.clear();
-        self.total_transitions = 0;
+impl BenchmarkablePrefetch<i32> for MarkovPrefetch<i32> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Markov
     }
 }
 
-// Implementation for usize keys
-impl PrefetchStrategy<usize> for MarkovPrefetch<usize> {
-    fn predict_next(&mut self, accessed_key: &usize) -> Vec<usize> {
-        let mut predictions = Vec::new();
-
-        if let Some(state) = &self.current_state {
-            let markov_predictions = self.get_sorted_predictions(state);
-            predictions.extend(markov_predictions.into_iter().map(|(key, _)| key));
-        }
-
-        if predictions.len() < self.max_predictions {
-            let context_predictions = self.get_context_predictions();
-            for pred in context_predictions {
-                if !predictions.contains(&pred) {
-                    predictions.push(pred);
-                    if predictions.len() >= self.max_predictions {
-                        break;
-                    }
-                }
-            }
-        }
-
-        if predictions.is_empty() && self.total_transitions < 10 {
-            for i in 1..=self.max_predictions.min(3) {
-                if let Some(next_key) = accessed_key.checked_add(i) {
-                    predictions.push(next_key);
-                }
-            }
-        }
-
-        predictions.truncate(self.max_predictions);
-        predictions
-    }
-
-    fn update_access_pattern(&mut self, key: &usize) {
-        if let Some(prev_state) = self.current_state.clone() {
-            self.update_transition(&prev_state, key);
-        }
-
-        self.current_state = Some(*key);
-        self.access_history.push(*key);
-        
-        if self.access_history.len() > self.max_history {
-            self.access_history.remove(0);
-        }
-    }
-
-    fn reset(&mut self) {
-        self.transitions.clear();
-        self.current_state = None;
-        self.access_history.clear();
-        self.total_transitions = 0;
+impl BenchmarkablePrefetch<i64> for MarkovPrefetch<i64> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Markov
     }
 }
 
-impl BenchmarkablePrefetch<i32> for MarkovPrefetch<i32> {
+impl BenchmarkablePrefetch<usize> for MarkovPrefetch<usize> {
     fn prefetch_type(&self) -> PrefetchType {
         PrefetchType::Markov
     }
 }
 
-impl BenchmarkablePrefetch<i64> for MarkovPrefetch<i64> {
+impl BenchmarkablePrefetch<u32> for MarkovPrefetch<u32> {
     fn prefetch_type(&self) -> PrefetchType {
         PrefetchType::Markov
     }
 }
 
-impl BenchmarkablePrefetch<usize> for MarkovPrefetch<usize> {
+impl BenchmarkablePrefetch<u64> for MarkovPrefetch<u64> {
     fn prefetch_type(&self) -> PrefetchType {
         PrefetchType::Markov
     }
@@ -514,4 +553,150 @@ mod tests {
         let predictions = strategy.predict_next(&large_key);
         assert!(predictions.len() <= 3);
     }
+
+    /// Predictions after each access of a short cyclic trace
+    fn trace_predictions<K: NumericKey>(keys: &[K]) -> Vec<Vec<i64>> {
+        let mut strategy = MarkovPrefetch::<K>::new();
+        keys.iter()
+            .map(|key| {
+                strategy.update_access_pattern(key);
+                strategy.predict_next(key).iter().map(NumericKey::to_i64).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_markov_key_types_predict_alike() {
+        let trace = [5, 6, 9, 5, 6, 9, 5, 40, 5, 6];
+        let expected = trace_predictions(&trace);
+        assert_eq!(expected[0], vec![6, 7, 8]);
+        assert_eq!(trace_predictions(&trace.map(|k| k as i64)), expected);
+        assert_eq!(trace_predictions(&trace.map(|k| k as usize)), expected);
+        assert_eq!(trace_predictions(&trace.map(|k| k as u32)), expected);
+
+        // Fallback predictions stop at the type's maximum without repeats
+        assert_eq!(trace_predictions(&[usize::MAX - 1]), vec![vec![i64::MAX]]);
+        assert_eq!(trace_predictions(&[u32::MAX - 2]), vec![vec![u32::MAX as i64 - 1, u32::MAX as i64]]);
+        assert!(trace_predictions(&[u64::MAX])[0].is_empty());
+    }
+
+    #[test]
+    fn test_markov_order_two_disambiguates_shared_state() {
+        // 1 -> 5 -> 2 and 3 -> 5 -> 4 both pass through 5
+        let train = |order: usize| {
+            let mut strategy = MarkovPrefetch::<i32>::with_config(10, 0.0, 1, 0.5, 1.0).with_order(order);
+            for _ in 0..4 {
+                for key in [1, 5, 2, 3, 5, 4] {
+                    strategy.update_access_pattern(&key);
+                }
+            }
+            strategy
+        };
+
+        // First order only sees 5, and follows whichever came last
+        let mut first = train(1);
+        first.update_access_pattern(&1);
+        first.update_access_pattern(&5);
+        assert_eq!(first.predict_next(&5), vec![4]);
+        // The first-order chain builds no multi-key contexts at all
+        assert!(first.context_transitions.is_empty());
+
+        let mut second = train(2);
+        assert_eq!(second.order(), 2);
+        second.update_access_pattern(&1);
+        second.update_access_pattern(&5);
+        assert_eq!(second.predict_next(&5), vec![2]);
+        second.update_access_pattern(&3);
+        second.update_access_pattern(&5);
+        assert_eq!(second.predict_next(&5), vec![4]);
+
+        // An unseen pair falls back to the first-order context
+        second.update_access_pattern(&9);
+        second.update_access_pattern(&5);
+        let fallback = second.predict_next_scored();
+        assert!(!fallback.is_empty());
+        assert_eq!(fallback, second.get_sorted_predictions(&second.transitions[&5]));
+    }
+
+    /// The eager decay this strategy used before decay became lazy
+    struct EagerMarkov {
+        transitions: HashMap<i32, HashMap<i32, f64>>,
+        total_transitions: usize,
+        learning_rate: f64,
+        decay_factor: f64,
+        floor: f64,
+    }
+
+    impl EagerMarkov {
+        fn update_transition(&mut self, from: i32, to: i32) {
+            if self.total_transitions > 0 {
+                for state_transitions in self.transitions.values_mut() {
+                    for prob in state_transitions.values_mut() {
+                        *prob *= self.decay_factor;
+                    }
+                    state_transitions.retain(|_, prob| *prob >= self.floor);
+                }
+            }
+            let from_transitions = self.transitions.entry(from).or_default();
+            let current_prob = from_transitions.get(&to).copied().unwrap_or(0.0);
+            from_transitions.insert(to, current_prob + self.learning_rate * (1.0 - current_prob));
+            let total: f64 = from_transitions.values().sum();
+            for prob in from_transitions.values_mut() {
+                *prob /= total;
+            }
+            self.total_transitions += 1;
+        }
+    }
+
+    #[test]
+    fn test_markov_lazy_decay_matches_eager() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut lazy = MarkovPrefetch::<i32>::with_config(10, 0.1, 3, 0.3, 0.9);
+        let mut eager = EagerMarkov {
+            transitions: HashMap::new(),
+            total_transitions: 0,
+            learning_rate: 0.3,
+            decay_factor: 0.9,
+            floor: 0.01,
+        };
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut previous = None;
+        for step in 0..5_000 {
+            // A small hot set revisited often, and a long tail rarely seen again
+            let key = if rng.gen_bool(0.7) { rng.gen_range(0..8) } else { rng.gen_range(0..400) };
+            lazy.update_access_pattern(&key);
+            if let Some(from) = previous {
+                eager.update_transition(from, key);
+            }
+            previous = Some(key);
+
+            // Compare the outgoing transitions of the state just left
+            if let Some(from) = lazy.access_history.iter().rev().nth(1).copied() {
+                let expected = eager.transitions.get(&from).cloned().unwrap_or_default();
+                let actual: HashMap<i32, f64> =
+                    lazy.transitions.get(&from).map(|t| lazy.decayed_transitions(t).collect()).unwrap_or_default();
+                assert_eq!(actual.len(), expected.len(), "state {} at step {}", from, step);
+                for (to, prob) in &expected {
+                    assert!((actual[to] - prob).abs() < 1e-9, "{} -> {} at step {}", from, to, step);
+                }
+            }
+
+            let expected: HashMap<i32, f64> = eager.transitions.get(&key).cloned().unwrap_or_default();
+            let actual = lazy.current_transitions().unwrap_or_default();
+            assert_eq!(actual.len(), expected.len(), "state {} at step {}", key, step);
+            let mut scored = lazy.predict_next_scored();
+            let mut reference: Vec<(i32, f64)> = expected.into_iter().filter(|(_, prob)| *prob >= 0.1).collect();
+            scored.sort_by_key(|(to, _)| *to);
+            reference.sort_by_key(|(to, _)| *to);
+            if reference.len() <= 3 {
+                assert_eq!(scored.len(), reference.len(), "step {}", step);
+                for ((to, prob), (ref_to, ref_prob)) in scored.iter().zip(&reference) {
+                    assert_eq!(to, ref_to);
+                    assert!((prob - ref_prob).abs() < 1e-9);
+                }
+            }
+        }
+    }
 }
@@ -5,12 +5,111 @@ pub mod markov;
 pub mod stride;
 pub mod history_based;
 pub mod adaptive;
+pub mod modular;
+pub mod scan_aware;
 
 pub use sequential::SequentialPrefetch;
 pub use markov::MarkovPrefetch;
 pub use stride::StridePrefetch;
 pub use history_based::HistoryBasedPrefetch;
 pub use adaptive::AdaptivePrefetch;
+pub use modular::ModularPrefetch;
+pub use scan_aware::ScanAwarePrefetch;
+
+/// Keys that can be mapped to/from `i64` for stride calculations
+///
+/// Implement it for a custom key type to use the strategies generic over
+/// it, such as [`AdaptivePrefetch`] and [`ModularPrefetch`].
+pub trait NumericKey: Clone + std::hash::Hash + Eq + Send {
+    /// The key as a signed offset
+    fn to_i64(&self) -> i64;
+    /// The key moved by `val`, which may be negative
+    fn add_i64(&self, val: i64) -> Self;
+}
+
+// Implementations for common numeric types
+impl NumericKey for i32 {
+    fn to_i64(&self) -> i64 { *self as i64 }
+    fn add_i64(&self, val: i64) -> Self { (*self as i64 + val) as i32 }
+}
+impl NumericKey for i64 {
+    fn to_i64(&self) -> i64 { *self }
+    fn add_i64(&self, val: i64) -> Self { *self + val }
+}
+
+// Narrow signed keys clamp at their bounds instead of wrapping
+macro_rules! impl_numeric_key_signed {
+    ($($key:ty),*) => {$(
+        impl NumericKey for $key {
+            fn to_i64(&self) -> i64 { *self as i64 }
+            fn add_i64(&self, val: i64) -> Self {
+                (*self as i64).saturating_add(val).clamp(<$key>::MIN as i64, <$key>::MAX as i64) as $key
+            }
+        }
+    )*};
+}
+
+// Unsigned keys saturate at zero and at their maximum, so a negative
+// stride near zero stops at zero rather than wrapping around
+macro_rules! impl_numeric_key_unsigned {
+    ($($key:ty),*) => {$(
+        impl NumericKey for $key {
+            fn to_i64(&self) -> i64 { i64::try_from(*self).unwrap_or(i64::MAX) }
+            fn add_i64(&self, val: i64) -> Self {
+                let delta = <$key>::try_from(val.unsigned_abs()).unwrap_or(<$key>::MAX);
+                if val < 0 { self.saturating_sub(delta) } else { self.saturating_add(delta) }
+            }
+        }
+    )*};
+}
+
+impl_numeric_key_signed!(i8, i16);
+impl_numeric_key_unsigned!(u8, u16, u32, u64, usize);
+
+/// Fixed-point confidence in `[0, 1]`, stored in ten-thousandths
+///
+/// Unlike `f64` it is totally ordered and hashable, so rankings built on it
+/// sort without panics and compare deterministically. Conversion from `f64`
+/// clamps to the range and maps NaN to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Confidence(u16);
+
+impl Confidence {
+    /// Raw value of full confidence
+    pub const SCALE: u16 = 10_000;
+    /// No confidence at all
+    pub const ZERO: Confidence = Confidence(0);
+    /// Full confidence
+    pub const ONE: Confidence = Confidence(Self::SCALE);
+
+    /// Rounds `value` to the nearest step; NaN becomes zero
+    pub fn from_f64(value: f64) -> Self {
+        if value.is_nan() {
+            return Self::ZERO;
+        }
+        Confidence((value.clamp(0.0, 1.0) * Self::SCALE as f64).round() as u16)
+    }
+
+    /// Builds from ten-thousandths, clamped to [`SCALE`](Self::SCALE)
+    pub fn from_raw(raw: u16) -> Self {
+        Confidence(raw.min(Self::SCALE))
+    }
+
+    /// Value in ten-thousandths
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// Value as a fraction in `[0, 1]`
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+
+    /// Sum capped at full confidence
+    pub fn saturating_add(self, other: Confidence) -> Self {
+        Self::from_raw(self.0.saturating_add(other.0))
+    }
+}
 
 /// Enumeration of available prefetch strategy types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -20,6 +119,7 @@ pub enum PrefetchType {
     Stride,
     HistoryBased,
     Adaptive,
+    Modular,
     None,
 }
 
@@ -31,6 +131,7 @@ impl PrefetchType {
             PrefetchType::Stride => "Stride",
             PrefetchType::HistoryBased => "HistoryBased",
             PrefetchType::Adaptive => "Adaptive",
+            PrefetchType::Modular => "Modular",
             PrefetchType::None => "None",
         }
     }
@@ -42,6 +143,7 @@ impl PrefetchType {
             PrefetchType::Stride => "Detects and predicts multiple stride patterns simultaneously",
             PrefetchType::HistoryBased => "Learns from historical access sequences (n-grams)",
             PrefetchType::Adaptive => "Dynamically combines multiple strategies with performance weighting",
+            PrefetchType::Modular => "Predicts recently seen keys sharing the accessed key's residue class",
             PrefetchType::None => "No prefetching - baseline strategy",
         }
     }
@@ -53,6 +155,7 @@ impl PrefetchType {
             PrefetchType::Stride,
             PrefetchType::HistoryBased,
             PrefetchType::Adaptive,
+            PrefetchType::Modular,
             PrefetchType::None,
         ]
     }
@@ -80,6 +183,7 @@ pub fn create_prefetch_strategy_i32(prefetch_type: PrefetchType) -> Box<dyn Pref
         PrefetchType::Stride => Box::new(StridePrefetch::<i32>::new()),
         PrefetchType::HistoryBased => Box::new(HistoryBasedPrefetch::<i32>::new()),
         PrefetchType::Adaptive => Box::new(AdaptivePrefetch::<i32>::new()),
+        PrefetchType::Modular => Box::new(ModularPrefetch::<i32>::default()),
         PrefetchType::None => Box::new(NoPrefetch),
     }
 }
@@ -91,6 +195,7 @@ pub fn create_prefetch_strategy_i64(prefetch_type: PrefetchType) -> Box<dyn Pref
         PrefetchType::Stride => Box::new(StridePrefetch::<i64>::new()),
         PrefetchType::HistoryBased => Box::new(HistoryBasedPrefetch::<i64>::new()),
         PrefetchType::Adaptive => Box::new(AdaptivePrefetch::<i64>::new()),
+        PrefetchType::Modular => Box::new(ModularPrefetch::<i64>::default()),
         PrefetchType::None => Box::new(NoPrefetch),
     }
 }
@@ -102,6 +207,31 @@ pub fn create_prefetch_strategy_usize(prefetch_type: PrefetchType) -> Box<dyn Pr
         PrefetchType::Stride => Box::new(StridePrefetch::<usize>::new()),
         PrefetchType::HistoryBased => Box::new(HistoryBasedPrefetch::<usize>::new()),
         PrefetchType::Adaptive => Box::new(AdaptivePrefetch::<usize>::new()),
+        PrefetchType::Modular => Box::new(ModularPrefetch::<usize>::default()),
+        PrefetchType::None => Box::new(NoPrefetch),
+    }
+}
+
+pub fn create_prefetch_strategy_u32(prefetch_type: PrefetchType) -> Box<dyn PrefetchStrategy<u32>> {
+    match prefetch_type {
+        PrefetchType::Sequential => Box::new(SequentialPrefetch::<u32>::new()),
+        PrefetchType::Markov => Box::new(MarkovPrefetch::<u32>::new()),
+        PrefetchType::Stride => Box::new(StridePrefetch::<u32>::new()),
+        PrefetchType::HistoryBased => Box::new(HistoryBasedPrefetch::<u32>::new()),
+        PrefetchType::Adaptive => Box::new(AdaptivePrefetch::<u32>::new()),
+        PrefetchType::Modular => Box::new(ModularPrefetch::<u32>::default()),
+        PrefetchType::None => Box::new(NoPrefetch),
+    }
+}
+
+pub fn create_prefetch_strategy_u64(prefetch_type: PrefetchType) -> Box<dyn PrefetchStrategy<u64>> {
+    match prefetch_type {
+        PrefetchType::Sequential => Box::new(SequentialPrefetch::<u64>::new()),
+        PrefetchType::Markov => Box::new(MarkovPrefetch::<u64>::new()),
+        PrefetchType::Stride => Box::new(StridePrefetch::<u64>::new()),
+        PrefetchType::HistoryBased => Box::new(HistoryBasedPrefetch::<u64>::new()),
+        PrefetchType::Adaptive => Box::new(AdaptivePrefetch::<u64>::new()),
+        PrefetchType::Modular => Box::new(ModularPrefetch::<u64>::default()),
         PrefetchType::None => Box::new(NoPrefetch),
     }
 }
@@ -154,6 +284,13 @@ where
                 adaptability: "Very High",
                 best_use_case: "Mixed or changing access patterns",
             },
+            PrefetchType::Modular => PrefetchCharacteristics {
+                prediction_accuracy: "High for partitioned data",
+                memory_overhead: "Low-Medium",
+                cpu_overhead: "Low",
+                adaptability: "Medium",
+                best_use_case: "Keys of one partition or hash bucket accessed together",
+            },
             PrefetchType::None => PrefetchCharacteristics {
                 prediction_accuracy: "N/A",
                 memory_overhead: "None",
@@ -233,8 +370,43 @@ mod tests {
         assert!(all_types.contains(&PrefetchType::Stride));
         assert!(all_types.contains(&PrefetchType::HistoryBased));
         assert!(all_types.contains(&PrefetchType::Adaptive));
+        assert!(all_types.contains(&PrefetchType::Modular));
         assert!(all_types.contains(&PrefetchType::None));
-        assert_eq!(all_types.len(), 6);
+        assert_eq!(all_types.len(), 7);
+    }
+
+    #[test]
+    fn test_confidence_ranking_survives_nan() {
+        assert_eq!(Confidence::from_f64(f64::NAN), Confidence::ZERO);
+        assert_eq!(Confidence::from_f64(-0.5), Confidence::ZERO);
+        assert_eq!(Confidence::from_f64(3.0), Confidence::ONE);
+        assert_eq!(Confidence::from_f64(0.25).raw(), 2500);
+        assert!(Confidence::from_f64(0.3) > Confidence::from_f64(0.2));
+
+        // A NaN learning rate turns every adaptive weight into NaN
+        let mut adaptive = AdaptivePrefetch::<i32>::with_config(20, 4, 5, f64::NAN, 0.3);
+        let mut history = HistoryBasedPrefetch::<i32>::with_config(10, 1, 4, 1, f64::NAN);
+        let mut markov = MarkovPrefetch::<i32>::with_config(10, 0.0, 3, f64::NAN, f64::NAN);
+        for key in (0..40).chain([3, 1, 4, 1, 5, 9, 2, 6]).chain(0..40) {
+            adaptive.update_access_pattern(&key);
+            history.update_access_pattern(&(key % 4));
+            markov.update_access_pattern(&(key % 4));
+            adaptive.predict_next(&key);
+            history.predict_next(&(key % 4));
+            markov.predict_next(&(key % 4));
+        }
+        for _ in 0..5 {
+            assert_eq!(adaptive.predict_next(&39), adaptive.predict_next(&39));
+            assert_eq!(history.predict_next(&3), history.predict_next(&3));
+            assert_eq!(markov.predict_next(&3), markov.predict_next(&3));
+        }
+
+        let mut markov = MarkovPrefetch::<i32>::new();
+        for key in [1, 2, 1, 2, 1, 3, 1, 2] {
+            markov.update_access_pattern(&key);
+        }
+        let scored = markov.predict_next_scored();
+        assert!(scored.windows(2).all(|w| w[0].1 >= w[1].1));
     }
 
     #[test]
@@ -322,4 +494,47 @@ mod tests {
         assert_eq!(adaptive.benchmark_name(), "Adaptive_prefetch");
         assert_eq!(<NoPrefetch as BenchmarkablePrefetch<i32>>::benchmark_name(&none), "None_prefetch");
     }
+
+    #[test]
+    fn test_numeric_key_saturates_on_narrow_types() {
+        assert_eq!(3u32.add_i64(-5), 0);
+        assert_eq!(0u64.add_i64(-1), 0);
+        assert_eq!(250u8.add_i64(10), u8::MAX);
+        assert_eq!(u64::MAX.add_i64(1), u64::MAX);
+        assert_eq!(7u16.add_i64(i64::MIN), 0);
+        assert_eq!(u64::MAX.to_i64(), i64::MAX);
+        assert_eq!(120i8.add_i64(10), i8::MAX);
+        assert_eq!((-32_000i16).add_i64(-1_000), i16::MIN);
+        assert_eq!(40u32.add_i64(-8), 32);
+    }
+
+    #[test]
+    fn test_unsigned_factories_cover_every_strategy() {
+        for &prefetch_type in PrefetchType::all() {
+            let mut narrow = create_prefetch_strategy_u32(prefetch_type);
+            let mut wide = create_prefetch_strategy_u64(prefetch_type);
+            for key in (0..20).rev() {
+                narrow.update_access_pattern(&key);
+                wide.update_access_pattern(&(key as u64));
+                narrow.predict_next(&key);
+                wide.predict_next(&(key as u64));
+            }
+            narrow.predict_next(&u32::MAX);
+            wide.predict_next(&u64::MAX);
+        }
+
+        let mut sequential = create_prefetch_strategy_u64(PrefetchType::Sequential);
+        for key in [100u64, 108, 116, 124] {
+            sequential.update_access_pattern(&key);
+        }
+        assert_eq!(sequential.predict_next(&124).first(), Some(&132));
+
+        use crate::CachePolicy;
+        let mut cache = crate::policies::ArcCache::with_prefetch_u64(8, PrefetchType::Adaptive);
+        for key in 0..16u64 {
+            cache.insert(key, key.to_string());
+            cache.get(&key);
+        }
+        assert_eq!(cache.get(&15).map(String::as_str), Some("15"));
+    }
 }
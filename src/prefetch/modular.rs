@@ -0,0 +1,161 @@
+use crate::PrefetchStrategy;
+use super::{BenchmarkablePrefetch, NumericKey, PrefetchType};
+use std::collections::{HashMap, VecDeque};
+
+/// Modular (residue class) prefetch strategy.
+///
+/// Groups keys by `key % modulus` and, on an access, predicts the keys of
+/// the same class seen most recently. Captures partition or hash-bucket
+/// locality where keys sharing a class are accessed together, a dimension
+/// stride and sequence detectors don't see.
+#[derive(Debug, Clone)]
+pub struct ModularPrefetch<K>
+where
+    K: NumericKey,
+{
+    /// Modulus defining the residue classes
+    modulus: u64,
+    /// Recently accessed keys per class, most recent first
+    classes: HashMap<u64, VecDeque<K>>,
+    /// Maximum number of keys remembered per class
+    class_history: usize,
+    /// Maximum number of predictions per access
+    max_predictions: usize,
+}
+
+impl<K> ModularPrefetch<K>
+where
+    K: NumericKey,
+{
+    /// Creates a modular prefetcher with default history and prediction limits
+    ///
+    /// # Panics
+    /// Panics if modulus is 0
+    pub fn new(modulus: u64) -> Self {
+        Self::with_config(modulus, 4, 3)
+    }
+
+    /// Creates a modular prefetcher with custom configuration
+    ///
+    /// # Arguments
+    /// * `modulus` - Keys are grouped by `key % modulus`
+    /// * `class_history` - How many recent keys are remembered per class
+    /// * `max_predictions` - Maximum number of keys to predict per access
+    ///
+    /// # Panics
+    /// Panics if modulus is 0
+    pub fn with_config(modulus: u64, class_history: usize, max_predictions: usize) -> Self {
+        assert!(modulus > 0, "Modular prefetch modulus must be greater than 0");
+        Self {
+            modulus,
+            classes: HashMap::new(),
+            class_history: class_history.max(1),
+            max_predictions,
+        }
+    }
+
+    /// Returns the configured modulus
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// Residue class of `key`, non-negative even for negative keys
+    fn class_of(&self, key: &K) -> u64 {
+        key.to_i64().rem_euclid(self.modulus as i64) as u64
+    }
+}
+
+impl<K> Default for ModularPrefetch<K>
+where
+    K: NumericKey,
+{
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl<K> PrefetchStrategy<K> for ModularPrefetch<K>
+where
+    K: NumericKey,
+{
+    /// Predicts the most recently seen other keys of the accessed key's class
+    fn predict_next(&mut self, accessed_key: &K) -> Vec<K> {
+        let class = self.class_of(accessed_key);
+        self.classes
+            .get(&class)
+            .map(|recent| {
+                recent
+                    .iter()
+                    .filter(|key| *key != accessed_key)
+                    .take(self.max_predictions)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Moves the key to the front of its class's recency list
+    fn update_access_pattern(&mut self, key: &K) {
+        let class = self.class_of(key);
+        let recent = self.classes.entry(class).or_default();
+        if let Some(pos) = recent.iter().position(|k| k == key) {
+            recent.remove(pos);
+        }
+        recent.push_front(key.clone());
+        recent.truncate(self.class_history);
+    }
+
+    /// Forgets every class
+    fn reset(&mut self) {
+        self.classes.clear();
+    }
+}
+
+impl BenchmarkablePrefetch<i32> for ModularPrefetch<i32> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Modular }
+}
+impl BenchmarkablePrefetch<i64> for ModularPrefetch<i64> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Modular }
+}
+impl BenchmarkablePrefetch<usize> for ModularPrefetch<usize> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Modular }
+}
+impl BenchmarkablePrefetch<u32> for ModularPrefetch<u32> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Modular }
+}
+impl BenchmarkablePrefetch<u64> for ModularPrefetch<u64> {
+    fn prefetch_type(&self) -> PrefetchType { PrefetchType::Modular }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modular_predicts_same_class() {
+        let mut strategy = ModularPrefetch::<i32>::new(4);
+        // Keys 0, 4, 8 of class 0 are co-accessed, interleaved with other classes
+        for key in [0, 1, 4, 7, 8, 2] {
+            strategy.update_access_pattern(&key);
+        }
+
+        let mut predictions = strategy.predict_next(&4);
+        predictions.sort();
+        assert_eq!(predictions, vec![0, 8]);
+        assert_eq!(strategy.predict_next(&12), vec![8, 4, 0]);
+        // Negative keys fall into the same non-negative classes
+        assert_eq!(strategy.predict_next(&-3), vec![1]);
+    }
+
+    #[test]
+    fn test_modular_class_history_is_bounded() {
+        let mut strategy = ModularPrefetch::<usize>::with_config(2, 2, 5);
+        for key in [0, 2, 4, 6] {
+            strategy.update_access_pattern(&key);
+        }
+        assert_eq!(strategy.predict_next(&100), vec![6, 4]);
+
+        strategy.reset();
+        assert!(strategy.predict_next(&6).is_empty());
+    }
+}
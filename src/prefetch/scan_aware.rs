@@ -0,0 +1,126 @@
+use crate::PrefetchStrategy;
+use super::NumericKey;
+
+/// Scan-suppressing wrapper around another prefetch strategy.
+///
+/// Tracks the current run of consecutive keys (each one above the last).
+/// Once a run grows longer than the threshold it is treated as a scan:
+/// every key of a scan is new, so prefetching ahead of it only pollutes the
+/// cache, and predictions are withheld until the run breaks. Shorter runs
+/// and non-sequential access get the wrapped strategy's predictions as is.
+#[derive(Debug, Clone)]
+pub struct ScanAwarePrefetch<K, P>
+where
+    K: NumericKey,
+    P: PrefetchStrategy<K>,
+{
+    /// Strategy whose predictions are passed through outside scans
+    inner: P,
+    /// Longest sequential run not treated as a scan
+    scan_threshold: usize,
+    /// Most recently accessed key
+    last_key: Option<K>,
+    /// Length of the sequential run ending at `last_key`
+    run_len: usize,
+}
+
+impl<K, P> ScanAwarePrefetch<K, P>
+where
+    K: NumericKey,
+    P: PrefetchStrategy<K>,
+{
+    /// Wraps `inner`, treating runs longer than 32 keys as scans
+    pub fn new(inner: P) -> Self {
+        Self::with_threshold(inner, 32)
+    }
+
+    /// Wraps `inner`, treating runs longer than `scan_threshold` keys as scans
+    ///
+    /// A threshold below 1 is clamped to 1.
+    pub fn with_threshold(inner: P, scan_threshold: usize) -> Self {
+        Self {
+            inner,
+            scan_threshold: scan_threshold.max(1),
+            last_key: None,
+            run_len: 0,
+        }
+    }
+
+    /// Whether the current run is long enough to be a scan
+    pub fn is_scanning(&self) -> bool {
+        self.run_len > self.scan_threshold
+    }
+
+    /// Length of the current sequential run
+    pub fn run_len(&self) -> usize {
+        self.run_len
+    }
+
+    /// Access the wrapped strategy
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+impl<K, P> PrefetchStrategy<K> for ScanAwarePrefetch<K, P>
+where
+    K: NumericKey,
+    P: PrefetchStrategy<K>,
+{
+    /// Withholds predictions during a scan, defers to the wrapped strategy otherwise
+    fn predict_next(&mut self, accessed_key: &K) -> Vec<K> {
+        if self.is_scanning() {
+            return Vec::new();
+        }
+        self.inner.predict_next(accessed_key)
+    }
+
+    /// Extends or restarts the sequential run; the wrapped strategy keeps
+    /// learning during scans
+    fn update_access_pattern(&mut self, key: &K) {
+        let extends_run = self
+            .last_key
+            .as_ref()
+            .is_some_and(|last| key.to_i64() == last.to_i64() + 1);
+        self.run_len = if extends_run { self.run_len + 1 } else { 1 };
+        self.last_key = Some(key.clone());
+        self.inner.update_access_pattern(key);
+    }
+
+    /// Forgets the current run and resets the wrapped strategy
+    fn reset(&mut self) {
+        self.last_key = None;
+        self.run_len = 0;
+        self.inner.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prefetch::SequentialPrefetch;
+
+    #[test]
+    fn test_scan_aware_suppresses_long_runs_only() {
+        let mut strategy = ScanAwarePrefetch::with_threshold(SequentialPrefetch::<i32>::new(), 8);
+        for key in 0..20 {
+            strategy.update_access_pattern(&key);
+        }
+        assert!(strategy.is_scanning());
+        assert!(strategy.predict_next(&19).is_empty());
+
+        // A jump ends the scan; a short burst gets the wrapped predictions
+        let mut bare = SequentialPrefetch::<i32>::new();
+        for key in (0..20).chain(1000..1005) {
+            bare.update_access_pattern(&key);
+        }
+        for key in 1000..1005 {
+            strategy.update_access_pattern(&key);
+        }
+        assert!(!strategy.is_scanning());
+        assert_eq!(strategy.run_len(), 5);
+        let predictions = strategy.predict_next(&1004);
+        assert!(!predictions.is_empty());
+        assert_eq!(predictions, bare.predict_next(&1004));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use crate::PrefetchStrategy;
 use super::{BenchmarkablePrefetch, PrefetchType};
 
@@ -27,8 +29,19 @@ where
     confidence: f64,
     /// Number of consecutive successful stride predictions
     consecutive_hits: usize,
+    /// Whether each recent stride matched the current one, oldest first
+    observations: VecDeque<bool>,
+    /// Number of stride observations confidence is computed over
+    window: usize,
+    /// Consecutive confirmations a detected stride needs before predicting
+    min_consecutive_hits: usize,
 }
 
+/// Confidence given to a stride seen once, before any confirmation
+const TENTATIVE_CONFIDENCE: f64 = 0.3;
+/// Confidence needed before any predictions are made
+const PREDICTION_THRESHOLD: f64 = 0.5;
+
 impl<K> SequentialPrefetch<K>
 where
     K: Clone,
@@ -56,8 +69,28 @@ where
             max_predictions,
             confidence: min_confidence,
             consecutive_hits: 0,
+            observations: VecDeque::new(),
+            window: 10,
+            min_consecutive_hits: 2,
         }
     }
+
+    /// Sets how many recent stride observations confidence is measured over
+    ///
+    /// Confidence is the fraction of those observations matching the current
+    /// stride, so a short window reacts quickly to pattern changes. Values
+    /// below 1 are clamped.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Sets how many consecutive confirmations a detected stride needs
+    /// before it is used for predictions
+    pub fn with_min_consecutive_hits(mut self, hits: usize) -> Self {
+        self.min_consecutive_hits = hits;
+        self
+    }
     
     /// Returns current stride if detected
     pub fn current_stride(&self) -> Option<i64> {
@@ -68,6 +101,60 @@ where
     pub fn confidence(&self) -> f64 {
         self.confidence
     }
+
+    /// Whether confidence and the confirmation streak allow predicting
+    ///
+    /// Before any stride is detected the default forward stride is used on
+    /// confidence alone.
+    fn ready_to_predict(&self) -> bool {
+        self.confidence >= PREDICTION_THRESHOLD
+            && (self.stride.is_none() || self.consecutive_hits >= self.min_consecutive_hits)
+    }
+
+    /// Records one observed stride and refreshes confidence
+    ///
+    /// `usable` is false for strides the key type can't predict with; they
+    /// count as mismatches and never replace the current stride.
+    fn observe_stride(&mut self, new_stride: i64, usable: bool) {
+        match self.stride {
+            Some(current_stride) => {
+                let matched = usable && new_stride == current_stride;
+                if matched {
+                    // Stride confirmed
+                    self.consecutive_hits += 1;
+                } else {
+                    // Stride changed - start a new streak
+                    self.consecutive_hits = 0;
+                    if usable {
+                        self.stride = Some(new_stride);
+                    }
+                }
+
+                self.observations.push_back(matched);
+                while self.observations.len() > self.window {
+                    self.observations.pop_front();
+                }
+                let matches = self.observations.iter().filter(|&&m| m).count();
+                self.confidence = matches as f64 / self.observations.len() as f64;
+            },
+            None => {
+                if usable {
+                    // First stride detected
+                    self.stride = Some(new_stride);
+                    self.confidence = TENTATIVE_CONFIDENCE;
+                }
+            }
+        }
+    }
+
+    /// Clears detected pattern state back to the initial confidence
+    fn reset_state(&mut self) {
+        self.last_key = None;
+        self.stride = None;
+        self.confidence = PREDICTION_THRESHOLD;
+        self.consecutive_hits = 0;
+        self.observations.clear();
+    }
 }
 
 impl<K> Default for SequentialPrefetch<K>
@@ -84,7 +171,7 @@ impl PrefetchStrategy<i32> for SequentialPrefetch<i32> {
     /// Predicts next keys based on detected stride pattern
     fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
         // If we don't have enough confidence, don't predict
-        if self.confidence < 0.5 {
+        if !self.ready_to_predict() {
             return Vec::new();
         }
         
@@ -108,26 +195,7 @@ impl PrefetchStrategy<i32> for SequentialPrefetch<i32> {
     fn update_access_pattern(&mut self, key: &i32) {
         if let Some(last_key) = self.last_key {
             let new_stride = (*key as i64) - (last_key as i64);
-            
-            match self.stride {
-                Some(current_stride) => {
-                    if new_stride == current_stride {
-                        // Stride confirmed - increase confidence
-                        self.consecutive_hits += 1;
-                        self.confidence = (self.confidence + 0.1).min(1.0);
-                    } else {
-                        // Stride changed - decrease confidence and update
-                        self.consecutive_hits = 0;
-                        self.confidence = (self.confidence - 0.2).max(0.0);
-                        self.stride = Some(new_stride);
-                    }
-                },
-                None => {
-                    // First stride detected
-                    self.stride = Some(new_stride);
-                    self.confidence = 0.3; // Start with moderate confidence
-                }
-            }
+            self.observe_stride(new_stride, true);
         }
         
         self.last_key = Some(*key);
@@ -135,17 +203,14 @@ impl PrefetchStrategy<i32> for SequentialPrefetch<i32> {
     
     /// Resets the strategy's internal state
     fn reset(&mut self) {
-        self.last_key = None;
-        self.stride = None;
-        self.confidence = 0.5;
-        self.consecutive_hits = 0;
+        self.reset_state();
     }
 }
 
 // Implementation for 64-bit integers
 impl PrefetchStrategy<i64> for SequentialPrefetch<i64> {
     fn predict_next(&mut self, accessed_key: &i64) -> Vec<i64> {
-        if self.confidence < 0.5 {
+        if !self.ready_to_predict() {
             return Vec::new();
         }
         
@@ -166,99 +231,63 @@ impl PrefetchStrategy<i64> for SequentialPrefetch<i64> {
     
     fn update_access_pattern(&mut self, key: &i64) {
         if let Some(last_key) = self.last_key {
-            let new_stride = *key - last_key;
-            
-            match self.stride {
-                Some(current_stride) => {
-                    if new_stride == current_stride {
-                        self.consecutive_hits += 1;
-                        self.confidence = (self.confidence + 0.1).min(1.0);
-                    } else {
-                        self.consecutive_hits = 0;
-                        self.confidence = (self.confidence - 0.2).max(0.0);
-                        self.stride = Some(new_stride);
-                    }
-                },
-                None => {
-                    self.stride = Some(new_stride);
-                    self.confidence = 0.3;
-                }
-            }
+            self.observe_stride(*key - last_key, true);
         }
         
         self.last_key = Some(*key);
     }
     
     fn reset(&mut self) {
-        self.last_key = None;
-        self.stride = None;
-        self.confidence = 0.5;
-        self.consecutive_hits = 0;
+        self.reset_state();
     }
 }
 
-// Implementation for usize (common for array indices)
-impl PrefetchStrategy<usize> for SequentialPrefetch<usize> {
-    fn predict_next(&mut self, accessed_key: &usize) -> Vec<usize> {
-        if self.confidence < 0.5 {
-            return Vec::new();
-        }
-        
-        let stride = self.stride.unwrap_or(1).max(1) as usize; // Ensure positive stride
-        let mut predictions = Vec::with_capacity(self.max_predictions);
-        
-        for i in 1..=self.max_predictions {
-            if predictions.len() >= self.prefetch_distance {
-                break;
-            }
-            
-            // Prevent overflow
-            if let Some(next_key) = accessed_key.checked_add(stride * i) {
-                predictions.push(next_key);
-            }
-        }
-        
-        predictions
-    }
-    
-    fn update_access_pattern(&mut self, key: &usize) {
-        if let Some(last_key) = self.last_key {
-            // Handle potential underflow by using signed arithmetic
-            let new_stride = (*key as i64) - (last_key as i64);
-            
-            match self.stride {
-                Some(current_stride) => {
-                    if new_stride == current_stride && new_stride > 0 {
-                        self.consecutive_hits += 1;
-                        self.confidence = (self.confidence + 0.1).min(1.0);
-                    } else {
-                        self.consecutive_hits = 0;
-                        self.confidence = (self.confidence - 0.2).max(0.0);
-                        if new_stride > 0 {
-                            self.stride = Some(new_stride);
-                        }
+// Implementation for unsigned keys (usize for array indices, u32/u64 for
+// row ids and offsets), which only follow forward strides
+macro_rules! impl_unsigned_key {
+    ($($key:ty),*) => {$(
+        impl PrefetchStrategy<$key> for SequentialPrefetch<$key> {
+            fn predict_next(&mut self, accessed_key: &$key) -> Vec<$key> {
+                if !self.ready_to_predict() {
+                    return Vec::new();
+                }
+
+                let stride = self.stride.unwrap_or(1).max(1) as $key; // Ensure positive stride
+                let mut predictions = Vec::with_capacity(self.max_predictions);
+
+                for i in 1..=self.max_predictions {
+                    if predictions.len() >= self.prefetch_distance {
+                        break;
                     }
-                },
-                None => {
-                    if new_stride > 0 {
-                        self.stride = Some(new_stride);
-                        self.confidence = 0.3;
+
+                    // Prevent overflow
+                    if let Some(next_key) = stride.checked_mul(i as $key).and_then(|step| accessed_key.checked_add(step)) {
+                        predictions.push(next_key);
                     }
                 }
+
+                predictions
+            }
+
+            fn update_access_pattern(&mut self, key: &$key) {
+                if let Some(last_key) = self.last_key {
+                    // Handle potential underflow by using signed arithmetic
+                    let new_stride = (*key as i64) - (last_key as i64);
+                    self.observe_stride(new_stride, new_stride > 0);
+                }
+
+                self.last_key = Some(*key);
+            }
+
+            fn reset(&mut self) {
+                self.reset_state();
             }
         }
-        
-        self.last_key = Some(*key);
-    }
-    
-    fn reset(&mut self) {
-        self.last_key = None;
-        self.stride = None;
-        self.confidence = 0.5;
-        self.consecutive_hits = 0;
-    }
+    )*};
 }
 
+impl_unsigned_key!(usize, u32, u64);
+
 impl BenchmarkablePrefetch<i32> for SequentialPrefetch<i32> {
     fn prefetch_type(&self) -> PrefetchType {
         PrefetchType::Sequential
@@ -277,6 +306,18 @@ impl BenchmarkablePrefetch<usize> for SequentialPrefetch<usize> {
     }
 }
 
+impl BenchmarkablePrefetch<u32> for SequentialPrefetch<u32> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Sequential
+    }
+}
+
+impl BenchmarkablePrefetch<u64> for SequentialPrefetch<u64> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Sequential
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,4 +406,34 @@ mod tests {
             assert_eq!(predictions[0], 2); // 4 + (-2)*1
         }
     }
+
+    #[test]
+    fn test_sequential_configured_window() {
+        let mut strategy = SequentialPrefetch::<i32>::new()
+            .with_window(4)
+            .with_min_consecutive_hits(3);
+
+        // Stride 5 detected at key 5, then confirmed three times
+        strategy.update_access_pattern(&0);
+        strategy.update_access_pattern(&5);
+        for key in [10, 15] {
+            strategy.update_access_pattern(&key);
+            assert!(strategy.predict_next(&key).is_empty());
+        }
+        strategy.update_access_pattern(&20);
+        assert_eq!(strategy.predict_next(&20), vec![25, 30]);
+        assert_eq!(strategy.confidence(), 1.0);
+
+        // A single break stops predictions straight away
+        strategy.update_access_pattern(&100);
+        assert!(strategy.predict_next(&100).is_empty());
+
+        // Confidence is a ratio over the last 4 strides only
+        for key in [101, 102, 103, 104] {
+            strategy.update_access_pattern(&key);
+        }
+        assert_eq!(strategy.current_stride(), Some(1));
+        assert_eq!(strategy.confidence(), 0.75);
+        assert_eq!(strategy.predict_next(&104), vec![105, 106]);
+    }
 }
@@ -99,6 +99,18 @@ impl Default for StridePrefetch<usize> {
     }
 }
 
+impl Default for StridePrefetch<u32> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for StridePrefetch<u64> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Implement `PrefetchStrategy<i32>` for `StridePrefetch<i32>`
 impl PrefetchStrategy<i32> for StridePrefetch<i32> {
     fn predict_next(&mut self, accessed_key: &i32) -> Vec<i32> {
@@ -252,91 +264,98 @@ impl PrefetchStrategy<i64> for StridePrefetch<i64> {
     }
 }
 
-/// Implement `PrefetchStrategy<usize>` for `StridePrefetch<usize>`
-impl PrefetchStrategy<usize> for StridePrefetch<usize> {
-    fn predict_next(&mut self, accessed_key: &usize) -> Vec<usize> {
-        let mut predictions = Vec::with_capacity(self.max_predictions);
-
-        if let Some(dominant) = self.dominant_stride {
-            if dominant > 0 {
-                if let Some(pattern) = self.stride_patterns.get(&dominant) {
-                    if pattern.confidence >= self.min_confidence {
-                        for i in 1..=self.prefetch_distance {
-                            if predictions.len() >= self.max_predictions {
-                                break;
-                            }
-                            if let Some(next_key) = accessed_key.checked_add((dominant as usize) * i) {
-                                predictions.push(next_key);
+/// Implement `PrefetchStrategy` for `StridePrefetch` over unsigned keys,
+/// which only follow forward strides
+macro_rules! impl_unsigned_key {
+    ($($key:ty),*) => {$(
+        impl PrefetchStrategy<$key> for StridePrefetch<$key> {
+            fn predict_next(&mut self, accessed_key: &$key) -> Vec<$key> {
+                let mut predictions = Vec::with_capacity(self.max_predictions);
+
+                if let Some(dominant) = self.dominant_stride {
+                    if dominant > 0 {
+                        if let Some(pattern) = self.stride_patterns.get(&dominant) {
+                            if pattern.confidence >= self.min_confidence {
+                                for i in 1..=self.prefetch_distance {
+                                    if predictions.len() >= self.max_predictions {
+                                        break;
+                                    }
+                                    if let Some(next_key) = (dominant as $key).checked_mul(i as $key).and_then(|step| accessed_key.checked_add(step)) {
+                                        predictions.push(next_key);
+                                    }
+                                }
                             }
                         }
                     }
                 }
-            }
-        }
 
-        if predictions.len() < self.max_predictions {
-            let mut other_strides: Vec<_> = self
-                .stride_patterns
-                .iter()
-                .filter(|(stride, pattern)| {
-                    **stride > 0
-                        && pattern.confidence >= self.min_confidence
-                        && Some(**stride) != self.dominant_stride
-                })
-                .collect();
+                if predictions.len() < self.max_predictions {
+                    let mut other_strides: Vec<_> = self
+                        .stride_patterns
+                        .iter()
+                        .filter(|(stride, pattern)| {
+                            **stride > 0
+                                && pattern.confidence >= self.min_confidence
+                                && Some(**stride) != self.dominant_stride
+                        })
+                        .collect();
 
-            other_strides.sort_by(|a, b| b.1.confidence.partial_cmp(&a.1.confidence).unwrap());
+                    other_strides.sort_by(|a, b| b.1.confidence.partial_cmp(&a.1.confidence).unwrap());
 
-            for (stride, _) in other_strides.iter().take(2) {
-                if predictions.len() >= self.max_predictions {
-                    break;
-                }
-                if let Some(next_key) = accessed_key.checked_add(**stride as usize) {
-                    if !predictions.contains(&next_key) {
-                        predictions.push(next_key);
+                    for (stride, _) in other_strides.iter().take(2) {
+                        if predictions.len() >= self.max_predictions {
+                            break;
+                        }
+                        if let Some(next_key) = accessed_key.checked_add(**stride as $key) {
+                            if !predictions.contains(&next_key) {
+                                predictions.push(next_key);
+                            }
+                        }
                     }
                 }
-            }
-        }
 
-        predictions
-    }
+                predictions
+            }
 
-    fn update_access_pattern(&mut self, key: &usize) {
-        self.access_history.push(*key);
-        if self.access_history.len() > self.max_history {
-            self.access_history.remove(0);
-        }
-        if self.access_history.len() >= 2 {
-            let current = *key as i64;
-            for i in 1..self.access_history.len() {
-                let prev = self.access_history[self.access_history.len() - 1 - i] as i64;
-                let stride = current - prev;
-                if stride > 0 {
-                    let pattern = self.stride_patterns.entry(stride).or_insert(StridePattern {
-                        confidence: 0.3,
-                        occurrences: 0,
-                    });
-                    pattern.occurrences += 1;
-                    if pattern.occurrences > 2 {
-                        pattern.confidence = (pattern.confidence + 0.05).min(1.0);
+            fn update_access_pattern(&mut self, key: &$key) {
+                self.access_history.push(*key);
+                if self.access_history.len() > self.max_history {
+                    self.access_history.remove(0);
+                }
+                if self.access_history.len() >= 2 {
+                    let current = *key as i64;
+                    for i in 1..self.access_history.len() {
+                        let prev = self.access_history[self.access_history.len() - 1 - i] as i64;
+                        let stride = current - prev;
+                        if stride > 0 {
+                            let pattern = self.stride_patterns.entry(stride).or_insert(StridePattern {
+                                confidence: 0.3,
+                                occurrences: 0,
+                            });
+                            pattern.occurrences += 1;
+                            if pattern.occurrences > 2 {
+                                pattern.confidence = (pattern.confidence + 0.05).min(1.0);
+                            }
+                        }
                     }
                 }
+                self.update_dominant_stride();
+                if self.stride_patterns.len() > 10 {
+                    self.cleanup_patterns();
+                }
             }
-        }
-        self.update_dominant_stride();
-        if self.stride_patterns.len() > 10 {
-            self.cleanup_patterns();
-        }
-    }
 
-    fn reset(&mut self) {
-        self.access_history.clear();
-        self.stride_patterns.clear();
-        self.dominant_stride = None;
-    }
+            fn reset(&mut self) {
+                self.access_history.clear();
+                self.stride_patterns.clear();
+                self.dominant_stride = None;
+            }
+        }
+    )*};
 }
 
+impl_unsigned_key!(usize, u32, u64);
+
 /// Criterion benchmarks integration for i32, i64, usize
 impl BenchmarkablePrefetch<i32> for StridePrefetch<i32> {
     fn prefetch_type(&self) -> PrefetchType {
@@ -353,5 +372,15 @@ impl BenchmarkablePrefetch<usize> for StridePrefetch<usize> {
         PrefetchType::Stride
     }
 }
+impl BenchmarkablePrefetch<u32> for StridePrefetch<u32> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Stride
+    }
+}
+impl BenchmarkablePrefetch<u64> for StridePrefetch<u64> {
+    fn prefetch_type(&self) -> PrefetchType {
+        PrefetchType::Stride
+    }
+}
 
 
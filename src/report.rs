@@ -0,0 +1,149 @@
+//! Side-by-side policy comparison over a recorded access trace
+
+use std::fmt;
+
+use crate::policies::{create_cache_policy, PolicyType};
+
+/// Outcome of replaying a trace against a single policy
+#[derive(Debug, Clone)]
+pub struct PolicyResult {
+    pub policy: PolicyType,
+    /// Fraction of accesses served from the cache, in `[0, 1]`
+    pub hit_ratio: f64,
+    /// Misses that pushed an existing entry out
+    pub evictions: u64,
+    /// Relative memory overhead from the policy's characteristics
+    pub memory_estimate: &'static str,
+}
+
+/// Policies ranked by hit ratio on one trace, best first
+#[derive(Debug, Clone)]
+pub struct ComparisonReport {
+    pub capacity: usize,
+    pub accesses: usize,
+    pub rows: Vec<PolicyResult>,
+    /// Policy of the top row
+    pub winner: PolicyType,
+}
+
+/// Replays `trace` against every policy at `capacity` and ranks the results
+///
+/// Each access is a read-through: a miss inserts the key. Rows are sorted by
+/// hit ratio, ties broken by fewer evictions and then by `PolicyType::all`
+/// order.
+///
+/// # Panics
+/// Panics if capacity is 0
+pub fn compare_policies(capacity: usize, trace: &[i32]) -> ComparisonReport {
+    let mut rows: Vec<PolicyResult> = PolicyType::all()
+        .iter()
+        .map(|&policy| simulate(policy, capacity, trace))
+        .collect();
+    rows.sort_by(|a, b| {
+        b.hit_ratio
+            .total_cmp(&a.hit_ratio)
+            .then(a.evictions.cmp(&b.evictions))
+    });
+
+    ComparisonReport {
+        capacity,
+        accesses: trace.len(),
+        winner: rows[0].policy,
+        rows,
+    }
+}
+
+fn simulate(policy: PolicyType, capacity: usize, trace: &[i32]) -> PolicyResult {
+    let mut cache = create_cache_policy::<i32, i32>(policy, capacity);
+    let mut hits = 0u64;
+    let mut evictions = 0u64;
+    for &key in trace {
+        if cache.get(&key).is_some() {
+            hits += 1;
+            continue;
+        }
+        let before = cache.len();
+        cache.insert(key, key);
+        if cache.len() <= before {
+            evictions += 1;
+        }
+    }
+
+    PolicyResult {
+        policy,
+        hit_ratio: if trace.is_empty() { 0.0 } else { hits as f64 / trace.len() as f64 },
+        evictions,
+        memory_estimate: cache.characteristics().memory_overhead,
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Policy comparison: capacity {}, {} accesses",
+            self.capacity, self.accesses
+        )?;
+        writeln!(
+            f,
+            "{:>4}  {:<6}  {:>9}  {:>9}  Memory",
+            "Rank", "Policy", "Hit ratio", "Evictions"
+        )?;
+        for (rank, row) in self.rows.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:<6}  {:>8.2}%  {:>9}  {}",
+                rank + 1,
+                row.policy.name(),
+                row.hit_ratio * 100.0,
+                row.evictions,
+                row.memory_estimate
+            )?;
+        }
+        write!(f, "Winner: {}", self.winner.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Draws `len` keys from a Zipf(1.0) distribution over `n` keys
+    fn zipf_trace(n: usize, len: usize, seed: u64) -> Vec<i32> {
+        let weights: Vec<f64> = (1..=n).map(|rank| 1.0 / rank as f64).collect();
+        let total: f64 = weights.iter().sum();
+        let mut cumulative = Vec::with_capacity(n);
+        let mut acc = 0.0;
+        for w in weights {
+            acc += w / total;
+            cumulative.push(acc);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..len)
+            .map(|_| {
+                let u: f64 = rng.gen_range(0.0..1.0);
+                cumulative.partition_point(|&c| c < u).min(n - 1) as i32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_policies_ranks_frequency_policies_on_zipf() {
+        let trace = zipf_trace(500, 4_000, 7);
+        let report = compare_policies(25, &trace);
+
+        let rank = |policy| report.rows.iter().position(|r| r.policy == policy).unwrap();
+        assert!(rank(PolicyType::Lfu) < rank(PolicyType::Fifo));
+        assert!(rank(PolicyType::Arc) < rank(PolicyType::Fifo));
+        assert_eq!(report.winner, report.rows[0].policy);
+        assert_eq!(report.rows.len(), PolicyType::all().len());
+        assert!(report.rows.windows(2).all(|w| w[0].hit_ratio >= w[1].hit_ratio));
+
+        let table = report.to_string();
+        assert!(table.contains("Winner: "));
+        assert_eq!(table.lines().count(), PolicyType::all().len() + 3);
+    }
+}
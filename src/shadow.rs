@@ -0,0 +1,178 @@
+use std::marker::PhantomData;
+
+use crate::CachePolicy;
+
+/// Result of a [`ShadowKeysCache::get`] lookup
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShadowLookup<'a, V> {
+    /// Key is present with a value
+    Hit(&'a V),
+    /// Key is present but its value was dropped and must be reloaded
+    Reload,
+    /// Key is not in the cache
+    Miss,
+}
+
+/// Cache wrapper that can drop values while keeping the key set
+///
+/// Values are stored as `Option<V>` inside any policy. [`clear_values`](Self::clear_values)
+/// turns every slot into `None` to free memory, but keys stay resident in
+/// their current eviction order. A later lookup reports [`ShadowLookup::Reload`]
+/// so the caller knows the key exists and the value has to be fetched again.
+/// Useful for negative caches and membership tracking.
+pub struct ShadowKeysCache<K, V, C>
+where
+    C: CachePolicy<K, Option<V>>,
+{
+    inner: C,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> ShadowKeysCache<K, V, C>
+where
+    C: CachePolicy<K, Option<V>>,
+{
+    /// Wrap a policy storing `Option<V>` values
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Look up a key, updating the policy's usage state like a normal `get`
+    pub fn get(&mut self, key: &K) -> ShadowLookup<'_, V> {
+        match self.inner.get(key) {
+            Some(Some(value)) => ShadowLookup::Hit(value),
+            Some(None) => ShadowLookup::Reload,
+            None => ShadowLookup::Miss,
+        }
+    }
+
+    /// Insert or reload a value for a key
+    pub fn insert(&mut self, key: K, value: V) {
+        self.inner.insert(key, Some(value));
+    }
+
+    /// Remove a key, returning its value if one was still held
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key).flatten()
+    }
+
+    /// Check whether a key is tracked, whether or not its value is loaded
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Drop every stored value while keeping keys and eviction order
+    pub fn clear_values(&mut self) {
+        self.inner.for_each_value_mut(&mut |_, value| *value = None);
+    }
+
+    /// Remove all keys and values
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Number of tracked keys
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if no keys are tracked
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of tracked keys
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Unwrap into the underlying policy
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{
+        ArcCache, CarCache, ClockCache, FifoCache, LfuCache, LirsCache, LruCache, MruCache,
+        RandomCache, S3FifoCache, SieveCache, SlruCache, TwoQCache,
+    };
+
+    #[test]
+    fn test_shadow_clear_values_keeps_keys() {
+        let mut cache = ShadowKeysCache::new(LruCache::new(3));
+        cache.insert(1, "one".to_string());
+        cache.insert(2, "two".to_string());
+        cache.insert(3, "three".to_string());
+
+        cache.clear_values();
+
+        assert_eq!(cache.len(), 3);
+        for key in 1..=3 {
+            assert!(cache.contains_key(&key));
+        }
+        assert_eq!(cache.get(&2), ShadowLookup::Reload);
+        assert_eq!(cache.get(&9), ShadowLookup::Miss);
+
+        cache.insert(2, "two again".to_string());
+        assert_eq!(cache.get(&2), ShadowLookup::Hit(&"two again".to_string()));
+    }
+
+    #[test]
+    fn test_shadow_clear_values_preserves_eviction_order() {
+        let mut cache = ShadowKeysCache::new(LruCache::new(3));
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(3, 3);
+        cache.get(&1);
+
+        cache.clear_values();
+        // Key 2 is still least recently used
+        cache.insert(4, 4);
+        assert!(!cache.contains_key(&2));
+        assert!(cache.contains_key(&1));
+        assert!(cache.contains_key(&3));
+    }
+
+    fn assert_values_cleared<C: CachePolicy<i32, Option<i32>>>(inner: C) {
+        let mut cache = ShadowKeysCache::new(inner);
+        for i in 0..8 {
+            cache.insert(i, i);
+        }
+        let before = cache.len();
+        cache.clear_values();
+        assert_eq!(cache.len(), before);
+        for i in 0..8 {
+            if cache.contains_key(&i) {
+                assert_eq!(cache.get(&i), ShadowLookup::Reload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shadow_clear_values_every_policy() {
+        assert_values_cleared(LruCache::new(8));
+        assert_values_cleared(MruCache::new(8));
+        assert_values_cleared(FifoCache::new(8));
+        assert_values_cleared(LfuCache::new(8));
+        assert_values_cleared(RandomCache::new(8));
+        assert_values_cleared(ArcCache::new(8));
+        assert_values_cleared(ClockCache::new(8));
+        assert_values_cleared(TwoQCache::new(8));
+        assert_values_cleared(SlruCache::new(8));
+        assert_values_cleared(CarCache::new(8));
+        assert_values_cleared(SieveCache::new(8));
+        assert_values_cleared(LirsCache::new(8));
+        assert_values_cleared(S3FifoCache::new(8));
+    }
+}
@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::CachePolicy;
+
+/// Cache wrapper storing values behind `Arc` for zero-copy reads
+///
+/// The wrapped policy holds `Arc<V>`, so a hit hands out a reference-count
+/// bump instead of a deep clone of `V`. Handy for large values that are
+/// read far more often than they are written.
+pub struct SharedValueCache<K, V, C>
+where
+    C: CachePolicy<K, Arc<V>>,
+{
+    inner: C,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> SharedValueCache<K, V, C>
+where
+    C: CachePolicy<K, Arc<V>>,
+{
+    /// Wrap a policy storing `Arc<V>` values
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Retrieve a shared handle to the value, updating the policy's usage state
+    pub fn get_arc(&mut self, key: &K) -> Option<Arc<V>> {
+        self.inner.get(key).cloned()
+    }
+
+    /// Insert a value, wrapping it in an `Arc`
+    pub fn insert(&mut self, key: K, value: V) {
+        self.inner.insert(key, Arc::new(value));
+    }
+
+    /// Insert an already shared value without re-allocating it
+    pub fn insert_arc(&mut self, key: K, value: Arc<V>) {
+        self.inner.insert(key, value);
+    }
+
+    /// Remove a key, returning its shared value if present
+    pub fn remove(&mut self, key: &K) -> Option<Arc<V>> {
+        self.inner.remove(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_shared_value_get_arc_same_allocation() {
+        let mut cache = SharedValueCache::new(LruCache::new(4));
+        cache.insert("blob", vec![7u8; 1 << 20]);
+
+        let first = cache.get_arc(&"blob").unwrap();
+        let second = cache.get_arc(&"blob").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.len(), 1 << 20);
+
+        // Handles outlive eviction of the entry
+        cache.remove(&"blob");
+        assert_eq!(second[0], 7);
+        assert!(cache.get_arc(&"blob").is_none());
+    }
+
+    #[test]
+    fn test_shared_value_insert_arc() {
+        let mut cache = SharedValueCache::new(LruCache::new(2));
+        let value = Arc::new(String::from("shared"));
+        cache.insert_arc(1, Arc::clone(&value));
+        assert!(Arc::ptr_eq(&cache.get_arc(&1).unwrap(), &value));
+    }
+}
@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+
+use crate::CachePolicy;
+
+/// Cache wrapper trimming toward a soft entry limit a little at a time
+///
+/// The wrapped policy's capacity stays the hard ceiling, enforced on every
+/// insert as usual. Above the soft limit, each lookup first evicts one
+/// entry, picked by the policy, so a burst of inserts is paid back
+/// gradually instead of in one go.
+pub struct SoftLimitCache<K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    inner: C,
+    soft_limit: Option<usize>,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> SoftLimitCache<K, V, C>
+where
+    C: CachePolicy<K, V>,
+{
+    /// Wrap a policy; without a soft limit it behaves exactly like `inner`
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            soft_limit: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets the number of entries lookups trim toward
+    ///
+    /// Clamped to `1..=capacity`; a limit at the capacity never trims.
+    pub fn set_soft_limit(&mut self, soft: usize) {
+        self.soft_limit = Some(soft.clamp(1, self.inner.capacity()));
+    }
+
+    /// The soft limit, if one is set
+    pub fn soft_limit(&self) -> Option<usize> {
+        self.soft_limit
+    }
+
+    /// Look up a key, first evicting one entry if above the soft limit
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.trim_one();
+        self.inner.get(key)
+    }
+
+    /// Insert or update a key-value pair, evicting only past the capacity
+    pub fn insert(&mut self, key: K, value: V) {
+        self.inner.insert(key, value);
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Hard maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Evicts the policy's next victim if the cache is above the soft limit
+    fn trim_one(&mut self) {
+        let capacity = self.inner.capacity();
+        let len = self.inner.len();
+        if let Some(soft) = self.soft_limit
+            && len > soft.min(capacity)
+        {
+            // The soft limit is at least 1, so len - 1 is a valid capacity
+            self.inner.resize(len - 1);
+            self.inner.resize(capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_soft_limit_trims_gradually() {
+        let mut cache = SoftLimitCache::new(LruCache::new(10));
+        cache.set_soft_limit(4);
+        for key in 0..15 {
+            cache.insert(key, key);
+            assert!(cache.len() <= 10);
+        }
+        assert_eq!(cache.len(), 10);
+
+        // One eviction per lookup, least recently used first
+        for (step, expected) in (4..10).rev().enumerate() {
+            assert_eq!(cache.get(&14), Some(&14));
+            assert_eq!(cache.len(), expected);
+            assert!(!cache.inner().contains_key(&(5 + step as i32)));
+        }
+        // At the soft limit lookups stop trimming
+        cache.get(&14);
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.capacity(), 10);
+
+        // Inserts may overshoot the soft limit up to the hard capacity again
+        for key in 20..30 {
+            cache.insert(key, key);
+        }
+        assert_eq!(cache.len(), 10);
+    }
+}
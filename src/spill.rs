@@ -0,0 +1,292 @@
+//! Spilling oversized values out of memory
+
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{CachePolicy, Weigher};
+
+/// Where an entry's value lives, as stored in the wrapped policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slot<V> {
+    /// Kept in memory
+    Inline(V),
+    /// Handed to the spill store under this id
+    Spilled(u64),
+}
+
+/// Out-of-memory storage for spilled values
+pub trait SpillStore<V> {
+    /// Stores a value, returning the id to load it back with
+    fn put(&mut self, value: &V) -> io::Result<u64>;
+
+    /// Loads a stored value
+    fn load(&self, id: u64) -> io::Result<V>;
+
+    /// Forgets a stored value; unknown ids are ignored
+    fn discard(&mut self, id: u64);
+}
+
+/// Spill store keeping one file per value in a private directory
+///
+/// Values go through the `encode`/`decode` pair given at creation. The
+/// directory and everything in it are removed on drop.
+pub struct FileSpillStore<V> {
+    dir: PathBuf,
+    next_id: u64,
+    encode: fn(&V) -> Vec<u8>,
+    decode: fn(&[u8]) -> V,
+}
+
+impl<V> FileSpillStore<V> {
+    /// Creates a store in a fresh directory under the system temp dir
+    pub fn in_temp_dir(encode: fn(&V) -> Vec<u8>, decode: fn(&[u8]) -> V) -> io::Result<Self> {
+        static STORES: AtomicU64 = AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "fulgurance-spill-{}-{}",
+            std::process::id(),
+            STORES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            next_id: 0,
+            encode,
+            decode,
+        })
+    }
+
+    /// Directory holding the spilled values
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.bin", id))
+    }
+}
+
+impl<V> SpillStore<V> for FileSpillStore<V> {
+    fn put(&mut self, value: &V) -> io::Result<u64> {
+        let id = self.next_id;
+        fs::write(self.path(id), (self.encode)(value))?;
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    fn load(&self, id: u64) -> io::Result<V> {
+        fs::read(self.path(id)).map(|bytes| (self.decode)(&bytes))
+    }
+
+    fn discard(&mut self, id: u64) {
+        let _ = fs::remove_file(self.path(id));
+    }
+}
+
+impl<V> Drop for FileSpillStore<V> {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Cache wrapper moving values heavier than a threshold to a spill store
+///
+/// Values the weigher rates at or below `threshold` are kept in the wrapped
+/// policy as usual; heavier ones are written to the store and the policy
+/// only holds their id, so capacity counts them as one small entry each.
+/// Spilled values read back on `get` at the cost of a store round trip.
+///
+/// When the policy evicts a spilled entry its stored value is discarded on
+/// the next insert that evicts, by checking every spilled key, so this
+/// suits caches where large values are the exception.
+pub struct SpillCache<K, V, C>
+where
+    C: CachePolicy<K, Slot<V>>,
+{
+    inner: C,
+    store: Box<dyn SpillStore<V>>,
+    weigher: Weigher<K, V>,
+    threshold: usize,
+    /// Spilled keys and their store ids
+    spilled: HashMap<K, u64>,
+}
+
+impl<K, V, C> SpillCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    C: CachePolicy<K, Slot<V>>,
+{
+    /// Wrap a policy, spilling values `weigher` rates above `threshold` to `store`
+    pub fn new(inner: C, store: Box<dyn SpillStore<V>>, weigher: Weigher<K, V>, threshold: usize) -> Self {
+        Self {
+            inner,
+            store,
+            weigher,
+            threshold,
+            spilled: HashMap::new(),
+        }
+    }
+
+    /// Retrieve a value, reading it back from the store if it was spilled
+    ///
+    /// A spilled value that can't be read back is dropped and reported as
+    /// a miss.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let id = match self.inner.get(key)? {
+            Slot::Inline(value) => return Some(value.clone()),
+            Slot::Spilled(id) => *id,
+        };
+        match self.store.load(id) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.remove(key);
+                None
+            }
+        }
+    }
+
+    /// Insert a value, spilling it if it is over the threshold
+    ///
+    /// If the store fails to take a value, it is kept in memory instead and
+    /// the store's error is returned.
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<()> {
+        if let Some(id) = self.spilled.remove(&key) {
+            self.store.discard(id);
+        }
+        let mut result = Ok(());
+        let slot = if (self.weigher)(&key, &value) > self.threshold {
+            match self.store.put(&value) {
+                Ok(id) => {
+                    self.spilled.insert(key.clone(), id);
+                    Slot::Spilled(id)
+                }
+                Err(err) => {
+                    result = Err(err);
+                    Slot::Inline(value)
+                }
+            }
+        } else {
+            Slot::Inline(value)
+        };
+
+        let fresh = !self.inner.contains_key(&key);
+        let before = self.inner.len();
+        self.inner.insert(key, slot);
+        if fresh && self.inner.len() <= before {
+            self.discard_evicted();
+        }
+        result
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.inner.remove(key)?;
+        match slot {
+            Slot::Inline(value) => Some(value),
+            Slot::Spilled(id) => {
+                self.spilled.remove(key);
+                let value = self.store.load(id).ok();
+                self.store.discard(id);
+                value
+            }
+        }
+    }
+
+    /// Number of entries whose value is in the spill store
+    pub fn spilled_len(&self) -> usize {
+        self.spilled.len()
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Remove all entries, discarding every spilled value
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        for (_, id) in self.spilled.drain() {
+            self.store.discard(id);
+        }
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Discards the stored values of spilled keys the policy has evicted
+    fn discard_evicted(&mut self) {
+        let inner = &self.inner;
+        let store = &mut self.store;
+        self.spilled.retain(|key, id| {
+            let resident = inner.contains_key(key);
+            if !resident {
+                store.discard(*id);
+            }
+            resident
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    fn file_count(dir: &Path) -> usize {
+        fs::read_dir(dir).unwrap().count()
+    }
+
+    #[test]
+    fn test_spill_cache_moves_large_values_to_files() {
+        let store = FileSpillStore::in_temp_dir(|v: &Vec<u8>| v.clone(), |b| b.to_vec()).unwrap();
+        let dir = store.dir().to_path_buf();
+        let mut cache = SpillCache::new(
+            LruCache::new(4),
+            Box::new(store),
+            Box::new(|_: &i32, v: &Vec<u8>| v.len()),
+            1024,
+        );
+
+        cache.insert(1, vec![1; 16]).unwrap();
+        cache.insert(2, vec![2; 64 * 1024]).unwrap();
+        cache.insert(3, vec![3; 32]).unwrap();
+        cache.insert(4, vec![4; 128 * 1024]).unwrap();
+        assert_eq!(cache.spilled_len(), 2);
+        assert_eq!(file_count(&dir), 2);
+
+        assert_eq!(cache.get(&1), Some(vec![1; 16]));
+        assert_eq!(cache.get(&2), Some(vec![2; 64 * 1024]));
+        assert_eq!(cache.get(&4), Some(vec![4; 128 * 1024]));
+
+        // Inline 3 then 1 are evicted first, leaving both files in place
+        cache.insert(5, vec![5; 8]).unwrap();
+        cache.insert(6, vec![6; 8]).unwrap();
+        assert_eq!(file_count(&dir), 2);
+        // Evicting spilled 2 deletes its file
+        cache.insert(7, vec![7; 8]).unwrap();
+        assert!(!cache.inner().contains_key(&2));
+        assert_eq!(cache.spilled_len(), 1);
+        assert_eq!(file_count(&dir), 1);
+
+        assert_eq!(cache.remove(&4), Some(vec![4; 128 * 1024]));
+        assert_eq!(file_count(&dir), 0);
+        drop(cache);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,229 @@
+//! Compact binary format for capturing and sharing cache workloads
+//!
+//! A trace starts with the magic bytes `FGTR` and a version byte. Each
+//! operation follows as one tag byte and the key's delta from the previous
+//! key, zigzag- and LEB128-encoded. Integer keys with locality therefore
+//! take one or two bytes per operation.
+
+use std::io::{self, Read, Write};
+
+/// Magic bytes opening every trace
+pub const TRACE_MAGIC: [u8; 4] = *b"FGTR";
+
+/// Format version written by [`TraceWriter`]
+pub const TRACE_VERSION: u8 = 1;
+
+/// A single recorded cache operation on an integer key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceOp {
+    Get(u64),
+    Insert(u64),
+    Remove(u64),
+}
+
+impl TraceOp {
+    /// Key the operation targets
+    pub fn key(&self) -> u64 {
+        match *self {
+            TraceOp::Get(key) | TraceOp::Insert(key) | TraceOp::Remove(key) => key,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            TraceOp::Get(_) => 0,
+            TraceOp::Insert(_) => 1,
+            TraceOp::Remove(_) => 2,
+        }
+    }
+
+    fn from_tag(tag: u8, key: u64) -> io::Result<Self> {
+        match tag {
+            0 => Ok(TraceOp::Get(key)),
+            1 => Ok(TraceOp::Insert(key)),
+            2 => Ok(TraceOp::Remove(key)),
+            _ => Err(invalid_data(format!("unknown trace op tag {}", tag))),
+        }
+    }
+}
+
+/// Streams operations into a trace
+pub struct TraceWriter<W: Write> {
+    inner: W,
+    last_key: u64,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Writes the trace header and returns a writer ready for operations
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        inner.write_all(&TRACE_MAGIC)?;
+        inner.write_all(&[TRACE_VERSION])?;
+        Ok(Self { inner, last_key: 0 })
+    }
+
+    /// Appends one operation
+    pub fn write_op(&mut self, op: TraceOp) -> io::Result<()> {
+        let key = op.key();
+        let delta = key.wrapping_sub(self.last_key) as i64;
+        self.last_key = key;
+
+        let mut buf = [0u8; 11];
+        buf[0] = op.tag();
+        let len = encode_varint(zigzag_encode(delta), &mut buf[1..]);
+        self.inner.write_all(&buf[..=len])
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads operations back from a trace
+///
+/// Malformed input surfaces as an `io::Error` of kind `InvalidData`, or
+/// `UnexpectedEof` when the trace is cut off mid-operation.
+pub struct TraceReader<R: Read> {
+    inner: R,
+    last_key: u64,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Reads and validates the trace header
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        inner.read_exact(&mut header)?;
+        if header[..4] != TRACE_MAGIC {
+            return Err(invalid_data("missing trace magic bytes".to_string()));
+        }
+        if header[4] != TRACE_VERSION {
+            return Err(invalid_data(format!("unsupported trace version {}", header[4])));
+        }
+        Ok(Self { inner, last_key: 0 })
+    }
+
+    /// Reads the next operation, or `None` at the end of the trace
+    pub fn read_op(&mut self) -> io::Result<Option<TraceOp>> {
+        let mut tag = [0u8; 1];
+        // A clean end of input is only allowed between operations
+        if self.inner.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+        let delta = zigzag_decode(self.read_varint()?);
+        let key = self.last_key.wrapping_add(delta as u64);
+        let op = TraceOp::from_tag(tag[0], key)?;
+        self.last_key = key;
+        Ok(Some(op))
+    }
+
+    fn read_varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let mut byte = [0u8; 1];
+            self.inner.read_exact(&mut byte)?;
+            let bits = (byte[0] & 0x7f) as u64;
+            // The tenth byte may only carry the top bit of a u64
+            if i == 9 && bits > 1 {
+                return Err(invalid_data("trace varint overflows u64".to_string()));
+            }
+            value |= bits << (7 * i);
+            if byte[0] & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data("trace varint longer than 10 bytes".to_string()))
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceOp>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_op().transpose()
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// LEB128-encodes `value` into `buf`, returning the number of bytes used
+fn encode_varint(mut value: u64, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            return len + 1;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_round_trip() {
+        let ops = vec![
+            TraceOp::Insert(100),
+            TraceOp::Get(101),
+            TraceOp::Get(99),
+            TraceOp::Remove(0),
+            TraceOp::Insert(u64::MAX),
+            TraceOp::Get(1),
+        ];
+        let mut writer = TraceWriter::new(Vec::new()).unwrap();
+        for &op in &ops {
+            writer.write_op(op).unwrap();
+        }
+        let bytes = writer.into_inner();
+        // Header plus small deltas stay tiny
+        assert_eq!(&bytes[..4], b"FGTR");
+        assert_eq!(bytes[5..9], [1, 0xc8, 0x01, 0]);
+
+        let read: Vec<TraceOp> = TraceReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read, ops);
+    }
+
+    #[test]
+    fn test_trace_malformed_input_is_an_error() {
+        // Bad magic, unknown version, truncated header
+        assert!(TraceReader::new(&b"NOPE\x01"[..]).is_err());
+        assert!(TraceReader::new(&b"FGTR\x09"[..]).is_err());
+        assert!(TraceReader::new(&b"FG"[..]).is_err());
+
+        // Unknown op tag
+        let mut reader = TraceReader::new(&b"FGTR\x01\x07\x02"[..]).unwrap();
+        assert_eq!(reader.read_op().unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Varint cut off mid-operation
+        let mut reader = TraceReader::new(&b"FGTR\x01\x00\x80"[..]).unwrap();
+        assert_eq!(reader.read_op().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        // Varint that never terminates
+        let mut bytes = b"FGTR\x01\x00".to_vec();
+        bytes.extend([0xff; 12]);
+        let mut reader = TraceReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.read_op().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::CachePolicy;
+use crate::clock::{Clock, SystemClock};
+
+/// Cache wrapper expiring entries a fixed time after their last insert
+///
+/// Expiry is lazy: an expired entry is dropped when it is next looked up,
+/// or by [`purge_expired`](Self::purge_expired). Until then it still counts
+/// towards `len()` and may be chosen as an eviction victim as usual.
+pub struct TtlCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    inner: C,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    /// Insert time of every key, possibly including some already evicted
+    inserted_at: HashMap<K, Instant>,
+    _phantom: PhantomData<V>,
+}
+
+impl<K, V, C> TtlCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    /// Wrap a policy, expiring entries `ttl` after insert in real time
+    pub fn new(inner: C, ttl: Duration) -> Self {
+        Self::with_clock(inner, ttl, Arc::new(SystemClock))
+    }
+
+    /// Wrap a policy, reading the time from `clock`
+    pub fn with_clock(inner: C, ttl: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            inner,
+            ttl,
+            clock,
+            inserted_at: HashMap::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Look up a key, dropping it instead if it has expired
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.is_expired(key) {
+            self.remove(key);
+            return None;
+        }
+        self.inner.get(key)
+    }
+
+    /// Insert or update a key-value pair, restarting its time to live
+    pub fn insert(&mut self, key: K, value: V) {
+        self.inserted_at.insert(key.clone(), self.clock.now());
+        self.inner.insert(key, value);
+        if self.inserted_at.len() > self.inner.capacity().saturating_mul(2) {
+            let inner = &self.inner;
+            self.inserted_at.retain(|k, _| inner.contains_key(k));
+        }
+    }
+
+    /// Remove a key, returning its value if present, expired or not
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inserted_at.remove(key);
+        self.inner.remove(key)
+    }
+
+    /// Drops every expired entry, returning how many were resident
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<K> = self
+            .inserted_at
+            .iter()
+            .filter(|(_, at)| now.duration_since(**at) >= self.ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired.iter().filter(|key| self.remove(key).is_some()).count()
+    }
+
+    /// Check whether a key is resident and not yet expired
+    ///
+    /// Unlike [`get`](Self::get), an expired entry is left in place.
+    pub fn contains_key(&self, key: &K) -> bool {
+        !self.is_expired(key) && self.inner.contains_key(key)
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.inserted_at.clear();
+        self.inner.clear();
+    }
+
+    /// Number of entries, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Time to live of every entry
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn is_expired(&self, key: &K) -> bool {
+        self.inserted_at
+            .get(key)
+            .is_some_and(|at| self.clock.now().duration_since(*at) >= self.ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_ttl_expires_exactly_at_deadline() {
+        let clock = Arc::new(MockClock::new());
+        let mut cache = TtlCache::with_clock(LruCache::new(8), Duration::from_secs(10), clock.clone());
+        cache.insert(1, "a");
+        clock.advance(Duration::from_secs(4));
+        cache.insert(2, "b");
+
+        clock.advance(Duration::from_millis(5_999));
+        assert_eq!(cache.get(&1), Some(&"a"));
+        // 10s after its insert, 1 is gone; 2 has 4s left
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some(&"b"));
+
+        // Re-inserting restarts the time to live
+        cache.insert(2, "b2");
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(cache.get(&2), Some(&"b2"));
+
+        cache.insert(3, "c");
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(cache.get(&3), Some(&"c"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_ttl_contains_key_hides_expired_without_dropping() {
+        let clock = Arc::new(MockClock::new());
+        let mut cache = TtlCache::with_clock(LruCache::new(4), Duration::from_secs(5), clock.clone());
+        cache.insert(1, 1);
+        assert!(cache.contains_key(&1));
+
+        clock.advance(Duration::from_secs(5));
+        assert!(!cache.contains_key(&1));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.len(), 0);
+
+        cache.insert(2, 2);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.purge_expired(), 0);
+    }
+
+    #[test]
+    fn test_ttl_over_unbounded_inner_cache() {
+        use crate::policies::UnboundedCache;
+
+        let clock = Arc::new(MockClock::new());
+        let mut cache = TtlCache::with_clock(UnboundedCache::new(), Duration::from_secs(5), clock.clone());
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        assert_eq!(cache.get(&1), Some(&1));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.len(), 1);
+    }
+}
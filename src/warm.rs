@@ -0,0 +1,66 @@
+//! Reproducible cache warm-up from a known popularity distribution
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::CachePolicy;
+
+/// Drives `policy` with `accesses` Zipf-distributed reads over `n_keys` keys
+///
+/// Key `i` is drawn with probability proportional to `1 / (i + 1)^skew`, so
+/// low keys are the popular ones; a skew of 0 is uniform. Each read is a
+/// read-through: a miss inserts the key with its decimal string as value.
+/// The same `seed` always replays the same access sequence.
+pub fn warm_from_zipf(
+    policy: &mut impl CachePolicy<i32, String>,
+    n_keys: usize,
+    skew: f64,
+    accesses: usize,
+    seed: u64,
+) {
+    if n_keys == 0 {
+        return;
+    }
+    let mut cumulative = Vec::with_capacity(n_keys);
+    let mut acc = 0.0;
+    for rank in 1..=n_keys {
+        acc += 1.0 / (rank as f64).powf(skew);
+        cumulative.push(acc);
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..accesses {
+        let u: f64 = rng.gen_range(0.0..acc);
+        let key = cumulative.partition_point(|&c| c < u).min(n_keys - 1) as i32;
+        if policy.get(&key).is_none() {
+            policy.insert(key, key.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{LfuCache, LruCache};
+
+    #[test]
+    fn test_warm_from_zipf_keeps_popular_keys() {
+        let mut lru = LruCache::new(100);
+        warm_from_zipf(&mut lru, 1000, 1.0, 20_000, 3);
+        let mut lfu = LfuCache::new(100);
+        warm_from_zipf(&mut lfu, 1000, 1.0, 20_000, 3);
+
+        assert_eq!(lru.len(), 100);
+        assert_eq!(lfu.len(), 100);
+        for key in 0..5 {
+            assert!(lru.contains_key(&key), "LRU lost popular key {}", key);
+            assert!(lfu.contains_key(&key), "LFU lost popular key {}", key);
+        }
+        assert_eq!(lru.get(&0), Some(&"0".to_string()));
+
+        // The same seed replays the same accesses
+        let mut again = LruCache::new(100);
+        warm_from_zipf(&mut again, 1000, 1.0, 20_000, 3);
+        assert!((0..1000).all(|key| again.contains_key(&key) == lru.contains_key(&key)));
+    }
+}
@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
+
+use crate::CachePolicy;
+
+/// Cache wrapper holding `Weak<V>` so cached values stay owned elsewhere
+///
+/// The cache never keeps a value alive: once the last `Arc` is dropped the
+/// entry goes stale, and the next `get` on it removes it and reports a miss.
+/// Fits secondary indexes into a store that owns the values.
+pub struct WeakCache<K, V, C>
+where
+    C: CachePolicy<K, Weak<V>>,
+{
+    inner: C,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> WeakCache<K, V, C>
+where
+    C: CachePolicy<K, Weak<V>>,
+{
+    /// Wrap a policy storing `Weak<V>` values
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Retrieve a strong handle if the value is still alive
+    ///
+    /// A stale entry is removed and treated as a miss.
+    pub fn get(&mut self, key: &K) -> Option<Arc<V>> {
+        let value = self.inner.get(key)?.upgrade();
+        if value.is_none() {
+            self.inner.remove(key);
+        }
+        value
+    }
+
+    /// Insert a weak reference to `value`
+    pub fn insert(&mut self, key: K, value: &Arc<V>) {
+        self.inner.insert(key, Arc::downgrade(value));
+    }
+
+    /// Remove a key, returning the value if it was still alive
+    pub fn remove(&mut self, key: &K) -> Option<Arc<V>> {
+        self.inner.remove(key).and_then(|weak| weak.upgrade())
+    }
+
+    /// Drop every stale entry, returning how many were removed
+    pub fn purge_dead(&mut self) -> usize
+    where
+        K: Clone,
+    {
+        let mut dead = Vec::new();
+        self.inner.for_each_value_mut(&mut |key, weak| {
+            if weak.strong_count() == 0 {
+                dead.push(key.clone());
+            }
+        });
+        for key in &dead {
+            self.inner.remove(key);
+        }
+        dead.len()
+    }
+
+    /// Number of entries, including stale ones not yet purged
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::LruCache;
+
+    #[test]
+    fn test_weak_value_dropped_is_a_miss() {
+        let mut cache = WeakCache::new(LruCache::new(4));
+        let owned = Arc::new(String::from("owned elsewhere"));
+        cache.insert(1, &owned);
+        assert!(Arc::ptr_eq(&cache.get(&1).unwrap(), &owned));
+        assert_eq!(Arc::strong_count(&owned), 1);
+
+        drop(owned);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&1).is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_weak_value_purge_dead() {
+        let mut cache = WeakCache::new(LruCache::new(4));
+        let alive = Arc::new(1);
+        let dead = Arc::new(2);
+        cache.insert("alive", &alive);
+        cache.insert("dead", &dead);
+        drop(dead);
+
+        assert_eq!(cache.purge_dead(), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"alive").as_deref(), Some(&1));
+    }
+}
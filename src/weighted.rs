@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{CachePolicy, Weigher};
+
+/// Byte allowance shared by several [`WeightedCache`]s
+///
+/// Clones share the same remaining count, so handing a clone to each cache
+/// caps their combined footprint without any central coordinator.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    remaining: Arc<AtomicUsize>,
+    limit: usize,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `bytes`, all of it available
+    pub fn new(bytes: usize) -> Self {
+        Self {
+            remaining: Arc::new(AtomicUsize::new(bytes)),
+            limit: bytes,
+        }
+    }
+
+    /// Total size of the budget
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes not reserved by anyone
+    pub fn remaining(&self) -> usize {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    /// Bytes currently reserved across all holders
+    pub fn used(&self) -> usize {
+        self.limit - self.remaining()
+    }
+
+    /// Reserves `bytes` if that many remain, leaving the budget untouched otherwise
+    pub fn try_reserve(&self, bytes: usize) -> bool {
+        self.remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |left| left.checked_sub(bytes))
+            .is_ok()
+    }
+
+    /// Gives back bytes obtained from [`try_reserve`](Self::try_reserve)
+    pub fn release(&self, bytes: usize) {
+        self.remaining.fetch_add(bytes, Ordering::AcqRel);
+    }
+}
+
+/// Cache wrapper charging every entry's weight against a shared [`MemoryBudget`]
+///
+/// An insert reserves the entry's weight; removals and evictions, including
+/// the ones the wrapped policy makes on its own, give it back. When the
+/// budget is exhausted the cache evicts its own coldest entries, in the
+/// policy's eviction order, until the new entry fits. An entry weighing
+/// the whole budget is admitted once everything else is gone. An entry
+/// heavier than the budget is rejected up front, leaving every entry in
+/// place. Otherwise an entry is rejected if it still doesn't fit once this
+/// cache is empty; bytes held by the other caches are never reclaimed.
+pub struct WeightedCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    inner: C,
+    budget: MemoryBudget,
+    weigher: Weigher<K, V>,
+    /// Reserved weight of every resident key
+    weights: HashMap<K, usize>,
+    used: usize,
+    _phantom: PhantomData<V>,
+}
+
+impl<K, V, C> WeightedCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    /// Wrap an empty policy, charging `weigher(key, value)` bytes per entry
+    ///
+    /// # Panics
+    /// Panics if `inner` isn't empty
+    pub fn new(inner: C, budget: MemoryBudget, weigher: Weigher<K, V>) -> Self {
+        assert!(inner.is_empty(), "WeightedCache must wrap an empty policy");
+        Self {
+            inner,
+            budget,
+            weigher,
+            weights: HashMap::new(),
+            used: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Wrap an empty policy with a budget of its own, capping it at `max_weight`
+    ///
+    /// # Panics
+    /// Panics if `inner` isn't empty
+    pub fn with_max_weight(inner: C, max_weight: usize, weigher: Weigher<K, V>) -> Self {
+        Self::new(inner, MemoryBudget::new(max_weight), weigher)
+    }
+
+    /// Look up a key
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.inner.get(key)
+    }
+
+    /// Insert or update a key-value pair, returning whether it was admitted
+    ///
+    /// An entry heavier than the whole budget changes nothing, not even an
+    /// older value of the key. Otherwise, on update the old weight is given
+    /// back before the new one is reserved, and an update rejected for lack
+    /// of room leaves the key absent.
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let weight = (self.weigher)(&key, &value);
+        if weight > self.budget.limit() {
+            return false;
+        }
+        if let Some(old) = self.weights.remove(&key) {
+            self.give_back(old);
+        }
+        if !self.reserve(weight) {
+            self.inner.remove(&key);
+            return false;
+        }
+        self.inner.insert(key.clone(), value);
+        self.weights.insert(key, weight);
+        self.used += weight;
+        if self.inner.len() != self.weights.len() {
+            self.release_evicted();
+        }
+        true
+    }
+
+    /// Remove a key, returning its value if present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if let Some(weight) = self.weights.remove(key) {
+            self.give_back(weight);
+        }
+        self.inner.remove(key)
+    }
+
+    /// Remove every entry, giving all of this cache's bytes back
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.weights.clear();
+        self.give_back(self.used);
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Total weight of the entries held, which is what this cache has
+    /// reserved from the budget
+    pub fn current_weight(&self) -> usize {
+        self.used
+    }
+
+    /// The shared budget
+    pub fn budget(&self) -> &MemoryBudget {
+        &self.budget
+    }
+
+    /// Access the wrapped policy
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Reserves `weight`, evicting this cache's coldest entries while the
+    /// budget is short
+    fn reserve(&mut self, weight: usize) -> bool {
+        while !self.budget.try_reserve(weight) {
+            if !self.evict_coldest() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Evicts the policy's next victim, returning false if nothing was evicted
+    ///
+    /// A key being reinserted may be the victim; its weight was already
+    /// given back, so it has none left to refund.
+    fn evict_coldest(&mut self) -> bool {
+        let Some((key, _)) = self.inner.evict_one() else {
+            return false;
+        };
+        if let Some(weight) = self.weights.remove(&key) {
+            self.give_back(weight);
+        }
+        true
+    }
+
+    /// Gives back the weight of every key the policy no longer holds
+    fn release_evicted(&mut self) {
+        let inner = &self.inner;
+        let mut freed = 0;
+        self.weights.retain(|key, weight| {
+            let resident = inner.contains_key(key);
+            if !resident {
+                freed += *weight;
+            }
+            resident
+        });
+        self.give_back(freed);
+    }
+
+    fn give_back(&mut self, bytes: usize) {
+        self.used -= bytes;
+        self.budget.release(bytes);
+    }
+}
+
+impl<K, V, C> Drop for WeightedCache<K, V, C>
+where
+    K: Hash + Eq + Clone,
+    C: CachePolicy<K, V>,
+{
+    fn drop(&mut self) {
+        self.budget.release(self.used);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::{ArcCache, FifoCache, LruCache, UnboundedCache};
+
+    fn weigh_len() -> Weigher<u32, Vec<u8>> {
+        Box::new(|_, value: &Vec<u8>| value.len())
+    }
+
+    #[test]
+    fn test_weighted_shared_budget_never_exceeded() {
+        let budget = MemoryBudget::new(200);
+        let mut caches = [
+            WeightedCache::new(LruCache::new(40), budget.clone(), weigh_len()),
+            WeightedCache::new(LruCache::new(10), budget.clone(), weigh_len()),
+        ];
+        let mut fifo = WeightedCache::new(FifoCache::new(30), budget.clone(), weigh_len());
+
+        let mut seed: u32 = 7;
+        for step in 0..3_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let key = (seed >> 8) % 64;
+            let value = vec![0u8; ((seed >> 4) % 30) as usize + 1];
+            match step % 7 {
+                0 => {
+                    fifo.insert(key, value);
+                }
+                1 => {
+                    fifo.remove(&key);
+                }
+                2 => {
+                    caches[1].get(&key);
+                }
+                n => {
+                    caches[n % 2].insert(key, value);
+                }
+            }
+            let footprint: usize = caches.iter().map(|c| c.current_weight()).sum::<usize>() + fifo.current_weight();
+            assert!(footprint <= 200, "step {}: combined footprint {}", step, footprint);
+            assert_eq!(budget.used(), footprint);
+        }
+
+        // Dropping a cache returns its share
+        let fifo_share = fifo.current_weight();
+        let before = budget.remaining();
+        drop(fifo);
+        assert_eq!(budget.remaining(), before + fifo_share);
+    }
+
+    #[test]
+    fn test_weighted_evicts_own_coldest_when_exhausted() {
+        let budget = MemoryBudget::new(30);
+        let mut a = WeightedCache::new(LruCache::new(10), budget.clone(), weigh_len());
+        let mut b = WeightedCache::new(LruCache::new(10), budget.clone(), weigh_len());
+
+        assert!(a.insert(1, vec![0; 10]));
+        assert!(a.insert(2, vec![0; 10]));
+        assert!(a.insert(3, vec![0; 10]));
+        a.get(&1);
+        // The budget is full: a frees its least recently used entry, 2
+        assert!(a.insert(4, vec![0; 10]));
+        assert!(!a.inner().contains_key(&2));
+        assert_eq!(a.current_weight(), 30);
+
+        // b holds nothing to evict and can't take a's bytes
+        assert!(!b.insert(1, vec![0; 5]));
+        assert!(b.is_empty());
+
+        // An update frees the old weight first
+        assert!(a.insert(1, vec![0; 4]));
+        assert!(b.insert(1, vec![0; 5]));
+        assert_eq!(a.current_weight() + b.current_weight(), 29);
+
+        // Larger than the whole budget is never admitted
+        assert!(!a.insert(9, vec![0; 31]));
+        a.clear();
+        assert_eq!(budget.used(), 5);
+    }
+
+    #[test]
+    fn test_weighted_boundary_at_budget_size() {
+        let budget = MemoryBudget::new(10);
+        let mut cache = WeightedCache::new(LruCache::new(8), budget.clone(), weigh_len());
+        cache.insert(1, vec![0; 3]);
+        cache.insert(2, vec![0; 3]);
+
+        // One under the budget: only as much as needed is evicted
+        assert!(cache.insert(3, vec![0; 9]));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.current_weight(), 9);
+
+        // Exactly the budget: admitted, everything else evicted
+        cache.insert(4, vec![0; 1]);
+        assert!(cache.insert(5, vec![0; 10]));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.inner().contains_key(&5));
+        assert_eq!(budget.remaining(), 0);
+
+        // One over: rejected, nothing evicted, not even the key's own value
+        assert!(!cache.insert(6, vec![0; 11]));
+        assert!(!cache.insert(5, vec![0; 11]));
+        assert_eq!(cache.get(&5).map(Vec::len), Some(10));
+        assert_eq!(cache.current_weight(), 10);
+    }
+
+    #[test]
+    fn test_weighted_max_weight_tracks_update_delta() {
+        let mut cache = WeightedCache::with_max_weight(LruCache::new(8), 20, weigh_len());
+        assert_eq!(cache.budget().limit(), 20);
+        cache.insert(1, vec![0; 5]);
+        cache.insert(2, vec![0; 5]);
+
+        // Growing a key charges the difference, not the new weight on top
+        assert!(cache.insert(1, vec![0; 12]));
+        assert_eq!(cache.current_weight(), 17);
+        assert_eq!(cache.len(), 2);
+
+        // Growing past the budget evicts the coldest other key
+        assert!(cache.insert(1, vec![0; 16]));
+        assert_eq!(cache.current_weight(), 16);
+        assert!(!cache.inner().contains_key(&2));
+
+        // Heavier than the budget: rejected, the current value survives
+        assert!(!cache.insert(1, vec![0; 21]));
+        assert_eq!(cache.get(&1).map(Vec::len), Some(16));
+        assert_eq!(cache.current_weight(), cache.budget().used());
+    }
+
+    #[test]
+    fn test_weighted_evicts_one_victim_without_resizing() {
+        // An unbounded policy can't be resized, but still gives up its oldest
+        let mut unbounded = WeightedCache::with_max_weight(UnboundedCache::new(), 20, weigh_len());
+        unbounded.insert(1, vec![0; 10]);
+        unbounded.insert(2, vec![0; 10]);
+        assert!(unbounded.insert(3, vec![0; 10]));
+        assert!(!unbounded.inner().contains_key(&1));
+        assert_eq!(unbounded.current_weight(), 20);
+
+        // ARC keeps its capacity and remembers the victim as a ghost
+        let mut arc = WeightedCache::with_max_weight(ArcCache::new(4), 20, weigh_len());
+        arc.insert(1, vec![0; 10]);
+        arc.insert(2, vec![0; 10]);
+        arc.get(&2);
+        assert!(arc.insert(3, vec![0; 10]));
+        assert_eq!(arc.capacity(), 4);
+        assert!(arc.inner().debug_lists().b1.contains(&1));
+        assert!(arc.inner().check_invariants().is_ok());
+        assert_eq!(arc.current_weight(), 20);
+    }
+}
@@ -0,0 +1,69 @@
+//! Smoke test for the shared bench helper in benches/common.rs
+
+#[path = "../benches/common.rs"]
+mod common;
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use criterion::Criterion;
+use fulgurance::policies::{ClockCache, LruCache};
+use fulgurance::prefetch::PrefetchType;
+
+#[test]
+fn test_bench_policy_runs_for_two_policies() {
+    let mut criterion = Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(1))
+        .measurement_time(Duration::from_millis(10))
+        .without_plots();
+    let traces = Cell::new(0);
+    let lru_runs = Cell::new(0);
+    let clock_runs = Cell::new(0);
+
+    let mut group = criterion.benchmark_group("bench_policy smoke LRU");
+    common::bench_policy(
+        &mut group,
+        &[(4, 16)],
+        &[PrefetchType::None],
+        |capacity, _| {
+            lru_runs.set(lru_runs.get() + 1);
+            LruCache::<u64, u64>::new(capacity)
+        },
+        |_, n| {
+            traces.set(traces.get() + 1);
+            (0..n as u64).collect()
+        },
+        |key| *key,
+    );
+    group.finish();
+
+    let mut group = criterion.benchmark_group("bench_policy smoke Clock");
+    common::bench_policy(
+        &mut group,
+        &[(4, 16), (8, 32)],
+        &[PrefetchType::None, PrefetchType::Sequential],
+        |capacity, prefetch| {
+            clock_runs.set(clock_runs.get() + 1);
+            match prefetch {
+                PrefetchType::None => ClockCache::<i32, String>::new(capacity),
+                _ => ClockCache::with_prefetch_i32(capacity, prefetch),
+            }
+        },
+        |_, n| {
+            traces.set(traces.get() + 1);
+            (0..n as i32).map(|i| i % 6).collect()
+        },
+        |key| key.to_string(),
+    );
+    group.finish();
+
+    // One trace per config, and every benchmark actually replayed it
+    assert_eq!(traces.get(), 3);
+    assert!(lru_runs.get() > 0);
+    assert!(clock_runs.get() >= 4);
+
+    // Replaying directly gives the expected residency
+    let mut cache = LruCache::new(4);
+    assert_eq!(common::replay(&mut cache, &[1u64, 2, 3, 4, 5, 1], &|key| *key), 4);
+}